@@ -0,0 +1,184 @@
+//! Passes the networks and the faucets configured in the files given by `NETWORKS_FILE` and
+//! `FAUCETS_FILE` to the crate as the `NETWORKS` and `FAUCETS` environment variables, unless they
+//! are set already. See `src/utils/network.rs` and `src/faucet/registry.rs`.
+//!
+//! The configured networks and faucets are validated here, so that an invalid configuration fails
+//! the build instead of being noticed once deployed.
+
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use url::Url;
+
+/// Mirror of `NetworkConfig` in `src/utils/network.rs`.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct NetworkConfig {
+    name: String,
+    prefix: String,
+    chain_id: u64,
+    #[serde(default)]
+    network_name: Option<String>,
+    rpc_endpoints: Vec<RpcEndpoint>,
+    #[serde(default)]
+    explorer_url: Option<Url>,
+}
+
+/// Mirror of `RpcEndpoint` in `src/utils/network.rs`.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct RpcEndpoint {
+    label: String,
+    url: Url,
+}
+
+/// Mirror of `FaucetConfig` in `src/faucet/registry.rs`.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct FaucetConfig {
+    id: String,
+    slug: String,
+    network: String,
+    token: TokenType,
+    #[serde(default)]
+    unit: Option<String>,
+    drip_amount: u64,
+    wallet_cap_multiplier: i64,
+    ip_cap_multiplier: i64,
+    drip_cap_multiplier: i64,
+    cooldown_seconds: i64,
+    #[serde(default)]
+    max_drips_per_minute: u32,
+    #[serde(default)]
+    pow_difficulty: u8,
+    #[serde(default)]
+    human_verification: bool,
+    secret_name: String,
+    #[serde(default)]
+    claim_api: bool,
+    #[serde(default)]
+    claim_all: bool,
+    max_gas_limit: u64,
+    max_gas_fee_cap: u64,
+    max_gas_premium: u64,
+    icon: String,
+    title: String,
+    page_title: String,
+    list_label: String,
+    description: String,
+    #[serde(default)]
+    about_html: Option<String>,
+}
+
+/// Mirror of `TokenType` in `src/utils/drip_amount.rs`.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TokenType {
+    Native,
+    Erc20(String),
+    Datacap,
+}
+
+/// Checks the networks configuration as `parse_networks` in `src/utils/network.rs` does. Returns
+/// the names of the networks.
+fn validate_networks(config: &str) -> Result<Vec<String>, String> {
+    let networks = serde_json::from_str::<Vec<NetworkConfig>>(config).map_err(|e| e.to_string())?;
+    for network in &networks {
+        if network.name.is_empty() {
+            return Err("Network without a name".to_string());
+        }
+        if !matches!(network.prefix.as_str(), "f" | "t") {
+            return Err(format!(
+                "Network `{}` has an invalid address prefix `{}`, expected `f` or `t`",
+                network.name, network.prefix
+            ));
+        }
+        if network.rpc_endpoints.is_empty() {
+            return Err(format!("Network `{}` has no RPC endpoint", network.name));
+        }
+    }
+    Ok(networks.into_iter().map(|network| network.name).collect())
+}
+
+/// Checks the faucets configuration as `parse_faucets` in `src/faucet/registry.rs` does, given the
+/// names of the known networks.
+fn validate_faucets(config: &str, networks: &[String]) -> Result<(), String> {
+    let faucets = serde_json::from_str::<Vec<FaucetConfig>>(config).map_err(|e| e.to_string())?;
+    let mut ids = HashSet::new();
+    let mut slugs = HashSet::new();
+    for faucet in &faucets {
+        if !ids.insert(&faucet.id) {
+            return Err(format!("Duplicate faucet `{}`", faucet.id));
+        }
+        if !slugs.insert(&faucet.slug) {
+            return Err(format!("Duplicate faucet slug `{}`", faucet.slug));
+        }
+        if faucet.claim_all && !faucet.claim_api {
+            return Err(format!(
+                "Faucet `{}` is claimed from by `claim_token_all` but not open to the claim API",
+                faucet.id
+            ));
+        }
+        match &faucet.token {
+            TokenType::Erc20(contract) => {
+                let valid = contract.strip_prefix("0x").is_some_and(|hex| {
+                    hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit())
+                });
+                if !valid {
+                    return Err(format!(
+                        "Faucet `{}` has an invalid contract address `{contract}`",
+                        faucet.id
+                    ));
+                }
+            }
+            TokenType::Native | TokenType::Datacap if faucet.unit.is_none() => {
+                return Err(format!("Faucet `{}` has no unit", faucet.id));
+            }
+            TokenType::Native | TokenType::Datacap => {}
+        }
+        if !networks.contains(&faucet.network) {
+            return Err(format!(
+                "Unknown network `{}` of faucet `{}`",
+                faucet.network, faucet.id
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the configuration of the `{var}` environment variable, or else the content of the file
+/// given by `{var}_FILE`, forwarding the latter as `{var}`.
+fn forward_file(var: &str) -> Option<String> {
+    let file_var = format!("{var}_FILE");
+    println!("cargo:rerun-if-env-changed={var}");
+    println!("cargo:rerun-if-env-changed={file_var}");
+    if let Ok(config) = env::var(var) {
+        return Some(config);
+    }
+    let path = env::var_os(&file_var)?;
+    println!("cargo:rerun-if-changed={}", path.to_string_lossy());
+    let config = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {e}", path.to_string_lossy()));
+    // Line breaks are not allowed in the value, nor in the strings of a JSON document.
+    println!(
+        "cargo:rustc-env={var}={}",
+        config.replace(['\r', '\n'], " ")
+    );
+    Some(config)
+}
+
+fn main() {
+    let mut networks = vec!["calibnet".to_string(), "mainnet".to_string()];
+    if let Some(config) = forward_file("NETWORKS") {
+        match validate_networks(&config) {
+            Ok(configured) => networks.extend(configured),
+            Err(e) => panic!("Invalid network configuration: {e}"),
+        }
+    }
+    if let Some(config) = forward_file("FAUCETS")
+        && let Err(e) = validate_faucets(&config, &networks)
+    {
+        panic!("Invalid faucet configuration: {e}");
+    }
+}
@@ -5,7 +5,7 @@
 export const PAGES = [
   {
     path: "",
-    buttons: ["Faucet List"],
+    buttons: ["Faucet List", "Search"],
     links: ["Filecoin Slack", "documentation"],
   },
   {
@@ -41,6 +41,9 @@ export const PAGES = [
 // clickable: Button should be interactive and should not throw any error when clicked.
 // expectError: Button should display an error message when clicked.
 export const BUTTON_ACTIONS = {
+  "": {
+    Search: { type: "expectError", errorMsg: "Enter an address" },
+  },
   "/faucet/calibnet_usdfc": {
     "Faucet List": { type: "navigate" },
     "Transaction History": { type: "clickable" },
@@ -18,10 +18,30 @@ export const options = {
 };
 
 function validateTransactionHash(txHash) {
-  // Remove outer quotes if present
-  txHash = txHash.replace(/^"|"$/g, '');
   // Both CalibnetFIL and CalibnetUSDFC now return an Ethereum format: 0x + 64 hex chars = 66 total
-  return txHash.startsWith('0x') && txHash.length === 66;
+  return typeof txHash === 'string' && txHash.startsWith('0x') && txHash.length === 66;
+}
+
+function parseClaimId(body) {
+  // The claim ID is returned as a JSON string
+  const claimId = body.replace(/^"|"$/g, '');
+  return /^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$/.test(claimId) ? claimId : null;
+}
+
+// Polls the status of a queued claim until its transaction hash is known, or it failed.
+function waitForClaim(faucetInfo, claimId) {
+  const url = `${API_CONFIG.BASE_URL}${API_CONFIG.STATUS_ENDPOINT}` +
+    `?faucet_info=${encodeURIComponent(faucetInfo)}&claim_id=${encodeURIComponent(claimId)}`;
+  let status = null;
+  for (let attempt = 1; attempt <= API_CONFIG.CLAIM_STATUS_ATTEMPTS; attempt++) {
+    const response = http.get(url, { timeout: API_CONFIG.REQUEST_TIMEOUT });
+    status = response.status === STATUS_CODES.SUCCESS ? JSON.parse(response.body) : null;
+    if (status && (status.status === 'failed' || status.tx_hash)) {
+      break;
+    }
+    sleep(API_CONFIG.CLAIM_STATUS_INTERVAL_SECONDS);
+  }
+  return status;
 }
 
 function runTestScenarios(scenarios, options = {}) {
@@ -47,8 +67,17 @@ function runTestScenarios(scenarios, options = {}) {
 
     if (statusCheckResult) {
       const additionalValidations = {
-        [`${testCase.name}: Valid transaction hash (if success)`]: (r) =>
-          r.status !== STATUS_CODES.SUCCESS || validateTransactionHash(r.body.trim())
+        [`${testCase.name}: Valid transaction hash (if success)`]: (r) => {
+          if (r.status !== STATUS_CODES.SUCCESS) {
+            return true;
+          }
+          const claimId = parseClaimId(r.body.trim());
+          if (!claimId) {
+            return false;
+          }
+          const status = waitForClaim(testCase.faucet_info, claimId);
+          return status !== null && status.status === 'pushed' && validateTransactionHash(status.tx_hash);
+        }
       };
 
       // Add any test-specific additional checks
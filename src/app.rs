@@ -1,7 +1,8 @@
-use crate::faucet::views::faucets::{
-    Faucets, calibnet::Faucet_Calibnet, calibnet_datacap::Faucet_Calibnet_Datacap,
-    calibnet_usdfc::Faucet_Calibnet_USDFC, mainnet::Faucet_Mainnet,
-};
+use crate::explorer::views::address::AddressPage;
+use crate::explorer::views::block::BlockPage;
+use crate::explorer::views::message::MessagePage;
+use crate::explorer::views::tipset::TipsetPage;
+use crate::faucet::views::faucets::{FaucetPage, Faucets};
 use crate::faucet::views::{components::layout::Footer, home::Explorer};
 use crate::utils::rpc_context::RpcContext;
 use leptos::prelude::*;
@@ -23,10 +24,11 @@ pub fn App() -> impl IntoView {
                 <Routes fallback=|| "Not found.">
                     <Route path=path!("/") view=Explorer />
                     <Route path=path!("/faucet") view=Faucets />
-                    <Route path=path!("/faucet/calibnet") view=Faucet_Calibnet />
-                    <Route path=path!("/faucet/mainnet") view=Faucet_Mainnet />
-                    <Route path=path!("/faucet/calibnet_usdfc") view=Faucet_Calibnet_USDFC />
-                    <Route path=path!("/faucet/calibnet_datacap") view=Faucet_Calibnet_Datacap />
+                    <Route path=path!("/faucet/:slug") view=FaucetPage />
+                    <Route path=path!("/tipset/:height") view=TipsetPage />
+                    <Route path=path!("/block/:cid") view=BlockPage />
+                    <Route path=path!("/message/:cid") view=MessagePage />
+                    <Route path=path!("/address/:address") view=AddressPage />
                 </Routes>
                 <Footer />
             </div>
@@ -0,0 +1,134 @@
+//! Search of the explorer, resolving what users type in the search bar to an explorer page.
+
+use alloy::primitives::TxHash;
+use anyhow::Context as _;
+use cid::Cid;
+use fvm_shared::address::{Address, Network};
+
+use crate::utils::address::parse_address;
+use crate::utils::rpc_context::{Provider, RpcBatch, RpcCall};
+
+// '0x' + 32 bytes
+const ETH_TX_HASH_LENGTH: usize = 66;
+
+/// What a search refers to, see [`classify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchQuery {
+    /// A chain epoch.
+    Height(i64),
+    /// An Ethereum transaction hash, resolved to the CID of its message.
+    EthTxHash(TxHash),
+    /// A Filecoin address of the network or an Ethereum address.
+    Address(Address),
+    /// The CID of a message or of a block.
+    Cid(Cid),
+}
+
+/// Classifies a search of the explorer, `None` if it is not a decimal epoch, an Ethereum
+/// transaction hash, an address of the network or a CID.
+pub fn classify(input: &str, network: Network) -> Option<SearchQuery> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    if input.chars().all(|c| c.is_ascii_digit()) {
+        return input.parse().ok().map(SearchQuery::Height);
+    }
+    if input.len() == ETH_TX_HASH_LENGTH && input.starts_with("0x") {
+        return input.parse().ok().map(SearchQuery::EthTxHash);
+    }
+    if let Ok(address) = parse_address(input, network) {
+        return Some(SearchQuery::Address(address));
+    }
+    input.parse().ok().map(SearchQuery::Cid)
+}
+
+/// Resolves a search to the path of its explorer page. Transaction hashes are resolved to their
+/// message and CIDs are looked up as a message and as a block in one JSON-RPC batch.
+pub async fn resolve(provider: &Provider, query: SearchQuery) -> anyhow::Result<String> {
+    match query {
+        SearchQuery::Height(height) => Ok(format!("/tipset/{height}")),
+        SearchQuery::Address(address) => Ok(format!("/address/{address}")),
+        SearchQuery::EthTxHash(tx_hash) => {
+            let cid = provider
+                .eth_get_message_cid_by_transaction_hash(tx_hash)
+                .await?
+                .with_context(|| format!("No transaction found with hash {tx_hash}"))?;
+            Ok(format!("/message/{cid}"))
+        }
+        SearchQuery::Cid(cid) => {
+            let mut batch = RpcBatch::default();
+            let message = batch.add(RpcCall::chain_get_message(cid)?);
+            let block = batch.add(RpcCall::chain_get_block(cid)?);
+            let mut results = provider.batch(&batch).await?;
+            if results.take(message).is_ok() {
+                Ok(format!("/message/{cid}"))
+            } else if results.take(block).is_ok() {
+                Ok(format!("/block/{cid}"))
+            } else {
+                anyhow::bail!("No message or block found with CID {cid}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let cid = "bafy2bzaceawvht75twtb7jbw262yi5am5oiu5jpzfxndkiyormggdyljxrr6e";
+        let tx_hash = "0x3b0f8c4a8f2c8e1fbc48d2ab3b7b41ba4b0a2a6cf8a8b2e0d6b4f1c7a9d0e2f1";
+        let eth_address = "0x5c6c2a1dcb9bdd0d01e9a7f1d4bcdc4ac43cd8b2";
+        let cases = [
+            ("42", Network::Testnet, Some(SearchQuery::Height(42))),
+            (" 0 ", Network::Mainnet, Some(SearchQuery::Height(0))),
+            (
+                tx_hash,
+                Network::Testnet,
+                Some(SearchQuery::EthTxHash(tx_hash.parse().unwrap())),
+            ),
+            (
+                "t01234",
+                Network::Testnet,
+                Some(SearchQuery::Address(Address::new_id(1234))),
+            ),
+            (
+                "f01234",
+                Network::Mainnet,
+                Some(SearchQuery::Address(Address::new_id(1234))),
+            ),
+            (
+                eth_address,
+                Network::Mainnet,
+                Some(SearchQuery::Address(
+                    parse_address(eth_address, Network::Mainnet).unwrap(),
+                )),
+            ),
+            (
+                cid,
+                Network::Testnet,
+                Some(SearchQuery::Cid(cid.parse().unwrap())),
+            ),
+            // Addresses of another network.
+            ("f01234", Network::Testnet, None),
+            // Epochs are not negative.
+            ("-1", Network::Testnet, None),
+            // Too large for an epoch.
+            ("99999999999999999999", Network::Testnet, None),
+            // Neither a transaction hash nor an address.
+            ("0x3b0f8c4a", Network::Testnet, None),
+            (
+                "0xzz0f8c4a8f2c8e1fbc48d2ab3b7b41ba4b0a2a6cf8a8b2e0d6b4f1c7a9d0e2f1",
+                Network::Testnet,
+                None,
+            ),
+            ("", Network::Testnet, None),
+            ("hello", Network::Testnet, None),
+        ];
+        for (input, network, expected) in cases {
+            assert_eq!(classify(input, network), expected, "{input}");
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use fvm_shared::address::Address;
+use leptos::prelude::*;
+use leptos::{IntoView, component, view};
+use leptos_meta::Title;
+use leptos_router::hooks::use_params_map;
+
+use crate::explorer::views::components::{DetailRow, ExplorerPage, Loaded};
+use crate::faucet::constants::FaucetInfo;
+use crate::utils::actor::ActorInfo;
+use crate::utils::address::parse_address;
+use crate::utils::drip_amount::{DripAmount, NATIVE_DECIMALS, TokenType};
+use crate::utils::error::error_message;
+use crate::utils::format::format_balance;
+use crate::utils::rpc_context::RpcContext;
+
+/// Balances of the address in the ERC-20 tokens dripped by the faucets of the selected network.
+#[component]
+fn Erc20Balances(address: Address) -> impl IntoView {
+    let rpc_context = RpcContext::use_context();
+    let balances = LocalResource::new(move || {
+        let network = rpc_context.network().get();
+        let provider = rpc_context.get();
+        async move {
+            let mut balances = Vec::new();
+            for faucet in FaucetInfo::all().filter(|faucet| faucet.network() == network) {
+                let token_type = faucet.token_type();
+                let TokenType::Erc20(contract_address) = token_type else {
+                    continue;
+                };
+                let balance = async {
+                    let metadata = provider.erc20_metadata(contract_address).await?;
+                    let balance = provider.wallet_balance(address, &token_type).await?;
+                    anyhow::Ok(format_balance(
+                        &balance,
+                        &metadata.symbol,
+                        metadata.decimals,
+                    ))
+                };
+                balances.push(balance.await.unwrap_or_else(|e| error_message(&e)));
+            }
+            balances
+        }
+    });
+    // Rendered once loaded, as the balances are secondary to the details of the actor.
+    move || {
+        balances.get().map(|balances| {
+            balances
+                .into_iter()
+                .map(|balance| view! { <DetailRow label="Token balance">{balance}</DetailRow> })
+                .collect::<Vec<_>>()
+        })
+    }
+}
+
+#[component]
+fn AddressDetails(address: Address, info: ActorInfo) -> impl IntoView {
+    let rpc_context = RpcContext::use_context();
+    let unit = rpc_context.network().get_untracked().native_unit();
+    let or_none = |form: Option<String>| form.unwrap_or_else(|| "(none)".to_string());
+    let eth_address = or_none(info.eth_address().map(|addr| addr.to_string()));
+    let masked_id_address = or_none(info.masked_id_address().map(|addr| addr.to_string()));
+    let fevm_address = info.fevm_address();
+    view! {
+        <dl class="detail-list">
+            <DetailRow label="Address">{address.to_string()}</DetailRow>
+            <DetailRow label="ID address">{or_none(info.id.map(|id| id.to_string()))}</DetailRow>
+            <DetailRow label="Robust address">
+                {or_none(info.robust.map(|robust| robust.to_string()))}
+            </DetailRow>
+            <DetailRow label="Ethereum address">{eth_address}</DetailRow>
+            <DetailRow label="Masked ID address">{masked_id_address}</DetailRow>
+            {match info.state {
+                Some(state) => {
+                    let actor_type = info
+                        .actor_type
+                        .map(|actor_type| actor_type.to_string())
+                        .unwrap_or_else(|| "Unknown".to_string());
+                    view! {
+                        <DetailRow label="Actor type">{actor_type}</DetailRow>
+                        <DetailRow label="Code CID">{state.code.to_string()}</DetailRow>
+                        <DetailRow label="Balance">
+                            {format_balance(&DripAmount::Token(state.balance), unit, NATIVE_DECIMALS)}
+                        </DetailRow>
+                        <DetailRow label="Nonce">{state.nonce}</DetailRow>
+                    }
+                        .into_any()
+                }
+                None => view! { <DetailRow label="Actor">"Not created on chain yet"</DetailRow> }.into_any(),
+            }}
+            {fevm_address.map(|address| view! { <Erc20Balances address=address /> })}
+        </dl>
+    }
+}
+
+/// Displays the actor of the address at `/address/{address}`. Any address accepted by
+/// [`parse_address`] is supported, i.e., Filecoin addresses of the selected network and Ethereum
+/// addresses.
+#[component]
+pub fn AddressPage() -> impl IntoView {
+    let params = use_params_map();
+    let rpc_context = RpcContext::use_context();
+    let raw_address = Memo::new(move |_| params.read().get("address").unwrap_or_default());
+    let actor = LocalResource::new(move || {
+        let raw_address = raw_address.get();
+        let network = rpc_context.network().get();
+        let provider = rpc_context.get();
+        async move {
+            let address =
+                parse_address(&raw_address, network.prefix).map_err(|e| error_message(&e))?;
+            let info = provider
+                .actor_info(address)
+                .await
+                .map_err(|e| error_message(&e))?;
+            Ok((address, info))
+        }
+    });
+    let title = Signal::derive(move || format!("Address {}", raw_address.get()));
+
+    view! {
+        <Title text=title />
+        <ExplorerPage title=title>
+            <Loaded
+                resource=actor
+                what="address"
+                render=|(address, info)| view! { <AddressDetails address=address info=info /> }
+            />
+        </ExplorerPage>
+    }
+}
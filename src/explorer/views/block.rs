@@ -0,0 +1,137 @@
+use cid::Cid;
+use leptos::prelude::*;
+use leptos::{IntoView, component, view};
+use leptos_meta::Title;
+use leptos_router::hooks::use_params_map;
+
+use crate::explorer::views::components::{
+    AddressLink, BlockLink, DetailRow, ExplorerPage, Loaded, MessageLink, TipsetLink,
+};
+use crate::utils::drip_amount::{DripAmount, NATIVE_DECIMALS};
+use crate::utils::error::error_message;
+use crate::utils::format::{format_balance, format_timestamp};
+use crate::utils::lotus_json::block_header::BlockHeader;
+use crate::utils::lotus_json::block_messages::BlockMessages;
+use crate::utils::rpc_context::RpcContext;
+
+#[component]
+fn BlockMessageList(messages: BlockMessages) -> impl IntoView {
+    let rpc_context = RpcContext::use_context();
+    let unit = rpc_context.network().get_untracked().native_unit();
+    if messages.cids.is_empty() {
+        return view! { <p>No messages.</p> }.into_any();
+    }
+    view! {
+        <table class="detail-table">
+            <thead>
+                <tr>
+                    <th>CID</th>
+                    <th>From</th>
+                    <th>To</th>
+                    <th>Method</th>
+                    <th>Value</th>
+                </tr>
+            </thead>
+            <tbody>
+                {messages
+                    .iter()
+                    .map(|(cid, msg)| {
+                        view! {
+                            <tr>
+                                <td>
+                                    <MessageLink cid=*cid />
+                                </td>
+                                <td>
+                                    <AddressLink address=msg.from />
+                                </td>
+                                <td>
+                                    <AddressLink address=msg.to />
+                                </td>
+                                <td>{msg.method_num}</td>
+                                <td>{format_balance(&DripAmount::Token(msg.value.clone()), unit, NATIVE_DECIMALS)}</td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()}
+            </tbody>
+        </table>
+    }
+    .into_any()
+}
+
+#[component]
+fn BlockDetails(cid: Cid, block: BlockHeader, messages: BlockMessages) -> impl IntoView {
+    let message_count = messages.cids.len();
+    view! {
+        <dl class="detail-list">
+            <DetailRow label="CID">{cid.to_string()}</DetailRow>
+            <DetailRow label="Height">
+                <TipsetLink height=block.height />
+            </DetailRow>
+            <DetailRow label="Miner">
+                <AddressLink address=block.miner />
+            </DetailRow>
+            <DetailRow label="Timestamp">{format_timestamp(block.timestamp)}</DetailRow>
+            <DetailRow label="Parent weight">{block.parent_weight.to_string()}</DetailRow>
+            <DetailRow label="Parent base fee">
+                {format!("{} attoFIL", block.parent_base_fee.atto())}
+            </DetailRow>
+            <DetailRow label="Parents">
+                <ul>
+                    {block
+                        .parents
+                        .0
+                        .into_iter()
+                        .map(|cid| view! { <li><BlockLink cid=cid /></li> })
+                        .collect_view()}
+                </ul>
+            </DetailRow>
+            <DetailRow label="Parent state root">{block.parent_state_root.to_string()}</DetailRow>
+            <DetailRow label="Messages">{message_count}</DetailRow>
+        </dl>
+        <BlockMessageList messages=messages />
+    }
+}
+
+/// Displays the block at `/block/{cid}` along with the messages it includes.
+#[component]
+pub fn BlockPage() -> impl IntoView {
+    let params = use_params_map();
+    let rpc_context = RpcContext::use_context();
+    let cid = Memo::new(move |_| {
+        params
+            .read()
+            .get("cid")
+            .and_then(|cid| cid.parse::<Cid>().ok())
+    });
+    let block = LocalResource::new(move || {
+        let cid = cid.get();
+        let provider = rpc_context.get();
+        async move {
+            let cid = cid.ok_or_else(|| "Invalid block CID".to_string())?;
+            let (block, messages) = futures::try_join!(
+                provider.chain_get_block(cid),
+                provider.chain_get_block_messages(cid)
+            )
+            .map_err(|e| error_message(&e))?;
+            Ok((cid, block, messages))
+        }
+    });
+    let title = Signal::derive(move || match cid.get() {
+        Some(cid) => format!("Block {cid}"),
+        None => "Block".to_string(),
+    });
+
+    view! {
+        <Title text=title />
+        <ExplorerPage title=title>
+            <Loaded
+                resource=block
+                what="block"
+                render=|(cid, block, messages)| {
+                    view! { <BlockDetails cid=cid block=block messages=messages /> }
+                }
+            />
+        </ExplorerPage>
+    }
+}
@@ -0,0 +1,102 @@
+use cid::Cid;
+use fvm_shared::address::Address;
+use leptos::prelude::*;
+use leptos::{IntoView, component, view};
+
+use crate::explorer::views::search::SearchBar;
+use crate::faucet::views::components::nav::GotoHome;
+use crate::faucet::views::components::rpc_selector::NetworkSelection;
+use crate::utils::rpc_context::RpcContext;
+
+/// Lays out a page of the explorer: its title, the network selection, and its content.
+#[component]
+pub fn ExplorerPage(title: Signal<String>, children: Children) -> impl IntoView {
+    let rpc_context = RpcContext::use_context();
+    view! {
+        <h1 class="header">{title}</h1>
+        <div class="main-container">
+            <div class="selector-group">
+                <NetworkSelection rpc_context=rpc_context />
+            </div>
+            <SearchBar />
+            {children()}
+            <GotoHome />
+        </div>
+    }
+}
+
+/// A labeled value in the details of a tipset, block or message.
+#[component]
+pub fn DetailRow(label: &'static str, children: Children) -> impl IntoView {
+    view! {
+        <div class="detail-row">
+            <dt class="detail-label">{label}</dt>
+            <dd class="detail-value">{children()}</dd>
+        </div>
+    }
+}
+
+/// Links to the page of a block.
+#[component]
+pub fn BlockLink(cid: Cid) -> impl IntoView {
+    view! {
+        <a class="link-text" href=format!("/block/{cid}")>
+            {cid.to_string()}
+        </a>
+    }
+}
+
+/// Links to the page of an address.
+#[component]
+pub fn AddressLink(address: Address) -> impl IntoView {
+    view! {
+        <a class="link-text" href=format!("/address/{address}")>
+            {address.to_string()}
+        </a>
+    }
+}
+
+/// Links to the page of a message.
+#[component]
+pub fn MessageLink(cid: Cid) -> impl IntoView {
+    view! {
+        <a class="link-text" href=format!("/message/{cid}")>
+            {cid.to_string()}
+        </a>
+    }
+}
+
+/// Links to the page of the tipset at a height.
+#[component]
+pub fn TipsetLink(height: i64) -> impl IntoView {
+    view! {
+        <a class="link-text" href=format!("/tipset/{height}")>
+            {height}
+        </a>
+    }
+}
+
+/// Shows the result of a chain query once it is loaded, or the reason it failed.
+#[component]
+pub fn Loaded<T, V>(
+    resource: LocalResource<Result<T, String>>,
+    what: &'static str,
+    render: fn(T) -> V,
+) -> impl IntoView
+where
+    T: Clone + Send + Sync + 'static,
+    V: IntoView + 'static,
+{
+    view! {
+        <Transition fallback=move || view! { <p>{format!("Loading {what}...")}</p> }>
+            {move || {
+                resource
+                    .get()
+                    .map(|result| match result {
+                        Ok(value) => render(value).into_any(),
+                        Err(e) => view! { <p class="error-text">{format!("Failed to load {what}: {e}")}</p> }.into_any(),
+                    })
+            }}
+        </Transition>
+    }
+}
@@ -0,0 +1,165 @@
+use cid::Cid;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use leptos::prelude::*;
+use leptos::{IntoView, component, view};
+use leptos_meta::Title;
+use leptos_router::hooks::use_params_map;
+
+use crate::explorer::views::components::{
+    AddressLink, DetailRow, ExplorerPage, Loaded, TipsetLink,
+};
+use crate::utils::drip_amount::{DripAmount, NATIVE_DECIMALS};
+use crate::utils::error::error_message;
+use crate::utils::format::format_balance;
+use crate::utils::lotus_json::{InvocResult, MessageLookup};
+use crate::utils::message::MethodCall;
+use crate::utils::rpc_context::RpcContext;
+
+/// Formats bytes as `0x`-prefixed hex, or `(none)` if there are none.
+fn format_bytes(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        "(none)".to_string()
+    } else {
+        alloy::hex::encode_prefixed(bytes)
+    }
+}
+
+#[component]
+fn MethodParams(msg: Message) -> impl IntoView {
+    match MethodCall::decode(&msg) {
+        Some(MethodCall::Send) => view! { <span>"(none)"</span> }.into_any(),
+        Some(MethodCall::AddVerifiedClient(params)) => view! {
+            <ul>
+                <li>{format!("Address: {}", params.address)}</li>
+                <li>{format!("Allowance: {} bytes", params.allowance)}</li>
+            </ul>
+        }
+        .into_any(),
+        None => view! { <span>{format_bytes(msg.params.bytes())}</span> }.into_any(),
+    }
+}
+
+#[component]
+fn MessageDetails(
+    cid: Cid,
+    msg: Message,
+    lookup: Option<MessageLookup>,
+    replay: Option<InvocResult>,
+) -> impl IntoView {
+    let rpc_context = RpcContext::use_context();
+    let unit = rpc_context.network().get_untracked().native_unit();
+    let format_fil = move |amount: &TokenAmount| {
+        format_balance(&DripAmount::Token(amount.clone()), unit, NATIVE_DECIMALS)
+    };
+    let method = match MethodCall::decode(&msg) {
+        Some(call) => format!("{} ({})", call.name(), msg.method_num),
+        None => msg.method_num.to_string(),
+    };
+    let value = format_fil(&msg.value);
+    let gas_fee_cap = format!("{} attoFIL", msg.gas_fee_cap.atto());
+    let gas_premium = format!("{} attoFIL", msg.gas_premium.atto());
+    let (from, to, nonce, gas_limit) = (msg.from, msg.to, msg.sequence, msg.gas_limit);
+    view! {
+        <dl class="detail-list">
+            <DetailRow label="CID">{cid.to_string()}</DetailRow>
+            <DetailRow label="From">
+                <AddressLink address=from />
+            </DetailRow>
+            <DetailRow label="To">
+                <AddressLink address=to />
+            </DetailRow>
+            <DetailRow label="Nonce">{nonce}</DetailRow>
+            <DetailRow label="Value">{value}</DetailRow>
+            <DetailRow label="Method">{method}</DetailRow>
+            <DetailRow label="Params">
+                <MethodParams msg=msg />
+            </DetailRow>
+            <DetailRow label="Gas limit">{gas_limit}</DetailRow>
+            <DetailRow label="Gas fee cap">{gas_fee_cap}</DetailRow>
+            <DetailRow label="Gas premium">{gas_premium}</DetailRow>
+            {match lookup {
+                Some(lookup) => {
+                    let receipt = lookup.receipt;
+                    view! {
+                        <DetailRow label="Executed in tipset">
+                            <TipsetLink height=lookup.height />
+                        </DetailRow>
+                        <DetailRow label="Exit code">
+                            {if receipt.exit_code.is_success() {
+                                format!("{} (success)", receipt.exit_code)
+                            } else {
+                                format!("{} (failed)", receipt.exit_code)
+                            }}
+                        </DetailRow>
+                        <DetailRow label="Gas used">{receipt.gas_used}</DetailRow>
+                        <DetailRow label="Return">{format_bytes(receipt.return_data.bytes())}</DetailRow>
+                    }
+                        .into_any()
+                }
+                None => view! { <DetailRow label="Status">"Not executed yet"</DetailRow> }.into_any(),
+            }}
+            {replay
+                .map(|replay| {
+                    view! {
+                        <DetailRow label="Total cost">{format_fil(&replay.gas_cost.total_cost)}</DetailRow>
+                        {(!replay.error.is_empty())
+                            .then(|| view! { <DetailRow label="Error">{replay.error}</DetailRow> })}
+                    }
+                })}
+        </dl>
+    }
+}
+
+/// Displays the message at `/message/{cid}`, along with its receipt and the tipset it was executed
+/// in once it is on chain.
+#[component]
+pub fn MessagePage() -> impl IntoView {
+    let params = use_params_map();
+    let rpc_context = RpcContext::use_context();
+    let cid = Memo::new(move |_| {
+        params
+            .read()
+            .get("cid")
+            .and_then(|cid| cid.parse::<Cid>().ok())
+    });
+    let message = LocalResource::new(move || {
+        let cid = cid.get();
+        let provider = rpc_context.get();
+        async move {
+            let cid = cid.ok_or_else(|| "Invalid message CID".to_string())?;
+            let (msg, lookup) = futures::try_join!(
+                provider.chain_get_message(cid),
+                provider.state_search_msg(cid, None)
+            )
+            .map_err(|e| error_message(&e))?;
+            // The replay only adds the gas cost and the error of executed messages.
+            let replay = match lookup {
+                Some(_) => provider
+                    .state_replay(cid)
+                    .await
+                    .inspect_err(|e| log::warn!("Failed to replay {cid}: {e}"))
+                    .ok(),
+                None => None,
+            };
+            Ok((cid, msg, lookup, replay))
+        }
+    });
+    let title = Signal::derive(move || match cid.get() {
+        Some(cid) => format!("Message {cid}"),
+        None => "Message".to_string(),
+    });
+
+    view! {
+        <Title text=title />
+        <ExplorerPage title=title>
+            <Loaded
+                resource=message
+                what="message"
+                render=|(cid, msg, lookup, replay)| {
+                    view! { <MessageDetails cid=cid msg=msg lookup=lookup replay=replay /> }
+                }
+            />
+        </ExplorerPage>
+    }
+}
@@ -0,0 +1,6 @@
+pub mod address;
+pub mod block;
+pub mod components;
+pub mod message;
+pub mod search;
+pub mod tipset;
@@ -0,0 +1,67 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos::{IntoView, component, leptos_dom::helpers::event_target_value, view};
+use leptos_router::hooks::use_navigate;
+
+use crate::explorer::search::{classify, resolve};
+use crate::utils::error::error_message;
+use crate::utils::rpc_context::RpcContext;
+
+/// Searches the selected network for an address, a message or block CID, an Ethereum transaction
+/// hash or an epoch, and opens its explorer page.
+#[component]
+pub fn SearchBar() -> impl IntoView {
+    let rpc_context = RpcContext::use_context();
+    let navigate = use_navigate();
+    let input = RwSignal::new(String::new());
+    let error = RwSignal::new(None::<String>);
+    let searching = RwSignal::new(false);
+    let search = move || {
+        let network = rpc_context.network().get_untracked();
+        let Some(query) = classify(&input.get_untracked(), network.prefix) else {
+            error.set(Some(
+                "Enter an address, a message or block CID, a transaction hash or an epoch"
+                    .to_string(),
+            ));
+            return;
+        };
+        error.set(None);
+        searching.set(true);
+        let provider = rpc_context.get();
+        let navigate = navigate.clone();
+        spawn_local(async move {
+            match resolve(&provider, query).await {
+                Ok(path) => {
+                    input.set(String::new());
+                    navigate(&path, Default::default());
+                }
+                Err(e) => error.set(Some(error_message(&e))),
+            }
+            searching.set(false);
+        });
+    };
+    let on_click = {
+        let search = search.clone();
+        move |_| search()
+    };
+    view! {
+        <div class="search-bar">
+            <input
+                type="text"
+                placeholder="Search by address, CID, transaction hash or epoch"
+                prop:value=input
+                on:input=move |ev| input.set(event_target_value(&ev))
+                on:keydown=move |ev| {
+                    if ev.key() == "Enter" && !searching.get_untracked() {
+                        search();
+                    }
+                }
+                class="input"
+            />
+            <button class="btn" disabled=searching on:click=on_click>
+                {move || if searching.get() { "Searching..." } else { "Search" }}
+            </button>
+        </div>
+        {move || error.get().map(|error| view! { <p class="error-text">{error}</p> })}
+    }
+}
@@ -0,0 +1,151 @@
+use leptos::prelude::*;
+use leptos::{IntoView, component, view};
+use leptos_meta::Title;
+use leptos_router::hooks::use_params_map;
+
+use crate::explorer::views::components::{BlockLink, DetailRow, ExplorerPage, Loaded, TipsetLink};
+use crate::utils::error::error_message;
+use crate::utils::format::format_timestamp;
+use crate::utils::lotus_json::tipset::Tipset;
+use crate::utils::rpc_context::RpcContext;
+
+#[component]
+fn TipsetDetails(tipset: Tipset, height: i64) -> impl IntoView {
+    let first_block = tipset.first_block().cloned();
+    view! {
+        {(tipset.height != height)
+            .then(|| {
+                view! {
+                    <p class="description">
+                        {format!("Epoch {height} is a null round. Showing the tipset below it.")}
+                    </p>
+                }
+            })}
+        <dl class="detail-list">
+            <DetailRow label="Height">{tipset.height}</DetailRow>
+            {first_block
+                .map(|block| {
+                    view! {
+                        <DetailRow label="Timestamp">{format_timestamp(block.timestamp)}</DetailRow>
+                        <DetailRow label="Parent weight">{block.parent_weight.to_string()}</DetailRow>
+                        <DetailRow label="Parent base fee">
+                            {format!("{} attoFIL", block.parent_base_fee.atto())}
+                        </DetailRow>
+                        <DetailRow label="Parents">
+                            <ul>
+                                {block
+                                    .parents
+                                    .0
+                                    .into_iter()
+                                    .map(|cid| view! { <li><BlockLink cid=cid /></li> })
+                                    .collect_view()}
+                            </ul>
+                        </DetailRow>
+                    }
+                })}
+            <DetailRow label="Blocks">
+                <table class="detail-table">
+                    <thead>
+                        <tr>
+                            <th>CID</th>
+                            <th>Miner</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {tipset
+                            .key
+                            .0
+                            .into_iter()
+                            .zip(tipset.blocks)
+                            .map(|(cid, block)| {
+                                view! {
+                                    <tr>
+                                        <td>
+                                            <BlockLink cid=cid />
+                                        </td>
+                                        <td>{block.miner.to_string()}</td>
+                                    </tr>
+                                }
+                            })
+                            .collect_view()}
+                    </tbody>
+                </table>
+            </DetailRow>
+        </dl>
+        <div class="nav-container">
+            {(tipset.height > 0)
+                .then(|| {
+                    view! {
+                        <span>
+                            "Previous: " <TipsetLink height=tipset.height - 1 />
+                        </span>
+                    }
+                })}
+            <span>
+                "Next: " <TipsetLink height=height + 1 />
+            </span>
+        </div>
+    }
+}
+
+/// Shows the height of the chain head of the selected network, linking to its tipset.
+#[component]
+pub fn LatestTipset() -> impl IntoView {
+    let rpc_context = RpcContext::use_context();
+    let provider = rpc_context.provider();
+    let head = LocalResource::new(move || {
+        provider.track();
+        let provider = rpc_context.get();
+        async move { provider.chain_head().await.ok().map(|tipset| tipset.height) }
+    });
+    view! {
+        <div class="network-info mx-auto">
+            <p>Latest tipset:</p>
+            <Transition fallback=move || view! { <p>Loading chain head...</p> }>
+                {move || head.get().flatten().map(|height| view! { <TipsetLink height=height /> })}
+            </Transition>
+        </div>
+    }
+}
+
+/// Displays the tipset at `/tipset/{height}`. The node returns the tipset below the height for a
+/// null round.
+#[component]
+pub fn TipsetPage() -> impl IntoView {
+    let params = use_params_map();
+    let rpc_context = RpcContext::use_context();
+    let height = Memo::new(move |_| {
+        params
+            .read()
+            .get("height")
+            .and_then(|height| height.parse::<i64>().ok())
+            .filter(|height| *height >= 0)
+    });
+    let tipset = LocalResource::new(move || {
+        let height = height.get();
+        let provider = rpc_context.get();
+        async move {
+            let height = height.ok_or_else(|| "Invalid height".to_string())?;
+            let tipset = provider
+                .chain_get_tipset_by_height(height)
+                .await
+                .map_err(|e| error_message(&e))?;
+            Ok((tipset, height))
+        }
+    });
+    let title = Signal::derive(move || match height.get() {
+        Some(height) => format!("Tipset {height}"),
+        None => "Tipset".to_string(),
+    });
+
+    view! {
+        <Title text=title />
+        <ExplorerPage title=title>
+            <Loaded
+                resource=tipset
+                what="tipset"
+                render=|(tipset, height)| view! { <TipsetDetails tipset=tipset height=height /> }
+            />
+        </ExplorerPage>
+    }
+}
@@ -0,0 +1,217 @@
+#![cfg(feature = "ssr")]
+//! API keys granting automated claimers, e.g., integration test suites, their own quotas.
+//!
+//! Keys are sent in the `Authorization: Bearer {key}` header and stored in the `API_KEYS` KV
+//! namespace under their [`api_key_id`], so that the keys themselves are never stored. Claims made
+//! with a key are accounted to the key alone: it has its own rate limiter per wallet and its own
+//! drip budget per faucet, and the anonymous limits do not apply to it.
+
+use super::constants::FaucetInfo;
+use crate::utils::drip_amount::DripAmount;
+use crate::utils::key::blake2b_256;
+use serde::{Deserialize, Serialize};
+use worker::{Error, Result};
+
+/// KV namespace binding holding the API keys.
+pub(super) const API_KEYS_BINDING: &str = "API_KEYS";
+
+/// Quota of an API key. Like the multipliers of the anonymous limits, caps are given as a number
+/// of drips.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyQuota {
+    /// Multiplier applied to the drip amount of the faucet.
+    pub drip_multiplier: u32,
+    /// Maximum number of drips per wallet every [`FaucetInfo::reset_limiter_seconds`].
+    pub wallet_cap: u32,
+    /// Maximum number of drips across all wallets every [`FaucetInfo::reset_limiter_seconds`].
+    pub drip_cap: u32,
+}
+
+impl ApiKeyQuota {
+    /// Amount of a single drip of the faucet with this quota.
+    pub fn drip_amount(&self, faucet_info: &FaucetInfo) -> DripAmount {
+        faucet_info.drip_amount() * i64::from(self.drip_multiplier)
+    }
+
+    /// Maximum amount that can be dripped to a single wallet with this quota.
+    pub fn wallet_cap(&self, faucet_info: &FaucetInfo) -> DripAmount {
+        self.drip_amount(faucet_info) * i64::from(self.wallet_cap)
+    }
+
+    /// Maximum amount that can be dripped across all wallets with this quota.
+    pub fn drip_cap(&self, faucet_info: &FaucetInfo) -> DripAmount {
+        self.drip_amount(faucet_info) * i64::from(self.drip_cap)
+    }
+
+    /// Encodes the quota as the query string of a rate limiter or drip budget request.
+    pub(super) fn to_query(self) -> String {
+        format!(
+            "drip_multiplier={}&wallet_cap={}&drip_cap={}",
+            self.drip_multiplier, self.wallet_cap, self.drip_cap
+        )
+    }
+
+    /// Decodes a quota encoded by [`ApiKeyQuota::to_query`].
+    fn from_query(query: &str) -> Result<Self> {
+        let (mut drip_multiplier, mut wallet_cap, mut drip_cap) = (None, None, None);
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            let field = match key.as_ref() {
+                "drip_multiplier" => &mut drip_multiplier,
+                "wallet_cap" => &mut wallet_cap,
+                "drip_cap" => &mut drip_cap,
+                _ => continue,
+            };
+            *field = Some(
+                value
+                    .parse::<u32>()
+                    .map_err(|e| Error::RustError(format!("Invalid quota {key}: {e}")))?,
+            );
+        }
+        match (drip_multiplier, wallet_cap, drip_cap) {
+            (Some(drip_multiplier), Some(wallet_cap), Some(drip_cap)) => Ok(ApiKeyQuota {
+                drip_multiplier,
+                wallet_cap,
+                drip_cap,
+            }),
+            _ => Err(Error::RustError(format!("Incomplete quota: {query}"))),
+        }
+    }
+}
+
+/// Amount of a single drip of the faucet, raised by the API key quota, if any.
+pub fn drip_amount(faucet_info: &FaucetInfo, quota: Option<&ApiKeyQuota>) -> DripAmount {
+    quota.map_or_else(
+        || faucet_info.drip_amount(),
+        |quota| quota.drip_amount(faucet_info),
+    )
+}
+
+/// Splits the URL of a rate limiter or drip budget request into its path and the API key quota
+/// given in its query, if any.
+pub(super) fn split_quota(url: &str) -> Result<(&str, Option<ApiKeyQuota>)> {
+    match url.split_once('?') {
+        Some((path, query)) => Ok((path, Some(ApiKeyQuota::from_query(query)?))),
+        None => Ok((url, None)),
+    }
+}
+
+/// An API key as stored in the `API_KEYS` KV namespace, e.g.,
+/// `{"name":"ci","faucets":["CalibnetFIL"],"drip_multiplier":2,"wallet_cap":50,"drip_cap":500}`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Name of the key holder, used in logs.
+    pub name: String,
+    /// Faucets the key can claim from.
+    pub faucets: Vec<FaucetInfo>,
+    #[serde(flatten)]
+    pub quota: ApiKeyQuota,
+}
+
+/// A known API key sent along with a claim.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiKey {
+    /// See [`api_key_id`].
+    pub id: String,
+    pub config: ApiKeyConfig,
+}
+
+/// Returns the identifier of an API key: the hex-encoded BLAKE2b-256 hash of the key.
+pub fn api_key_id(key: &str) -> String {
+    hex::encode(blake2b_256(key.as_bytes()))
+}
+
+/// Extracts the API key from the value of an `Authorization` header, e.g., `Bearer {key}`.
+pub fn parse_bearer(header: &str) -> Option<&str> {
+    let (scheme, key) = header.trim().split_once(' ')?;
+    let key = key.trim();
+    (scheme.eq_ignore_ascii_case("Bearer") && !key.is_empty()).then_some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_shared::econ::TokenAmount;
+
+    const QUOTA: ApiKeyQuota = ApiKeyQuota {
+        drip_multiplier: 2,
+        wallet_cap: 10,
+        drip_cap: 100,
+    };
+
+    #[test]
+    fn test_quota_amounts() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        assert_eq!(
+            QUOTA.drip_amount(&faucet_info),
+            DripAmount::Token(TokenAmount::from_whole(10))
+        );
+        assert_eq!(
+            QUOTA.wallet_cap(&faucet_info),
+            DripAmount::Token(TokenAmount::from_whole(100))
+        );
+        assert_eq!(
+            QUOTA.drip_cap(&faucet_info),
+            DripAmount::Token(TokenAmount::from_whole(1000))
+        );
+        assert_eq!(drip_amount(&faucet_info, None), faucet_info.drip_amount());
+        assert_eq!(
+            drip_amount(&faucet_info, Some(&QUOTA)),
+            QUOTA.drip_amount(&faucet_info)
+        );
+    }
+
+    #[test]
+    fn test_split_quota() {
+        let url = format!(
+            "http://do/rate_limiter/CalibnetFIL/t1abc?{}",
+            QUOTA.to_query()
+        );
+        assert_eq!(
+            split_quota(&url).unwrap(),
+            ("http://do/rate_limiter/CalibnetFIL/t1abc", Some(QUOTA))
+        );
+        assert_eq!(
+            split_quota("http://do/rate_limiter/CalibnetFIL/t1abc").unwrap(),
+            ("http://do/rate_limiter/CalibnetFIL/t1abc", None)
+        );
+        assert!(split_quota("http://do/rate_limiter/CalibnetFIL/t1abc?wallet_cap=1").is_err());
+        assert!(
+            split_quota("http://do/rate_limiter/CalibnetFIL/t1abc?drip_multiplier=-1&wallet_cap=1&drip_cap=1")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_api_key_config() {
+        let config: ApiKeyConfig = serde_json::from_str(
+            r#"{"name":"ci","faucets":["CalibnetFIL","CalibnetUSDFC"],"drip_multiplier":2,"wallet_cap":10,"drip_cap":100}"#,
+        )
+        .unwrap();
+        assert_eq!(config.name, "ci");
+        assert_eq!(
+            config.faucets,
+            [
+                "CalibnetFIL".parse::<FaucetInfo>().unwrap(),
+                "CalibnetUSDFC".parse::<FaucetInfo>().unwrap()
+            ]
+        );
+        assert_eq!(config.quota, QUOTA);
+    }
+
+    #[test]
+    fn test_parse_bearer() {
+        assert_eq!(parse_bearer("Bearer secret"), Some("secret"));
+        assert_eq!(parse_bearer("bearer  secret "), Some("secret"));
+        assert_eq!(parse_bearer("Basic secret"), None);
+        assert_eq!(parse_bearer("Bearer "), None);
+        assert_eq!(parse_bearer("secret"), None);
+    }
+
+    #[test]
+    fn test_api_key_id() {
+        let id = api_key_id("secret");
+        assert_eq!(id.len(), 64);
+        assert_eq!(id, api_key_id("secret"));
+        assert_ne!(id, api_key_id("other"));
+    }
+}
@@ -0,0 +1,364 @@
+//! Hashcash-style proof-of-work challenges guarding the drip endpoints.
+//!
+//! The server issues a [`Challenge`] bound to the recipient address and signed with a server-side
+//! secret. To claim, the client has to find a `solution` such that the BLAKE2b-256 hash of the
+//! UTF-8 string `{challenge}:{solution}` starts with at least `difficulty` zero bits, where
+//! `{challenge}` is the challenge token as returned by the server, e.g.,
+//! `4f1c…e2.20.1735689600.9a0b…7d`, and `{solution}` is a decimal `u64`.
+
+use crate::utils::key::blake2b_256;
+#[cfg(feature = "ssr")]
+use fvm_shared::address::Address;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Number of seconds a challenge can be solved and redeemed in.
+#[cfg(feature = "ssr")]
+const CHALLENGE_TTL_SECONDS: i64 = 300;
+
+/// Number of solutions tried between two yields to the browser, see [`Challenge::solve`].
+const SOLVE_CHUNK: u64 = 10_000;
+
+/// A proof-of-work challenge. It is (de)serialized as its token, i.e.,
+/// `{nonce}.{difficulty}.{expires_at}.{signature}`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Challenge {
+    /// Random hex string making the challenge unique.
+    nonce: String,
+    /// Number of leading zero bits the hash of the solution must have.
+    difficulty: u8,
+    /// Unix timestamp (seconds) after which the challenge is no longer accepted.
+    expires_at: i64,
+    /// Hex-encoded keyed BLAKE2b-256 hash of the other fields and the recipient address.
+    signature: String,
+}
+
+/// A [`Challenge`] along with the solution found by the client.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeSolution {
+    pub challenge: Challenge,
+    pub solution: u64,
+}
+
+/// Reasons a [`ChallengeSolution`] is rejected.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ChallengeError {
+    #[error("a proof-of-work challenge is required")]
+    Missing,
+    #[error("invalid challenge: {0}")]
+    Malformed(String),
+    #[error("the challenge expired")]
+    Expired,
+    #[error("the challenge difficulty {actual} is below the required {required}")]
+    TooEasy { required: u8, actual: u8 },
+    #[error("the challenge was not issued by this faucet for this address")]
+    BadSignature,
+    #[error("the solution does not meet the challenge difficulty")]
+    WrongSolution,
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.nonce, self.difficulty, self.expires_at, self.signature
+        )
+    }
+}
+
+impl FromStr for Challenge {
+    type Err = ChallengeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || ChallengeError::Malformed(s.to_string());
+        let mut parts = s.trim().split('.');
+        let (Some(nonce), Some(difficulty), Some(expires_at), Some(signature), None) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return Err(malformed());
+        };
+        Ok(Challenge {
+            nonce: nonce.to_string(),
+            difficulty: difficulty.parse().map_err(|_| malformed())?,
+            expires_at: expires_at.parse().map_err(|_| malformed())?,
+            signature: signature.to_string(),
+        })
+    }
+}
+
+impl From<Challenge> for String {
+    fn from(challenge: Challenge) -> Self {
+        challenge.to_string()
+    }
+}
+
+impl TryFrom<String> for Challenge {
+    type Error = ChallengeError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Challenge {
+    /// Number of leading zero bits the hash of the solution must have.
+    pub fn difficulty(&self) -> u8 {
+        self.difficulty
+    }
+
+    /// Issues a challenge of the given difficulty for claims to `address`.
+    #[cfg(feature = "ssr")]
+    pub fn issue(address: &Address, difficulty: u8, secret: &str, now: i64) -> Self {
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        let expires_at = now + CHALLENGE_TTL_SECONDS;
+        let signature = signature(&nonce, difficulty, expires_at, address, secret);
+        Challenge {
+            nonce,
+            difficulty,
+            expires_at,
+            signature,
+        }
+    }
+
+    /// Finds the smallest solution to the challenge. This takes about `2^difficulty` hashes, so
+    /// the search yields to the browser every [`SOLVE_CHUNK`] of them to keep the page responsive.
+    pub async fn solve(self) -> ChallengeSolution {
+        let mut start: u64 = 0;
+        loop {
+            let end = start.saturating_add(SOLVE_CHUNK);
+            if let Some(solution) = self.solve_in(start..end) {
+                return ChallengeSolution {
+                    challenge: self,
+                    solution,
+                };
+            }
+            start = end;
+            gloo_timers::future::TimeoutFuture::new(0).await;
+        }
+    }
+
+    /// Finds the smallest solution to the challenge among `candidates`, if any.
+    fn solve_in(&self, mut candidates: Range<u64>) -> Option<u64> {
+        candidates.find(|solution| meets_difficulty(self, *solution))
+    }
+}
+
+impl ChallengeSolution {
+    /// Checks that the challenge was issued for `address` by a server holding `secret`, that it is
+    /// still valid and at least as hard as `required`, and that the solution is correct.
+    ///
+    /// Note that a solution can be redeemed several times until the challenge expires. Since the
+    /// challenge is bound to the recipient, the rate limiter takes care of repeated claims.
+    #[cfg(feature = "ssr")]
+    pub fn verify(
+        &self,
+        address: &Address,
+        required: u8,
+        secret: &str,
+        now: i64,
+    ) -> Result<(), ChallengeError> {
+        let challenge = &self.challenge;
+        if challenge.expires_at <= now {
+            return Err(ChallengeError::Expired);
+        }
+        if challenge.difficulty < required {
+            return Err(ChallengeError::TooEasy {
+                required,
+                actual: challenge.difficulty,
+            });
+        }
+        let expected = signature_hash(
+            &challenge.nonce,
+            challenge.difficulty,
+            challenge.expires_at,
+            address,
+            secret,
+        );
+        let signature =
+            hex::decode(&challenge.signature).map_err(|_| ChallengeError::BadSignature)?;
+        // The hash compares in constant time, so that the time taken does not tell how much of a
+        // forged signature is right.
+        if expected != *signature.as_slice() {
+            return Err(ChallengeError::BadSignature);
+        }
+        if !meets_difficulty(challenge, self.solution) {
+            return Err(ChallengeError::WrongSolution);
+        }
+        Ok(())
+    }
+}
+
+/// Signs the fields of a challenge and the address it is bound to with a BLAKE2b-256 hash keyed
+/// by the server secret.
+#[cfg(feature = "ssr")]
+fn signature(
+    nonce: &str,
+    difficulty: u8,
+    expires_at: i64,
+    address: &Address,
+    secret: &str,
+) -> String {
+    hex::encode(signature_hash(nonce, difficulty, expires_at, address, secret).as_bytes())
+}
+
+/// Same as [`signature`], before hex encoding.
+#[cfg(feature = "ssr")]
+fn signature_hash(
+    nonce: &str,
+    difficulty: u8,
+    expires_at: i64,
+    address: &Address,
+    secret: &str,
+) -> blake2b_simd::Hash {
+    let key = blake2b_256(secret.as_bytes());
+    blake2b_simd::Params::new()
+        .hash_length(32)
+        .key(&key)
+        .to_state()
+        .update(format!("{nonce}.{difficulty}.{expires_at}").as_bytes())
+        .update(&address.to_bytes())
+        .finalize()
+}
+
+/// Checks whether the hash of `{challenge}:{solution}` has at least as many leading zero bits as
+/// the challenge difficulty.
+fn meets_difficulty(challenge: &Challenge, solution: u64) -> bool {
+    let hash = blake2b_256(format!("{challenge}:{solution}").as_bytes());
+    leading_zero_bits(&hash) >= u32::from(challenge.difficulty)
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test_secret";
+    const NOW: i64 = 1_735_689_600;
+
+    fn address() -> Address {
+        Address::new_id(163506)
+    }
+
+    fn solve(challenge: Challenge) -> ChallengeSolution {
+        let solution = challenge.solve_in(0..u64::MAX).unwrap();
+        ChallengeSolution {
+            challenge,
+            solution,
+        }
+    }
+
+    #[test]
+    fn test_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0xff, 0x00]), 0);
+        assert_eq!(leading_zero_bits(&[0x00, 0x80]), 8);
+        assert_eq!(leading_zero_bits(&[0x00, 0x00, 0x1f]), 19);
+        assert_eq!(leading_zero_bits(&[0x00; 32]), 256);
+    }
+
+    #[test]
+    fn test_challenge_token_roundtrip() {
+        let challenge = Challenge::issue(&address(), 8, SECRET, NOW);
+        let token = challenge.to_string();
+        assert_eq!(token.split('.').count(), 4);
+        assert_eq!(token.parse::<Challenge>().unwrap(), challenge);
+        assert_eq!(
+            serde_json::to_string(&challenge).unwrap(),
+            format!("\"{token}\"")
+        );
+
+        assert!(matches!(
+            "abc.8.123".parse::<Challenge>(),
+            Err(ChallengeError::Malformed(_))
+        ));
+        assert!(matches!(
+            "abc.hard.123.def".parse::<Challenge>(),
+            Err(ChallengeError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_solve_and_verify() {
+        let solution = solve(Challenge::issue(&address(), 8, SECRET, NOW));
+        assert_eq!(solution.verify(&address(), 8, SECRET, NOW), Ok(()));
+        // A harder challenge than required is fine.
+        assert_eq!(solution.verify(&address(), 4, SECRET, NOW), Ok(()));
+    }
+
+    /// Checks that searching chunk by chunk finds the smallest solution.
+    #[test]
+    fn test_solve_in() {
+        let challenge = Challenge::issue(&address(), 8, SECRET, NOW);
+        let solution = solve(challenge.clone()).solution;
+        assert_eq!(challenge.solve_in(0..solution), None);
+        assert_eq!(challenge.solve_in(solution..solution + 1), Some(solution));
+    }
+
+    #[test]
+    fn test_verify_rejects_invalid_solutions() {
+        let solution = solve(Challenge::issue(&address(), 8, SECRET, NOW));
+
+        assert_eq!(
+            solution.verify(&address(), 8, SECRET, NOW + CHALLENGE_TTL_SECONDS),
+            Err(ChallengeError::Expired)
+        );
+        assert_eq!(
+            solution.verify(&address(), 12, SECRET, NOW),
+            Err(ChallengeError::TooEasy {
+                required: 12,
+                actual: 8
+            })
+        );
+        assert_eq!(
+            solution.verify(&Address::new_id(1), 8, SECRET, NOW),
+            Err(ChallengeError::BadSignature)
+        );
+        assert_eq!(
+            solution.verify(&address(), 8, "other_secret", NOW),
+            Err(ChallengeError::BadSignature)
+        );
+        let mut unsigned = solution.clone();
+        unsigned.challenge.signature = "not hex".to_string();
+        assert_eq!(
+            unsigned.verify(&address(), 8, SECRET, NOW),
+            Err(ChallengeError::BadSignature)
+        );
+
+        // Tampering with the difficulty invalidates the signature.
+        let mut easier = solution.clone();
+        easier.challenge.difficulty = 0;
+        assert_eq!(
+            easier.verify(&address(), 0, SECRET, NOW),
+            Err(ChallengeError::BadSignature)
+        );
+
+        let wrong = ChallengeSolution {
+            solution: (0..)
+                .find(|s| !meets_difficulty(&solution.challenge, *s))
+                .unwrap(),
+            ..solution
+        };
+        assert_eq!(
+            wrong.verify(&address(), 8, SECRET, NOW),
+            Err(ChallengeError::WrongSolution)
+        );
+    }
+}
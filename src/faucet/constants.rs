@@ -1,134 +1,137 @@
+use super::registry::{FaucetConfig, faucets};
 use crate::utils::drip_amount::{DripAmount, TokenType};
-use alloy::primitives::address;
-use fvm_shared::{address::Network, econ::TokenAmount, sector::StoragePower};
-use serde::{Deserialize, Serialize};
-use std::{str::FromStr as _, sync::LazyLock};
-use strum::{Display, EnumString};
-
-/// The amount of calibnet FIL to be dripped to the user.
-static CALIBNET_DRIP_AMOUNT: LazyLock<TokenAmount> = LazyLock::new(|| {
-    TokenAmount::from_nano(
-        option_env!("CALIBNET_DRIP_AMOUNT")
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(5_000_000_000),
-    )
-});
-
-/// The amount of mainnet FIL to be dripped to the user. This corresponds to 0.01 FIL.
-static MAINNET_DRIP_AMOUNT: LazyLock<TokenAmount> = LazyLock::new(|| {
-    TokenAmount::from_nano(
-        option_env!("MAINNET_DRIP_AMOUNT")
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(10_000_000),
-    )
-});
-
-/// The amount of calibnet `USDFC` to be dripped to the user.
-static CALIBNET_USDFC_DRIP_AMOUNT: LazyLock<TokenAmount> = LazyLock::new(|| {
-    TokenAmount::from_nano(
-        option_env!("CALIBNET_USDFC_DRIP_AMOUNT")
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(5_000_000_000),
-    )
-});
-
-/// The amount of calibnet Datacap to be dripped to the user.
-static CALIBNET_DATACAP_DRIP_AMOUNT: LazyLock<StoragePower> = LazyLock::new(|| {
-    StoragePower::from(
-        option_env!("CALIBNET_DATACAP_DRIP_AMOUNT")
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(1 << 20), // 1 MiB of storage power
-    )
-});
-
-/// Multiplier to determine the maximum amount of tokens that can be dripped per wallet every [`FaucetInfo::reset_limiter_seconds`].
-const MAINNET_PER_WALLET_DRIP_MULTIPLIER: i64 = 1;
-const CALIBNET_PER_WALLET_DRIP_MULTIPLIER: i64 = 2;
-
-/// Multiplier used to determine the maximum amount of tokens that can be dripped globally every [`FaucetInfo::reset_limiter_seconds`].
-const MAINNET_GLOBAL_DRIP_MULTIPLIER: i64 = 2;
-const CALIBNET_GLOBAL_DRIP_MULTIPLIER: i64 = 200;
-
-/// Cool-down duration in seconds between faucet requests on mainnet.
-const MAINNET_COOLDOWN_SECONDS: i64 = 600; // 10 minutes
-/// Cool-down duration in seconds between faucet requests on calibnet.
-const CALIBNET_COOLDOWN_SECONDS: i64 = 60; // 1 minute
+use crate::utils::network::NetworkConfig;
+use fvm_shared::{econ::TokenAmount, sector::StoragePower};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
 /// Time in seconds after which the wallet drip cap resets.
 const DRIP_CAP_RESET_SECONDS: i64 = 86400; // 24 hours
 
-/// Maximum gas limit for mainnet including buffer.
-const MAX_MAINNET_GAS_LIMIT: u64 = 10_000_000;
-/// Maximum gas limit for calibnet including buffer.
-const MAX_CALIBNET_GAS_LIMIT: u64 = 100_000_000;
-
-/// Maximum gas fee cap for mainnet including buffer.
-static MAX_MAINNET_GAS_FEE_CAP: LazyLock<TokenAmount> =
-    LazyLock::new(|| TokenAmount::from_atto(100_000_000));
-/// Maximum gas fee cap for calibnet including buffer.
-static MAX_CALIBNET_GAS_FEE_CAP: LazyLock<TokenAmount> =
-    LazyLock::new(|| TokenAmount::from_atto(200_000));
-
-/// Maximum gas premium for mainnet including buffer.
-static MAX_MAINNET_GAS_PREMIUM: LazyLock<TokenAmount> =
-    LazyLock::new(|| TokenAmount::from_atto(1_000_000));
-/// Maximum gas premium for calibnet including buffer.
-static MAX_CALIBNET_GAS_PREMIUM: LazyLock<TokenAmount> =
-    LazyLock::new(|| TokenAmount::from_atto(200_000));
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Display, Serialize, Deserialize, EnumString)]
-pub enum FaucetInfo {
-    MainnetFIL,
-    CalibnetFIL,
-    CalibnetUSDFC,
-    CalibnetDatacap,
+/// A faucet of the [registry](super::registry). It is cheap to copy, and is (de)serialized as its
+/// ID, e.g., `CalibnetFIL`.
+#[derive(Clone, Copy)]
+pub struct FaucetInfo(&'static FaucetConfig);
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("Unknown faucet `{0}`")]
+pub struct UnknownFaucet(String);
+
+impl PartialEq for FaucetInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id == other.0.id
+    }
+}
+
+impl Eq for FaucetInfo {}
+
+impl fmt::Debug for FaucetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.id)
+    }
+}
+
+impl fmt::Display for FaucetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.id)
+    }
+}
+
+impl FromStr for FaucetInfo {
+    type Err = UnknownFaucet;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        faucets()
+            .iter()
+            .find(|faucet| faucet.id == id)
+            .map(FaucetInfo)
+            .ok_or_else(|| UnknownFaucet(id.to_string()))
+    }
+}
+
+impl Serialize for FaucetInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.id)
+    }
+}
+
+impl<'de> Deserialize<'de> for FaucetInfo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        FaucetInfo::from_str(&id).map_err(serde::de::Error::custom)
+    }
 }
 
 impl FaucetInfo {
+    /// Returns all the faucets, in the order of the faucet list.
+    pub fn all() -> impl Iterator<Item = FaucetInfo> {
+        faucets().iter().map(FaucetInfo)
+    }
+
+    /// Returns the faucet whose page is at `/faucet/{slug}`, if any.
+    pub fn by_slug(slug: &str) -> Option<FaucetInfo> {
+        faucets()
+            .iter()
+            .find(|faucet| faucet.slug == slug)
+            .map(FaucetInfo)
+    }
+
+    /// Returns the path of the faucet page under `/faucet/`.
+    pub fn slug(&self) -> &'static str {
+        &self.0.slug
+    }
+
     /// Return the drip amount for the given faucet in the defined token unit.
     pub fn drip_amount(&self) -> DripAmount {
-        match self {
-            FaucetInfo::MainnetFIL => DripAmount::Token(MAINNET_DRIP_AMOUNT.clone()),
-            FaucetInfo::CalibnetFIL => DripAmount::Token(CALIBNET_DRIP_AMOUNT.clone()),
-            FaucetInfo::CalibnetUSDFC => DripAmount::Token(CALIBNET_USDFC_DRIP_AMOUNT.clone()),
-            FaucetInfo::CalibnetDatacap => {
-                DripAmount::Storage(CALIBNET_DATACAP_DRIP_AMOUNT.clone())
+        match self.0.token {
+            TokenType::Native | TokenType::Erc20(_) => {
+                DripAmount::Token(TokenAmount::from_nano(self.0.drip_amount))
             }
+            TokenType::Datacap => DripAmount::Storage(StoragePower::from(self.0.drip_amount)),
         }
     }
 
-    /// Returns the rate limit in seconds for the given faucet. The rate limit defines period after
-    /// which the faucet is temporarily disabled and no more drips can be sent.
+    /// Returns the rate limit in seconds for the given faucet. The rate limit defines the period
+    /// during which a claimant cannot request another drip from the faucet.
     pub fn rate_limit_seconds(&self) -> i64 {
-        match self {
-            FaucetInfo::MainnetFIL => MAINNET_COOLDOWN_SECONDS,
-            FaucetInfo::CalibnetFIL | FaucetInfo::CalibnetUSDFC | FaucetInfo::CalibnetDatacap => {
-                CALIBNET_COOLDOWN_SECONDS
-            }
-        }
+        self.0.cooldown_seconds
     }
 
     /// Returns the maximum amount of tokens that can be dripped by the wallet per [`FaucetInfo::reset_limiter_seconds`].
     /// This is used to prevent the wallet from being drained completely and to ensure that the
     /// faucet can continue to operate.
     pub fn drip_cap(&self) -> DripAmount {
-        match self {
-            FaucetInfo::MainnetFIL => self.drip_amount() * MAINNET_GLOBAL_DRIP_MULTIPLIER,
-            FaucetInfo::CalibnetFIL | FaucetInfo::CalibnetUSDFC | FaucetInfo::CalibnetDatacap => {
-                self.drip_amount() * CALIBNET_GLOBAL_DRIP_MULTIPLIER
-            }
-        }
+        self.drip_amount() * self.0.drip_cap_multiplier
     }
 
     /// Returns the maximum amount of tokens that can be claimed by the wallet per [`FaucetInfo::reset_limiter_seconds`].
     /// This is used to prevent the wallet from being drained completely and to ensure that the
     /// faucet can continue to operate.
     pub fn wallet_cap(&self) -> DripAmount {
-        match self {
-            FaucetInfo::MainnetFIL => self.drip_amount() * MAINNET_PER_WALLET_DRIP_MULTIPLIER,
-            FaucetInfo::CalibnetFIL | FaucetInfo::CalibnetUSDFC | FaucetInfo::CalibnetDatacap => {
-                self.drip_amount() * CALIBNET_PER_WALLET_DRIP_MULTIPLIER
-            }
-        }
+        self.drip_amount() * self.0.wallet_cap_multiplier
+    }
+
+    /// Returns the maximum amount of tokens that can be claimed from the same client IP (or IP
+    /// prefix) per [`FaucetInfo::reset_limiter_seconds`], regardless of the recipient wallets.
+    /// Wallets are free to generate, so this bounds what a single client can claim.
+    pub fn ip_cap(&self) -> DripAmount {
+        self.drip_amount() * self.0.ip_cap_multiplier
+    }
+
+    /// Returns the maximum number of drips the faucet sends per minute across all claimants, or
+    /// `None` if the throughput of the faucet is only bounded by [`FaucetInfo::drip_cap`].
+    pub fn max_drips_per_minute(&self) -> Option<u32> {
+        let limit = self.0.max_drips_per_minute;
+        (limit > 0).then_some(limit)
+    }
+
+    /// Returns the difficulty, in leading zero bits, of the proof-of-work challenge a claimant
+    /// has to solve before each drip, or `None` if claims do not require one.
+    pub fn pow_difficulty(&self) -> Option<u8> {
+        let difficulty = self.0.pow_difficulty;
+        (difficulty > 0).then_some(difficulty)
     }
 
     /// Returns the number of seconds after which the all drip cap resets for the faucet.
@@ -136,109 +139,113 @@ impl FaucetInfo {
         DRIP_CAP_RESET_SECONDS
     }
 
-    /// Returns the unit of the token for the given faucet.
-    pub fn unit(&self) -> &str {
-        match self {
-            FaucetInfo::MainnetFIL => "FIL",
-            FaucetInfo::CalibnetFIL => "tFIL",
-            FaucetInfo::CalibnetUSDFC => "tUSDFC",
-            FaucetInfo::CalibnetDatacap => "MiB",
-        }
+    /// Returns the configured unit of the token for the given faucet, if any. ERC-20 faucets
+    /// default to the symbol read from the token contract.
+    pub fn unit(&self) -> Option<&'static str> {
+        self.0.unit.as_deref()
     }
 
     /// Returns the the secret key label as configured in the CloudFlare Worker secrets.
     #[cfg(any(test, feature = "ssr"))]
-    pub fn secret_key_name(&self) -> &str {
-        match self {
-            FaucetInfo::CalibnetFIL => "SECRET_WALLET",
-            FaucetInfo::MainnetFIL => "SECRET_MAINNET_WALLET",
-            FaucetInfo::CalibnetUSDFC => "SECRET_CALIBNET_USDFC_WALLET",
-            FaucetInfo::CalibnetDatacap => "SECRET_CALIBNET_DATACAP_WALLET",
-        }
+    pub fn secret_key_name(&self) -> &'static str {
+        &self.0.secret_name
     }
 
-    /// Returns the network type for the given faucet. Note that there might be multiple faucets on
-    /// a given network, e.g., for ERC-20 tokens.
-    pub fn network(&self) -> Network {
-        match self {
-            FaucetInfo::MainnetFIL => Network::Mainnet,
-            FaucetInfo::CalibnetFIL | FaucetInfo::CalibnetUSDFC | FaucetInfo::CalibnetDatacap => {
-                Network::Testnet
-            }
-        }
+    /// Returns whether tokens of the faucet can be claimed with the claim API, besides its page.
+    #[cfg(any(test, feature = "ssr"))]
+    pub fn claim_api(&self) -> bool {
+        self.0.claim_api
+    }
+
+    /// Returns whether tokens of the faucet are claimed by `claim_token_all`.
+    #[cfg(any(test, feature = "ssr"))]
+    pub fn claim_all(&self) -> bool {
+        self.0.claim_all
+    }
+
+    /// Returns the network of the given faucet. Note that there might be multiple faucets on a
+    /// given network, e.g., for ERC-20 tokens.
+    pub fn network(&self) -> &'static NetworkConfig {
+        NetworkConfig::by_name(&self.0.network).expect("faucet networks are validated")
+    }
+
+    /// Returns the Turnstile site key if browser drips of the faucet require a human verification,
+    /// or `None` otherwise.
+    pub fn turnstile_site_key(&self) -> Option<&'static str> {
+        let site_key = option_env!("TURNSTILE_SITE_KEY").filter(|key| !key.is_empty())?;
+        self.0.human_verification.then_some(site_key)
     }
 
     /// Returns the base URL for transactions on the given faucet. This is used to link to
     /// transaction details in the block explorer.
     pub fn transaction_base_url(&self) -> Option<url::Url> {
-        let url_str = match self {
-            FaucetInfo::MainnetFIL => {
-                option_env!("FAUCET_TX_URL_MAINNET").unwrap_or("https://beryx.io/fil/mainnet/")
-            }
-            FaucetInfo::CalibnetFIL | FaucetInfo::CalibnetUSDFC | FaucetInfo::CalibnetDatacap => {
-                option_env!("FAUCET_TX_URL_CALIBNET").unwrap_or("https://beryx.io/fil/calibration/")
-            }
-        };
-        url::Url::parse(url_str).ok()
+        self.network().explorer_url.clone()
     }
 
     /// Returns the type of token for the given faucet. This is used to determine how the token
     /// is represented in the interface and how it is handled in the backend.
     pub fn token_type(&self) -> TokenType {
-        match self {
-            FaucetInfo::MainnetFIL | FaucetInfo::CalibnetFIL => TokenType::Native,
-            FaucetInfo::CalibnetUSDFC => TokenType::Erc20(
-                option_env!("CALIBNET_USDFC_CONTRACT_ADDRESS")
-                    .and_then(|addr| alloy::primitives::Address::from_str(addr).ok())
-                    // Default, as present in: https://stg.usdfc.net/#/
-                    .unwrap_or_else(|| address!("0xb3042734b608a1B16e9e86B374A3f3e389B4cDf0")),
-            ),
-            FaucetInfo::CalibnetDatacap => TokenType::Datacap,
-        }
+        self.0.token.clone()
     }
 
-    /// Returns the Ethereum chain ID for the given network. We could query the provider for this,
-    /// but since we know the chain ID for the networks we support, we can just return it directly
+    /// Returns the Ethereum chain ID of the network of the faucet. We could query the provider
+    /// for this, but since it is configured along with the network, we can just return it directly
     /// and avoid the overhead of a network request.
     #[cfg(any(test, feature = "ssr"))]
     pub fn chain_id(&self) -> u64 {
-        match self.network() {
-            Network::Mainnet => 314,    // https://chainlist.org/chain/314
-            Network::Testnet => 314159, // https://chainlist.org/chain/314159
-        }
+        self.network().chain_id
     }
 
     /// Returns the maximum allowed gas limit.
     #[allow(dead_code)]
     pub fn max_gas_limit(&self) -> u64 {
-        match self {
-            FaucetInfo::MainnetFIL => MAX_MAINNET_GAS_LIMIT,
-            FaucetInfo::CalibnetFIL | FaucetInfo::CalibnetUSDFC | FaucetInfo::CalibnetDatacap => {
-                MAX_CALIBNET_GAS_LIMIT
-            }
-        }
+        self.0.max_gas_limit
     }
 
     /// Returns the maximum allowed gas fee cap (in attoFIL).
     #[allow(dead_code)]
     pub fn max_gas_fee_cap(&self) -> TokenAmount {
-        match self {
-            FaucetInfo::MainnetFIL => MAX_MAINNET_GAS_FEE_CAP.clone(),
-            FaucetInfo::CalibnetFIL | FaucetInfo::CalibnetUSDFC | FaucetInfo::CalibnetDatacap => {
-                MAX_CALIBNET_GAS_FEE_CAP.clone()
-            }
-        }
+        TokenAmount::from_atto(self.0.max_gas_fee_cap)
     }
 
     /// Returns the maximum allowed gas premium (in attoFIL).
     #[allow(dead_code)]
     pub fn max_gas_premium(&self) -> TokenAmount {
-        match self {
-            FaucetInfo::MainnetFIL => MAX_MAINNET_GAS_PREMIUM.clone(),
-            FaucetInfo::CalibnetFIL | FaucetInfo::CalibnetUSDFC | FaucetInfo::CalibnetDatacap => {
-                MAX_CALIBNET_GAS_PREMIUM.clone()
-            }
-        }
+        TokenAmount::from_atto(self.0.max_gas_premium)
+    }
+
+    /// Returns the heading of the faucet page, with its icon.
+    pub fn title(&self) -> String {
+        format!("{} {}", self.0.icon, self.0.title)
+    }
+
+    /// Returns the title of the faucet page in the browser, with its icon.
+    pub fn page_title(&self) -> String {
+        format!("{} {}", self.0.icon, self.0.page_title)
+    }
+
+    /// Returns the label of the faucet in the faucet list, with its icon.
+    pub fn list_label(&self) -> String {
+        format!("{} {}", self.0.icon, self.0.list_label)
+    }
+
+    /// Returns the description of the faucet page.
+    pub fn description(&self) -> &'static str {
+        &self.0.description
+    }
+
+    /// Returns the additional paragraph of the faucet page, as HTML, if any.
+    pub fn about_html(&self) -> Option<&'static str> {
+        self.0.about_html.as_deref()
+    }
+
+    /// Returns a copy of the faucet with its configuration changed by `update`. The copy is not
+    /// part of the registry, so it is not found by its ID.
+    #[cfg(test)]
+    pub fn with_config(self, update: impl FnOnce(&mut FaucetConfig)) -> Self {
+        let mut config = self.0.clone();
+        update(&mut config);
+        Self(Box::leak(Box::new(config)))
     }
 }
 
@@ -249,16 +256,22 @@ mod tests {
     #[test]
     fn test_faucet_info() {
         // these tests are not exactly useful, but they give coverage and ensure that some warts with
-        // lazily initializing constants are caught.
-        let mainnet_faucet = FaucetInfo::MainnetFIL;
+        // lazily initializing the registry are caught.
+        let mainnet_faucet = FaucetInfo::from_str("MainnetFIL").unwrap();
+        let drip_amount = TokenAmount::from_nano(10_000_000);
         assert_eq!(
             mainnet_faucet.drip_amount(),
-            DripAmount::Token(MAINNET_DRIP_AMOUNT.clone())
+            DripAmount::Token(drip_amount.clone())
         );
+        assert_eq!(mainnet_faucet.slug(), "mainnet");
         assert_eq!(mainnet_faucet.rate_limit_seconds(), 600);
-        assert_eq!(mainnet_faucet.unit(), "FIL");
-        assert_eq!(mainnet_faucet.network(), Network::Mainnet);
+        assert_eq!(mainnet_faucet.max_drips_per_minute(), None);
+        assert_eq!(mainnet_faucet.pow_difficulty(), None);
+        assert_eq!(mainnet_faucet.turnstile_site_key(), None);
+        assert_eq!(mainnet_faucet.unit(), Some("FIL"));
+        assert_eq!(mainnet_faucet.network(), NetworkConfig::mainnet());
         assert_eq!(mainnet_faucet.secret_key_name(), "SECRET_MAINNET_WALLET");
+        assert!(!mainnet_faucet.claim_api());
         assert!(mainnet_faucet.transaction_base_url().is_some());
         assert_eq!(mainnet_faucet.token_type(), TokenType::Native);
         assert_eq!(mainnet_faucet.chain_id(), 314);
@@ -273,26 +286,32 @@ mod tests {
         );
         assert_eq!(
             mainnet_faucet.wallet_cap(),
-            DripAmount::Token(MAINNET_PER_WALLET_DRIP_MULTIPLIER * &*MAINNET_DRIP_AMOUNT)
+            DripAmount::Token(drip_amount.clone())
         );
         assert_eq!(
             mainnet_faucet.drip_cap(),
-            DripAmount::Token(MAINNET_GLOBAL_DRIP_MULTIPLIER * &*MAINNET_DRIP_AMOUNT)
+            DripAmount::Token(&drip_amount * 2)
         );
+        assert_eq!(mainnet_faucet.ip_cap(), DripAmount::Token(&drip_amount * 2));
 
-        let calibnet_fil_faucet = FaucetInfo::CalibnetFIL;
+        let calibnet_fil_faucet = FaucetInfo::from_str("CalibnetFIL").unwrap();
+        let drip_amount = TokenAmount::from_nano(5_000_000_000u64);
         assert_eq!(
             calibnet_fil_faucet.drip_amount(),
-            DripAmount::Token(CALIBNET_DRIP_AMOUNT.clone())
+            DripAmount::Token(drip_amount.clone())
         );
+        assert_eq!(calibnet_fil_faucet.slug(), "calibnet");
         assert_eq!(calibnet_fil_faucet.rate_limit_seconds(), 60);
-        assert_eq!(calibnet_fil_faucet.unit(), "tFIL");
-        assert_eq!(calibnet_fil_faucet.network(), Network::Testnet);
+        assert_eq!(calibnet_fil_faucet.max_drips_per_minute(), None);
+        assert_eq!(calibnet_fil_faucet.pow_difficulty(), None);
+        assert_eq!(calibnet_fil_faucet.unit(), Some("tFIL"));
+        assert_eq!(calibnet_fil_faucet.network(), NetworkConfig::calibnet());
         assert_eq!(calibnet_fil_faucet.secret_key_name(), "SECRET_WALLET");
+        assert!(calibnet_fil_faucet.claim_api());
         assert!(calibnet_fil_faucet.transaction_base_url().is_some());
         assert_eq!(calibnet_fil_faucet.token_type(), TokenType::Native);
         assert_eq!(calibnet_fil_faucet.chain_id(), 314159);
-        assert_eq!(calibnet_fil_faucet.max_gas_limit(), MAX_CALIBNET_GAS_LIMIT);
+        assert_eq!(calibnet_fil_faucet.max_gas_limit(), 100_000_000);
         assert_eq!(
             calibnet_fil_faucet.max_gas_fee_cap(),
             TokenAmount::from_atto(200_000)
@@ -303,26 +322,28 @@ mod tests {
         );
         assert_eq!(
             calibnet_fil_faucet.wallet_cap(),
-            DripAmount::Token(CALIBNET_PER_WALLET_DRIP_MULTIPLIER * &*CALIBNET_DRIP_AMOUNT)
+            DripAmount::Token(&drip_amount * 2)
         );
         assert_eq!(
             calibnet_fil_faucet.drip_cap(),
-            DripAmount::Token(CALIBNET_GLOBAL_DRIP_MULTIPLIER * &*CALIBNET_DRIP_AMOUNT)
+            DripAmount::Token(&drip_amount * 200)
+        );
+        assert_eq!(
+            calibnet_fil_faucet.ip_cap(),
+            DripAmount::Token(&drip_amount * 10)
         );
 
-        let calibnet_usdfc_faucet = FaucetInfo::CalibnetUSDFC;
+        let calibnet_usdfc_faucet = FaucetInfo::from_str("CalibnetUSDFC").unwrap();
         assert_eq!(
             calibnet_usdfc_faucet.drip_amount(),
-            DripAmount::Token(CALIBNET_USDFC_DRIP_AMOUNT.clone())
+            DripAmount::Token(drip_amount.clone())
         );
-        assert_eq!(calibnet_usdfc_faucet.rate_limit_seconds(), 60);
-        assert_eq!(calibnet_usdfc_faucet.unit(), "tUSDFC");
-        assert_eq!(calibnet_usdfc_faucet.network(), Network::Testnet);
+        assert_eq!(calibnet_usdfc_faucet.unit(), Some("tUSDFC"));
+        assert_eq!(calibnet_usdfc_faucet.network(), NetworkConfig::calibnet());
         assert_eq!(
             calibnet_usdfc_faucet.secret_key_name(),
             "SECRET_CALIBNET_USDFC_WALLET"
         );
-        assert!(calibnet_usdfc_faucet.transaction_base_url().is_some());
         assert_eq!(
             calibnet_usdfc_faucet.token_type(),
             TokenType::Erc20(
@@ -330,64 +351,41 @@ mod tests {
                     .unwrap()
             )
         );
-        assert_eq!(calibnet_usdfc_faucet.chain_id(), 314159);
-        assert_eq!(
-            calibnet_usdfc_faucet.max_gas_limit(),
-            MAX_CALIBNET_GAS_LIMIT
-        );
-        assert_eq!(
-            calibnet_usdfc_faucet.max_gas_premium(),
-            TokenAmount::from_atto(200_000)
-        );
-        assert_eq!(
-            calibnet_usdfc_faucet.max_gas_fee_cap(),
-            TokenAmount::from_atto(200_000)
-        );
-        assert_eq!(
-            calibnet_usdfc_faucet.wallet_cap(),
-            DripAmount::Token(CALIBNET_PER_WALLET_DRIP_MULTIPLIER * &*CALIBNET_USDFC_DRIP_AMOUNT)
-        );
-        assert_eq!(
-            calibnet_usdfc_faucet.drip_cap(),
-            DripAmount::Token(CALIBNET_GLOBAL_DRIP_MULTIPLIER * &*CALIBNET_USDFC_DRIP_AMOUNT)
-        );
+        assert!(calibnet_usdfc_faucet.about_html().is_some());
 
-        let calibnet_datacap_faucet = FaucetInfo::CalibnetDatacap;
+        let calibnet_datacap_faucet = FaucetInfo::by_slug("calibnet_datacap").unwrap();
+        let drip_amount = StoragePower::from(1_048_576);
+        assert_eq!(calibnet_datacap_faucet.to_string(), "CalibnetDatacap");
         assert_eq!(
             calibnet_datacap_faucet.drip_amount(),
-            DripAmount::Storage(CALIBNET_DATACAP_DRIP_AMOUNT.clone())
+            DripAmount::Storage(drip_amount.clone())
         );
-        assert_eq!(calibnet_datacap_faucet.rate_limit_seconds(), 60);
-        assert_eq!(calibnet_datacap_faucet.unit(), "MiB");
-        assert_eq!(calibnet_datacap_faucet.network(), Network::Testnet);
-        assert_eq!(
-            calibnet_datacap_faucet.secret_key_name(),
-            "SECRET_CALIBNET_DATACAP_WALLET"
-        );
-        assert!(calibnet_datacap_faucet.transaction_base_url().is_some());
+        assert_eq!(calibnet_datacap_faucet.unit(), Some("MiB"));
         assert_eq!(calibnet_datacap_faucet.token_type(), TokenType::Datacap);
-        assert_eq!(calibnet_datacap_faucet.chain_id(), 314159);
+        assert!(calibnet_datacap_faucet.claim_api());
+        assert!(!calibnet_datacap_faucet.claim_all());
         assert_eq!(
-            calibnet_datacap_faucet.max_gas_limit(),
-            MAX_CALIBNET_GAS_LIMIT
-        );
-        assert_eq!(
-            calibnet_datacap_faucet.max_gas_fee_cap(),
-            TokenAmount::from_atto(200_000)
-        );
-        assert_eq!(
-            calibnet_datacap_faucet.max_gas_premium(),
-            TokenAmount::from_atto(200_000)
+            calibnet_datacap_faucet.drip_cap(),
+            DripAmount::Storage(&drip_amount * 200)
         );
+
+        assert_eq!(FaucetInfo::all().count(), 4);
+        assert!(FaucetInfo::by_slug("unknown").is_none());
         assert_eq!(
-            calibnet_datacap_faucet.wallet_cap(),
-            DripAmount::Storage(
-                CALIBNET_PER_WALLET_DRIP_MULTIPLIER * &*CALIBNET_DATACAP_DRIP_AMOUNT
-            )
+            FaucetInfo::from_str("Unknown"),
+            Err(UnknownFaucet("Unknown".to_string()))
         );
+    }
+
+    #[test]
+    fn test_faucet_info_serialization() {
+        let faucet = FaucetInfo::from_str("CalibnetUSDFC").unwrap();
+        let serialized = serde_json::to_string(&faucet).unwrap();
+        assert_eq!(serialized, r#""CalibnetUSDFC""#);
         assert_eq!(
-            calibnet_datacap_faucet.drip_cap(),
-            DripAmount::Storage(CALIBNET_GLOBAL_DRIP_MULTIPLIER * &*CALIBNET_DATACAP_DRIP_AMOUNT)
+            serde_json::from_str::<FaucetInfo>(&serialized).unwrap(),
+            faucet
         );
+        assert!(serde_json::from_str::<FaucetInfo>(r#""Unknown""#).is_err());
     }
 }
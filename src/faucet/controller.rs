@@ -1,10 +1,14 @@
+use super::challenge::ChallengeSolution;
 use super::constants::FaucetInfo;
 use super::server_api::{
-    faucet_address, signed_datacap_allocation, signed_erc20_transfer, signed_fil_transfer,
+    claim_challenge, faucet_address, push_datacap_allocation, push_erc20_transfer,
+    push_fil_transfer,
 };
-use crate::faucet::model::FaucetModel;
+use crate::faucet::model::{Balances, FaucetModel};
 use crate::utils::address::AddressAlloyExt;
-use crate::utils::drip_amount::{DripAmount, TokenType};
+use crate::utils::conversions::TokenAmountAlloyExt as _;
+use crate::utils::drip_amount::{DripAmount, NATIVE_DECIMALS, TokenType};
+use crate::utils::erc20::{self, Erc20Metadata};
 use crate::utils::error::FaucetError;
 use crate::utils::lotus_json::LotusJson;
 use crate::utils::message::AddVerifiedClientParams;
@@ -15,6 +19,7 @@ use crate::utils::{
     error::catch_all,
     message::{message_grant_datacap, message_transfer},
 };
+use alloy::network::TransactionBuilder as _;
 use anyhow::bail;
 use fvm_ipld_encoding::RawBytes;
 use leptos::leptos_dom::logging::console_log;
@@ -22,6 +27,10 @@ use leptos::prelude::*;
 use leptos::task::spawn_local;
 use uuid::Uuid;
 
+/// Number of epochs searched for the execution of a sent message. Messages are polled for right
+/// after they are sent, so they are executed at most a few epochs ago.
+const RECENT_LOOKBACK_EPOCHS: i64 = 10;
+
 #[derive(Clone)]
 pub struct FaucetController {
     faucet: FaucetModel,
@@ -33,7 +42,7 @@ pub struct FaucetController {
 impl FaucetController {
     pub fn new(faucet_info: FaucetInfo) -> Self {
         let network = faucet_info.network();
-        fvm_shared::address::set_current_network(network);
+        fvm_shared::address::set_current_network(network.prefix);
         let rpc_context = RpcContext::use_context();
         let provider = rpc_context.provider();
         let balance_trigger = Trigger::new();
@@ -43,47 +52,61 @@ impl FaucetController {
         });
         let sender_address = RwSignal::new(String::new());
         let target_address = RwSignal::new(String::new());
-        let token_type = faucet_info.token_type();
-        let target_balance = LocalResource::new(move || {
-            provider.track();
-            let target_address = target_address.get();
-            balance_trigger.track();
-            let token_type = token_type.clone();
-            async move {
-                if let Ok(address) = parse_address(&target_address, network) {
-                    rpc_context
-                        .get()
-                        .wallet_balance(address, &token_type)
-                        .await
-                        .ok()
-                        .unwrap_or(DripAmount::zero(token_type))
-                } else {
-                    DripAmount::zero(token_type)
-                }
-            }
-        });
         let faucet_address = LocalResource::new(move || async move {
             faucet_address(faucet_info)
                 .await
                 .ok()
                 .and_then(|s| s.to_filecoin_address(network).ok())
         });
+        let token_metadata = LocalResource::new(move || {
+            provider.track();
+            let token_type = faucet_info.token_type();
+            async move {
+                let TokenType::Erc20(contract_address) = token_type else {
+                    return None;
+                };
+                rpc_context
+                    .get()
+                    .erc20_metadata(contract_address)
+                    .await
+                    .inspect_err(|e| {
+                        log::error!("Failed to read the metadata of {faucet_info}: {e}")
+                    })
+                    .ok()
+            }
+        });
         let token_type = faucet_info.token_type();
-        let faucet_balance = LocalResource::new(move || {
+        let balances = LocalResource::new(move || {
             provider.track();
+            let target_address = target_address.get();
             balance_trigger.track();
             let token_type = token_type.clone();
             async move {
-                if let Some(addr) = faucet_address.await {
+                let faucet_address = faucet_address.await;
+                if let Some(addr) = faucet_address {
                     sender_address.set(addr.to_string());
-                    rpc_context
-                        .get()
-                        .wallet_balance(addr, &token_type)
-                        .await
-                        .ok()
-                        .unwrap_or(DripAmount::zero(token_type))
-                } else {
-                    DripAmount::zero(token_type)
+                }
+                let target_address = parse_address(&target_address, network.prefix).ok();
+                // Both balances are fetched in one batch request.
+                let addresses = faucet_address
+                    .into_iter()
+                    .chain(target_address)
+                    .collect::<Vec<_>>();
+                let mut fetched = rpc_context
+                    .get()
+                    .wallet_balances(&addresses, &token_type)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter();
+                let mut next_balance = |address: Option<_>| {
+                    address
+                        .and_then(|_| fetched.next())
+                        .and_then(Result::ok)
+                        .unwrap_or(DripAmount::zero(token_type.clone()))
+                };
+                Balances {
+                    faucet: next_balance(faucet_address),
+                    target: next_balance(target_address),
                 }
             }
         });
@@ -93,10 +116,11 @@ impl FaucetController {
             sent_messages: RwSignal::new(Vec::new()),
             error_messages: RwSignal::new(Vec::new()),
             balance_trigger,
-            target_balance,
-            faucet_balance,
+            balances,
+            token_metadata,
             sender_address,
             target_address,
+            human_token: RwSignal::new(None),
         };
         Self {
             faucet,
@@ -126,7 +150,10 @@ impl FaucetController {
             for id in pending {
                 match id {
                     TransactionId::Native(cid) => {
-                        if let Some(lookup) = rpc.state_search_msg(cid).await? {
+                        if let Some(lookup) = rpc
+                            .state_search_msg(cid, Some(RECENT_LOOKBACK_EPOCHS))
+                            .await?
+                        {
                             messages.update(|messages| {
                                 for (cid, sent) in messages {
                                     if *cid == TransactionId::Native(lookup.message) {
@@ -154,8 +181,9 @@ impl FaucetController {
     }
     pub fn get_target_balance(&self) -> DripAmount {
         self.faucet
-            .target_balance
+            .balances
             .get()
+            .map(|balances| balances.target)
             .unwrap_or(DripAmount::zero(self.info.token_type()))
     }
 
@@ -167,8 +195,31 @@ impl FaucetController {
         self.faucet.target_address.get()
     }
 
+    pub fn get_faucet_info(&self) -> FaucetInfo {
+        self.info
+    }
+
+    /// Returns the metadata of the token, once read, if the faucet dispenses an ERC-20 token.
+    pub fn get_token_metadata(&self) -> Option<Erc20Metadata> {
+        self.faucet.token_metadata.get().flatten()
+    }
+
+    /// Returns the unit of the token, i.e., the configured one or else the symbol of the ERC-20
+    /// token.
     pub fn get_fil_unit(&self) -> String {
-        self.info.unit().to_string()
+        match self.info.unit() {
+            Some(unit) => unit.to_string(),
+            None => self
+                .get_token_metadata()
+                .map(|metadata| metadata.symbol)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns the number of decimals of the token, used to format its amounts.
+    pub fn get_decimals(&self) -> u8 {
+        self.get_token_metadata()
+            .map_or(NATIVE_DECIMALS, |metadata| metadata.decimals)
     }
 
     pub fn set_target_address(&self, address: String) {
@@ -177,8 +228,9 @@ impl FaucetController {
 
     pub fn get_faucet_balance(&self) -> DripAmount {
         self.faucet
-            .faucet_balance
+            .balances
             .get()
+            .map(|balances| balances.faucet)
             .unwrap_or(DripAmount::zero(self.info.token_type()))
     }
 
@@ -225,7 +277,23 @@ impl FaucetController {
         target_balance < drip_amount
     }
 
+    /// Returns the Turnstile site key if drips require a human verification.
+    pub fn get_turnstile_site_key(&self) -> Option<&'static str> {
+        self.info.turnstile_site_key()
+    }
+
+    /// Sets the token of the human verification passed along with the next drip.
+    pub fn set_human_token(&self, token: Option<String>) {
+        self.faucet.human_token.set(token);
+    }
+
     pub fn drip(&self) {
+        if self.info.turnstile_site_key().is_some()
+            && self.faucet.human_token.get_untracked().is_none()
+        {
+            self.add_error_message("Please complete the human verification first".to_string());
+            return;
+        }
         match self.info.token_type() {
             TokenType::Native => self.drip_native_token(),
             TokenType::Erc20(_) => self.drip_erc20_token(),
@@ -238,7 +306,8 @@ impl FaucetController {
         let network = self.info.network();
         let info = self.info;
         let rpc_context = RpcContext::use_context();
-        match parse_address(&self.faucet.target_address.get(), network) {
+        let human_token = self.faucet.human_token.get_untracked();
+        match parse_address(&self.faucet.target_address.get(), network.prefix) {
             Ok(recipient) => {
                 spawn_local(async move {
                     catch_all(faucet.error_messages, async move {
@@ -248,37 +317,40 @@ impl FaucetController {
                             bail!("Expected DripAmount::Token variant")
                         };
                         let rpc = rpc_context.get();
-                        let id_address = rpc.lookup_id(recipient).await.unwrap_or(recipient);
                         let from = faucet_address(info)
                             .await
                             .map_err(|e| anyhow::anyhow!("Error getting faucet address: {}", e))?
                             .to_filecoin_address(network)?;
-                        let nonce = rpc.mpool_get_nonce(from).await?;
-                        let raw_msg = message_transfer(from, id_address, drip_amount);
-                        let msg = rpc.estimate_gas(raw_msg).await?;
-                        match signed_fil_transfer(
+                        let (id_address, _, msg) = rpc
+                            .prepare_message(message_transfer(from, recipient, drip_amount))
+                            .await?;
+                        let challenge = solve_challenge(info, id_address.to_string()).await?;
+                        match push_fil_transfer(
                             LotusJson(id_address),
                             msg.gas_limit,
                             LotusJson(msg.gas_fee_cap),
                             LotusJson(msg.gas_premium),
-                            nonce,
                             info,
+                            challenge,
+                            human_token,
                         )
                         .await
                         {
-                            Ok(LotusJson(smsg)) => {
-                                let cid = rpc.mpool_push(smsg).await?;
+                            Ok(LotusJson(cid)) => {
                                 faucet.sent_messages.update(|messages| {
                                     messages.push((TransactionId::Native(cid), false));
                                 });
                                 log::info!("Sent message: {:?}", cid);
                             }
                             Err(e) => {
-                                log::error!("Error signing {info} transaction: {e}");
-                                if let FaucetError::RateLimited { retry_after_secs } = e {
+                                log::error!("Error sending {info} transaction: {e}");
+                                if let FaucetError::RateLimited {
+                                    retry_after_secs, ..
+                                } = e
+                                {
                                     faucet.send_limited.set(retry_after_secs);
                                 }
-                                bail!("Failed to sign {info} transaction: {e}");
+                                bail!("Failed to send {info} transaction: {e}");
                             }
                         }
                         Ok(())
@@ -303,7 +375,8 @@ impl FaucetController {
         let network = self.info.network();
         let info = self.info;
         let rpc_context = RpcContext::use_context();
-        match parse_address(&self.faucet.target_address.get(), network) {
+        let human_token = self.faucet.human_token.get_untracked();
+        match parse_address(&self.faucet.target_address.get(), network.prefix) {
             Ok(recipient) => {
                 spawn_local(async move {
                     catch_all(faucet.error_messages, async move {
@@ -315,23 +388,41 @@ impl FaucetController {
                             .map_err(|e| anyhow::anyhow!("Error getting faucet address: {}", e))?
                             .to_filecoin_address(network)?;
                         let eth_to = recipient.into_eth_address()?;
-                        let nonce = filecoin_rpc.mpool_get_nonce(owner_fil_address).await?;
-                        let gas_price = filecoin_rpc.gas_price().await?;
-                        match signed_erc20_transfer(eth_to, nonce, gas_price, info).await {
-                            Ok(signed) => {
-                                let tx_id =
-                                    filecoin_rpc.send_eth_transaction_signed(&signed).await?;
+                        let (TokenType::Erc20(contract_address), DripAmount::Token(amount)) =
+                            (info.token_type(), info.drip_amount())
+                        else {
+                            bail!("{info} is not an ERC-20 faucet");
+                        };
+                        let decimals = filecoin_rpc
+                            .erc20_metadata(contract_address)
+                            .await?
+                            .decimals;
+                        // The server builds the transaction itself, this one is only estimated.
+                        let estimate = erc20::transfer_request(
+                            contract_address,
+                            eth_to,
+                            amount.to_alloy_amount(decimals),
+                        )
+                        .with_from(owner_fil_address.into_eth_address()?);
+                        let fees = filecoin_rpc.estimate_eth_fees(&estimate).await?;
+                        let challenge = solve_challenge(info, eth_to.to_string()).await?;
+                        match push_erc20_transfer(eth_to, fees, info, challenge, human_token).await
+                        {
+                            Ok(tx_id) => {
                                 faucet.sent_messages.update(|messages| {
                                     messages.push((TransactionId::Eth(tx_id), false));
                                 });
                                 console_log(&format!("Transaction sent successfully: {tx_id}"));
                             }
                             Err(e) => {
-                                log::error!("Error signing {info} transaction: {e}");
-                                if let FaucetError::RateLimited { retry_after_secs } = e {
+                                log::error!("Error sending {info} transaction: {e}");
+                                if let FaucetError::RateLimited {
+                                    retry_after_secs, ..
+                                } = e
+                                {
                                     faucet.send_limited.set(retry_after_secs);
                                 }
-                                bail!("Failed to sign {info} transaction: {e}");
+                                bail!("Failed to send {info} transaction: {e}");
                             }
                         }
                         Ok(())
@@ -355,7 +446,8 @@ impl FaucetController {
         let network = self.info.network();
         let info = self.info;
         let rpc_context = RpcContext::use_context();
-        match parse_address(&self.faucet.target_address.get(), network) {
+        let human_token = self.faucet.human_token.get_untracked();
+        match parse_address(&self.faucet.target_address.get(), network.prefix) {
             Ok(recipient) => {
                 spawn_local(async move {
                     catch_all(faucet.error_messages, async move {
@@ -365,44 +457,52 @@ impl FaucetController {
                             bail!("Expected DripAmount::Storage variant")
                         };
                         let rpc = rpc_context.get();
-                        let id_address = rpc.lookup_id(recipient).await.unwrap_or(recipient);
                         let from = faucet_address(info)
                             .await
                             .map_err(|e| anyhow::anyhow!("Error getting faucet address: {}", e))?
                             .to_filecoin_address(network)?;
-                        let nonce = rpc.mpool_get_nonce(from).await?;
+                        // Fails early instead of solving the challenge for a drip the server
+                        // rejects anyway.
+                        rpc.datacap_status(from, recipient)
+                            .await?
+                            .check_allocation(&allowance)
+                            .map_err(FaucetError::Datacap)?;
                         let params = AddVerifiedClientParams {
-                            address: id_address,
+                            address: recipient,
                             allowance,
                         };
                         let raw_msg = message_grant_datacap(
                             from,
                             RawBytes::new(fvm_ipld_encoding::to_vec(&params)?),
                         );
-                        let msg = rpc.estimate_gas(raw_msg).await?;
-                        match signed_datacap_allocation(
-                            LotusJson(id_address),
+                        let (_, _, msg) = rpc.prepare_message(raw_msg).await?;
+                        let challenge = solve_challenge(info, recipient.to_string()).await?;
+                        match push_datacap_allocation(
+                            LotusJson(recipient),
                             msg.gas_limit,
                             LotusJson(msg.gas_fee_cap),
                             LotusJson(msg.gas_premium),
-                            nonce,
                             info,
+                            challenge,
+                            human_token,
                         )
                         .await
                         {
-                            Ok(LotusJson(smsg)) => {
-                                let cid = rpc.mpool_push(smsg).await?;
+                            Ok(LotusJson(cid)) => {
                                 faucet.sent_messages.update(|messages| {
                                     messages.push((TransactionId::Native(cid), false));
                                 });
                                 log::info!("Sent message: {:?}", cid);
                             }
                             Err(e) => {
-                                log::error!("Error signing {info} transaction: {e}");
-                                if let FaucetError::RateLimited { retry_after_secs } = e {
+                                log::error!("Error sending {info} transaction: {e}");
+                                if let FaucetError::RateLimited {
+                                    retry_after_secs, ..
+                                } = e
+                                {
                                     faucet.send_limited.set(retry_after_secs);
                                 }
-                                bail!("Failed to sign {info} transaction: {e}");
+                                bail!("Failed to send {info} transaction: {e}");
                             }
                         }
                         Ok(())
@@ -422,3 +522,18 @@ impl FaucetController {
         }
     }
 }
+
+/// Fetches and solves the proof-of-work challenge for a drip to `address`, if the faucet requires
+/// one.
+async fn solve_challenge(
+    info: FaucetInfo,
+    address: String,
+) -> anyhow::Result<Option<ChallengeSolution>> {
+    if info.pow_difficulty().is_none() {
+        return Ok(None);
+    }
+    let challenge = claim_challenge(info, address)
+        .await
+        .map_err(|e| anyhow::anyhow!("Error getting proof-of-work challenge: {}", e))?;
+    Ok(Some(challenge.solve().await))
+}
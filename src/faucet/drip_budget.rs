@@ -0,0 +1,809 @@
+#![cfg(feature = "ssr")]
+//! Coordinator tracking the global drip budget of a faucet. Per-wallet limits are enforced by the
+//! [`super::rate_limiter`] objects; this object only ensures that the faucet stays within its
+//! [`FaucetInfo::drip_cap`] and [`FaucetInfo::max_drips_per_minute`]. Claims made with an API key
+//! are accounted to a separate object per key, capped by [`ApiKeyQuota::drip_cap`] instead.
+
+use super::api_key::{ApiKeyQuota, drip_amount, split_quota};
+use super::rate_limiter::{
+    RESERVATION_TTL_SECONDS, RateLimiterReply, RateLimiterStorage, ensure_reset_alarm,
+    parse_reservation_path, reservation_key, seconds_until_alarm,
+};
+use crate::faucet::constants::FaucetInfo;
+use crate::utils::drip_amount::DripAmount;
+use crate::utils::error::RateLimitReason;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use worker::*;
+
+/// Length of the sliding window used for [`FaucetInfo::max_drips_per_minute`].
+const THROUGHPUT_WINDOW_SECONDS: i64 = 60;
+
+/// Storage key of the timestamps (seconds) of the drips sent within the throughput window.
+const RECENT_DRIPS_KEY: &str = "recent_drips";
+
+/// Part of the drip budget held on behalf of an admitted request. It shares the ID of the
+/// reservation made by the claimant's rate limiter, so both are settled together.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct BudgetReservation {
+    /// Identifier of the claimant the budget was reserved for.
+    id: String,
+    /// Amount added to the `dripped` counter.
+    amount: DripAmount,
+    /// Unix timestamp (seconds) at which the reservation was made. Also recorded in the recent
+    /// drips if the faucet has a throughput limit.
+    reserved_at: i64,
+    /// Unix timestamp (seconds) after which the reservation can no longer be released.
+    expires_at: i64,
+}
+
+/// Outcome of [`DripBudgetCore::get_budget`].
+#[derive(Debug)]
+enum BudgetStatus {
+    /// The drip fits into the budget. Contains the counters the reservation is based on.
+    Allowed {
+        dripped: DripAmount,
+        recent_drips: Vec<i64>,
+    },
+    /// The budget is exhausted for the given reason.
+    Limited {
+        reason: RateLimitReason,
+        retry_after: i64,
+    },
+}
+
+/// Core logic for the global drip budget, generic over a storage backend.
+/// There is a single instance per faucet. It is used by the [`DripBudget`] durable object handler
+/// in production and by mocks in tests.
+struct DripBudgetCore<S: RateLimiterStorage> {
+    storage: S,
+    /// Throughput limit applied instead of the one of the faucet, as none of the default faucets
+    /// has one.
+    #[cfg(test)]
+    max_drips_per_minute: Option<u32>,
+}
+
+impl<S: RateLimiterStorage> DripBudgetCore<S> {
+    fn new(storage: S) -> Self {
+        Self {
+            storage,
+            #[cfg(test)]
+            max_drips_per_minute: None,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_max_drips_per_minute(storage: S, max_drips_per_minute: u32) -> Self {
+        Self {
+            storage,
+            max_drips_per_minute: Some(max_drips_per_minute),
+        }
+    }
+
+    /// Returns the throughput limit of the faucet, see [`FaucetInfo::max_drips_per_minute`].
+    fn max_drips_per_minute(&self, faucet_info: &FaucetInfo) -> Option<u32> {
+        #[cfg(test)]
+        if self.max_drips_per_minute.is_some() {
+            return self.max_drips_per_minute;
+        }
+        faucet_info.max_drips_per_minute()
+    }
+
+    /// Returns the timestamps of the drips sent within the throughput window ending at `now`.
+    /// Always empty if the faucet has no throughput limit.
+    async fn get_recent_drips(&self, faucet_info: &FaucetInfo, now: DateTime<Utc>) -> Vec<i64> {
+        if self.max_drips_per_minute(faucet_info).is_none() {
+            return Vec::new();
+        }
+        let window_start = now.timestamp() - THROUGHPUT_WINDOW_SECONDS;
+        self.storage
+            .get::<Vec<i64>>(RECENT_DRIPS_KEY)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|&timestamp| timestamp > window_start)
+            .collect()
+    }
+
+    /// Checks the drip cap first and the faucet throughput second. The first limit hit is
+    /// reported.
+    async fn get_budget(
+        &self,
+        faucet_info: &FaucetInfo,
+        quota: Option<&ApiKeyQuota>,
+        now: DateTime<Utc>,
+    ) -> BudgetStatus {
+        let dripped = self
+            .storage
+            .get::<DripAmount>("dripped")
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(DripAmount::zero(faucet_info.token_type()));
+        let (drip_cap, reason) = match quota {
+            Some(quota) => (quota.drip_cap(faucet_info), RateLimitReason::KeyCap),
+            None => (faucet_info.drip_cap(), RateLimitReason::DripCap),
+        };
+        if dripped >= drip_cap {
+            let retry_after = seconds_until_alarm(&self.storage, now).await;
+            log::info!(
+                "{faucet_info} Drip budget invoked: Drip capped now={now:?}, dripped={dripped:?}, retry_after={retry_after:?}"
+            );
+            return BudgetStatus::Limited {
+                reason,
+                retry_after,
+            };
+        }
+        let recent_drips = self.get_recent_drips(faucet_info, now).await;
+        if let Some(max_drips) = self.max_drips_per_minute(faucet_info)
+            && recent_drips.len() >= max_drips as usize
+        {
+            // The oldest drip leaving the window frees up a slot.
+            let oldest = recent_drips
+                .iter()
+                .min()
+                .copied()
+                .unwrap_or(now.timestamp());
+            let retry_after = (oldest + THROUGHPUT_WINDOW_SECONDS - now.timestamp()).max(0);
+            log::info!(
+                "{faucet_info} Drip budget invoked: Throughput limited now={now:?}, recent_drips={}, retry_after={retry_after:?}",
+                recent_drips.len()
+            );
+            return BudgetStatus::Limited {
+                reason: RateLimitReason::Throughput,
+                retry_after,
+            };
+        }
+        BudgetStatus::Allowed {
+            dripped,
+            recent_drips,
+        }
+    }
+
+    /// Takes a drip out of the budget for the reservation given in the path, e.g.,
+    /// `/reserve/{faucet_info}/{id}/{reservation_id}`. Claims made with an API key carry the key's
+    /// quota in the query, see [`ApiKeyQuota::to_query`].
+    async fn handle_request(&self, url: &str, now: DateTime<Utc>) -> Result<RateLimiterReply> {
+        let (path, quota) = split_quota(url)?;
+        let (faucet_info, id, reservation_id) = parse_reservation_path(path)?;
+        let (dripped, mut recent_drips) =
+            match self.get_budget(&faucet_info, quota.as_ref(), now).await {
+                BudgetStatus::Allowed {
+                    dripped,
+                    recent_drips,
+                } => (dripped, recent_drips),
+                BudgetStatus::Limited {
+                    reason,
+                    retry_after,
+                } => {
+                    return Ok(RateLimiterReply::Limited {
+                        retry_after,
+                        reason,
+                    });
+                }
+            };
+        let drip_amount = drip_amount(&faucet_info, quota.as_ref());
+        let updated_dripped = &dripped + &drip_amount;
+        self.storage.put("dripped", updated_dripped.clone()).await?;
+        if self.max_drips_per_minute(&faucet_info).is_some() {
+            recent_drips.push(now.timestamp());
+            self.storage.put(RECENT_DRIPS_KEY, recent_drips).await?;
+        }
+        ensure_reset_alarm(&self.storage, &faucet_info).await?;
+        let reservation = BudgetReservation {
+            id: id.clone(),
+            amount: drip_amount,
+            reserved_at: now.timestamp(),
+            expires_at: (now + Duration::seconds(RESERVATION_TTL_SECONDS)).timestamp(),
+        };
+        self.storage
+            .put(&reservation_key(&reservation_id), reservation)
+            .await?;
+        log::info!(
+            "{faucet_info} Drip budget for {id} set: now={now:?}, dripped={updated_dripped:?}"
+        );
+        Ok(RateLimiterReply::Reserved(reservation_id))
+    }
+
+    /// Marks the reservation as final; the budget stays consumed. Returns `false` if the
+    /// reservation is unknown.
+    async fn handle_commit(&self, path: &str) -> Result<bool> {
+        let (faucet_info, id, reservation_id) = parse_reservation_path(path)?;
+        if self
+            .get_reservation(&faucet_info, &id, &reservation_id, "commit")
+            .await?
+            .is_none()
+        {
+            return Ok(false);
+        }
+        self.storage
+            .delete(&reservation_key(&reservation_id))
+            .await?;
+        Ok(true)
+    }
+
+    /// Looks up a budget reservation, checking that it belongs to the claimant `id`. Returns
+    /// `None` if the reservation is unknown.
+    async fn get_reservation(
+        &self,
+        faucet_info: &FaucetInfo,
+        id: &str,
+        reservation_id: &str,
+        action: &str,
+    ) -> Result<Option<BudgetReservation>> {
+        let Some(reservation) = self
+            .storage
+            .get::<BudgetReservation>(&reservation_key(reservation_id))
+            .await?
+        else {
+            log::warn!(
+                "{faucet_info} Budget reservation {reservation_id} for {id} not found on {action}"
+            );
+            return Ok(None);
+        };
+        if reservation.id != id {
+            return Err(Error::RustError(format!(
+                "Budget reservation {reservation_id} does not belong to {id}"
+            )));
+        }
+        Ok(Some(reservation))
+    }
+
+    /// Extends the reservation to [`RESERVATION_TTL_SECONDS`] from now, see
+    /// [`super::rate_limiter`]. Returns `false` if the reservation is unknown.
+    async fn handle_extend(&self, path: &str, now: DateTime<Utc>) -> Result<bool> {
+        let (faucet_info, id, reservation_id) = parse_reservation_path(path)?;
+        let Some(mut reservation) = self
+            .get_reservation(&faucet_info, &id, &reservation_id, "extend")
+            .await?
+        else {
+            return Ok(false);
+        };
+        reservation.expires_at = (now + Duration::seconds(RESERVATION_TTL_SECONDS)).timestamp();
+        self.storage
+            .put(&reservation_key(&reservation_id), reservation)
+            .await?;
+        Ok(true)
+    }
+
+    /// Gives the reserved drip back to the budget and frees its throughput slot. Returns `false`
+    /// if nothing was refunded, either because the reservation is unknown or because it expired.
+    async fn handle_release(&self, path: &str, now: DateTime<Utc>) -> Result<bool> {
+        let (faucet_info, id, reservation_id) = parse_reservation_path(path)?;
+        let Some(reservation) = self
+            .get_reservation(&faucet_info, &id, &reservation_id, "release")
+            .await?
+        else {
+            return Ok(false);
+        };
+        self.storage
+            .delete(&reservation_key(&reservation_id))
+            .await?;
+        if reservation.expires_at <= now.timestamp() {
+            log::warn!(
+                "{faucet_info} Budget reservation {reservation_id} for {id} expired, budget stays consumed"
+            );
+            return Ok(false);
+        }
+
+        let zero = DripAmount::zero(faucet_info.token_type());
+        let dripped = self
+            .storage
+            .get::<DripAmount>("dripped")
+            .await?
+            .unwrap_or(zero.clone());
+        let refunded_dripped = (&dripped - &reservation.amount).max(zero);
+        self.storage
+            .put("dripped", refunded_dripped.clone())
+            .await?;
+        if self.max_drips_per_minute(&faucet_info).is_some() {
+            let mut recent_drips = self
+                .storage
+                .get::<Vec<i64>>(RECENT_DRIPS_KEY)
+                .await?
+                .unwrap_or_default();
+            if let Some(pos) = recent_drips
+                .iter()
+                .position(|&timestamp| timestamp == reservation.reserved_at)
+            {
+                recent_drips.remove(pos);
+                self.storage.put(RECENT_DRIPS_KEY, recent_drips).await?;
+            }
+        }
+        log::info!(
+            "{faucet_info} Budget reservation {reservation_id} for {id} released: dripped={refunded_dripped:?}"
+        );
+        Ok(true)
+    }
+
+    async fn handle_alarm(&self) -> Result<()> {
+        log::info!("Drip budget alarm triggered. DurableObject will be deleted.");
+        self.storage.delete_all().await
+    }
+}
+
+/// Durable object holding the global drip budget of a faucet. It is addressed by the faucet name,
+/// or by `{faucet_info}/key/{key_id}` for the budget of an API key.
+#[cfg(not(test))]
+#[durable_object]
+pub struct DripBudget {
+    #[cfg(not(test))]
+    state: State,
+}
+
+#[cfg(not(test))]
+impl DripBudget {
+    fn create_core(&self) -> DripBudgetCore<super::rate_limiter::DurableObjectStorage<'_>> {
+        DripBudgetCore::new(super::rate_limiter::DurableObjectStorage::new(&self.state))
+    }
+}
+
+#[cfg(not(test))]
+impl DurableObject for DripBudget {
+    fn new(state: State, _env: Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&self, req: Request) -> Result<Response> {
+        let now = Utc::now();
+        let path = req.path();
+        let core = self.create_core();
+        match path.split('/').nth(1) {
+            Some("commit") => Response::from_json(&core.handle_commit(&path).await?),
+            Some("release") => Response::from_json(&core.handle_release(&path, now).await?),
+            Some("extend") => Response::from_json(&core.handle_extend(&path, now).await?),
+            _ => Response::from_json(&core.handle_request(req.url()?.as_str(), now).await?),
+        }
+    }
+
+    async fn alarm(&self) -> Result<Response> {
+        self.create_core().handle_alarm().await.ok();
+        Response::ok("OK")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::faucet::rate_limiter::MockRateLimiterStorage;
+    use fvm_shared::econ::TokenAmount;
+
+    const CALIBNET_PER_WALLET_DRIP_MULTIPLIER: i64 = 2;
+
+    /// Throughput limit of the faucet in the tests of the throughput window.
+    const TEST_MAX_DRIPS_PER_MINUTE: u32 = 30;
+
+    /// Configuration for mock storage used in drip budget tests.
+    struct MockStorageConfig {
+        dripped: Option<DripAmount>,
+        recent_drips: Option<Vec<i64>>,
+        alarm: Option<i64>,
+        // If true, expect puts and set_alarm (for allowed requests)
+        expect_puts: bool,
+    }
+
+    fn new_mock_storage(config: MockStorageConfig) -> MockRateLimiterStorage {
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<DripAmount>()
+            .with(mockall::predicate::eq("dripped"))
+            .returning(move |_| Ok(config.dripped.clone()));
+        mock_storage
+            .expect_get::<Vec<i64>>()
+            .with(mockall::predicate::eq(RECENT_DRIPS_KEY))
+            .returning(move |_| Ok(config.recent_drips.clone()));
+        mock_storage
+            .expect_get_alarm()
+            .returning(move || Ok(config.alarm));
+        if config.expect_puts {
+            mock_storage
+                .expect_put::<DripAmount>()
+                .with(
+                    mockall::predicate::eq("dripped"),
+                    mockall::predicate::always(),
+                )
+                .returning(|_, _| Ok(()));
+            mock_storage
+                .expect_put::<Vec<i64>>()
+                .with(
+                    mockall::predicate::eq(RECENT_DRIPS_KEY),
+                    mockall::predicate::always(),
+                )
+                .returning(|_, _| Ok(()));
+            mock_storage.expect_set_alarm().returning(|_| Ok(()));
+            mock_storage
+                .expect_put::<BudgetReservation>()
+                .with(
+                    mockall::predicate::function(|key: &str| key.starts_with("reservation_")),
+                    mockall::predicate::always(),
+                )
+                .returning(|_, _| Ok(()));
+        }
+        mock_storage
+    }
+
+    fn reserve_path(faucet_info: FaucetInfo, wallet_id: &str) -> String {
+        format!(
+            "http://do/reserve/{faucet_info}/{wallet_id}/{}",
+            uuid::Uuid::new_v4()
+        )
+    }
+
+    /// Checks that the initial request takes a drip out of an untouched budget.
+    #[tokio::test]
+    async fn test_drip_budget_initial_request() {
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            dripped: None,
+            recent_drips: None,
+            alarm: None,
+            expect_puts: true,
+        });
+        let core = DripBudgetCore::new(mock_storage);
+        let reply = core
+            .handle_request("http://do/reserve/CalibnetFIL/test_wallet/abc", Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(reply, RateLimiterReply::Reserved("abc".to_string()));
+    }
+
+    /// Checks that the budget of an API key is capped by the key's quota rather than by the drip
+    /// cap of the faucet.
+    #[tokio::test]
+    async fn test_drip_budget_api_key_cap() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let quota = ApiKeyQuota {
+            drip_multiplier: 1,
+            wallet_cap: 2,
+            drip_cap: 1000,
+        };
+        let path = format!(
+            "{}?{}",
+            reserve_path(faucet_info, "test_wallet"),
+            quota.to_query()
+        );
+
+        let core = DripBudgetCore::new(new_mock_storage(MockStorageConfig {
+            dripped: Some(faucet_info.drip_cap()),
+            recent_drips: None,
+            alarm: None,
+            expect_puts: true,
+        }));
+        let reply = core.handle_request(&path, Utc::now()).await.unwrap();
+        assert!(reply.retry_after().is_none());
+
+        let core = DripBudgetCore::new(new_mock_storage(MockStorageConfig {
+            dripped: Some(quota.drip_cap(&faucet_info)),
+            recent_drips: None,
+            alarm: None,
+            expect_puts: false,
+        }));
+        let reply = core.handle_request(&path, Utc::now()).await.unwrap();
+        assert_eq!(reply.reason(), Some(RateLimitReason::KeyCap));
+    }
+
+    /// Checks that a request is rate limited if the global drip cap is exceeded.
+    #[tokio::test]
+    async fn test_drip_budget_drip_cap_exceeded() {
+        let now = Utc::now();
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let exceeded_amount =
+            &faucet_info.drip_cap() + &DripAmount::Token(TokenAmount::from_whole(1));
+        let alarm_time = now.timestamp_millis() + 7200 * 1000; // 2 hours from now
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            dripped: Some(exceeded_amount),
+            recent_drips: None,
+            alarm: Some(alarm_time),
+            expect_puts: false,
+        });
+        let core = DripBudgetCore::new(mock_storage);
+        let reply = core
+            .handle_request(&reserve_path(faucet_info, "test_wallet"), now)
+            .await
+            .unwrap();
+        assert_eq!(reply.reason(), Some(RateLimitReason::DripCap));
+        let retry_after = reply.retry_after().unwrap();
+        assert!(retry_after > 0);
+        assert!(retry_after <= 7200);
+    }
+
+    /// Simulates multiple users claiming up to the global drip cap, ensuring all are rate limited
+    /// at the cap and that the throughput limit applies across users in between.
+    #[tokio::test]
+    async fn test_multiple_user_journey_to_drip_cap() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let wallet_1 = "wallet_1";
+        let wallet_2 = "wallet_2";
+        let wallet_3 = "wallet_3";
+        let drip_amount = faucet_info.drip_amount();
+        let drip_cap = faucet_info.drip_cap();
+        let mut dripped = DripAmount::zero(faucet_info.token_type());
+
+        // Wallets 1 and 2: Up to their wallet caps
+        for wallet in [wallet_1, wallet_2] {
+            for _ in 0..CALIBNET_PER_WALLET_DRIP_MULTIPLIER {
+                if dripped >= drip_cap {
+                    break;
+                }
+                let mock_storage = new_mock_storage(MockStorageConfig {
+                    dripped: Some(dripped.clone()),
+                    recent_drips: None,
+                    alarm: None,
+                    expect_puts: true,
+                });
+                let core = DripBudgetCore::with_max_drips_per_minute(
+                    mock_storage,
+                    TEST_MAX_DRIPS_PER_MINUTE,
+                );
+                let result = core
+                    .handle_request(&reserve_path(faucet_info, wallet), Utc::now())
+                    .await
+                    .unwrap()
+                    .retry_after();
+                assert!(result.is_none());
+                dripped += &drip_amount;
+            }
+        }
+        // Wallet 3: Throttled while the faucet is at its throughput limit, even though the drip
+        // cap is not reached
+        let max_drips = TEST_MAX_DRIPS_PER_MINUTE as i64;
+        let now = Utc::now();
+        let busy_window: Vec<i64> = (0..max_drips).map(|i| now.timestamp() - i % 30).collect();
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            dripped: Some(dripped.clone()),
+            recent_drips: Some(busy_window),
+            alarm: None,
+            expect_puts: false,
+        });
+        let core =
+            DripBudgetCore::with_max_drips_per_minute(mock_storage, TEST_MAX_DRIPS_PER_MINUTE);
+        let reply = core
+            .handle_request(&reserve_path(faucet_info, wallet_3), now)
+            .await
+            .unwrap();
+        assert_eq!(reply.reason(), Some(RateLimitReason::Throughput));
+        let retry_after = reply.retry_after().unwrap();
+        assert!(retry_after > 0 && retry_after <= THROUGHPUT_WINDOW_SECONDS);
+        // Wallet 3: Up to drip cap, drips older than the window no longer count towards the
+        // throughput limit
+        while dripped < drip_cap {
+            let stale_window: Vec<i64> = (0..max_drips)
+                .map(|i| now.timestamp() - THROUGHPUT_WINDOW_SECONDS - i)
+                .collect();
+            let mock_storage = new_mock_storage(MockStorageConfig {
+                dripped: Some(dripped.clone()),
+                recent_drips: Some(stale_window),
+                alarm: None,
+                expect_puts: true,
+            });
+            let core =
+                DripBudgetCore::with_max_drips_per_minute(mock_storage, TEST_MAX_DRIPS_PER_MINUTE);
+            let result = core
+                .handle_request(&reserve_path(faucet_info, wallet_3), now)
+                .await
+                .unwrap()
+                .retry_after();
+            assert!(result.is_none());
+            dripped += &drip_amount;
+        }
+        // Now all wallets should be rate limited due to drip cap
+        for wallet in [wallet_1, wallet_2, wallet_3] {
+            let alarm_time =
+                Utc::now().timestamp_millis() + faucet_info.reset_limiter_seconds() * 1000;
+            let mock_storage = new_mock_storage(MockStorageConfig {
+                dripped: Some(dripped.clone()),
+                recent_drips: None,
+                alarm: Some(alarm_time),
+                expect_puts: false,
+            });
+            let core =
+                DripBudgetCore::with_max_drips_per_minute(mock_storage, TEST_MAX_DRIPS_PER_MINUTE);
+            let reply = core
+                .handle_request(&reserve_path(faucet_info, wallet), Utc::now())
+                .await
+                .unwrap();
+            assert!(reply.retry_after().is_some());
+            assert_eq!(reply.reason(), Some(RateLimitReason::DripCap));
+        }
+    }
+
+    /// Simulates reaching the drip cap, triggering the alarm reset, and verifies new requests are
+    /// allowed after reset.
+    #[tokio::test]
+    async fn test_alarm_reset_cycle() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let alarm_time = Utc::now().timestamp_millis() + faucet_info.reset_limiter_seconds() * 1000;
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            dripped: Some(faucet_info.drip_cap()),
+            recent_drips: None,
+            alarm: Some(alarm_time),
+            expect_puts: false,
+        });
+        let core = DripBudgetCore::new(mock_storage);
+        let reply = core
+            .handle_request(&reserve_path(faucet_info, "wallet_1"), Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(reply.reason(), Some(RateLimitReason::DripCap));
+        // Simulate alarm handler
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage.expect_delete_all().returning(|| Ok(()));
+        let core = DripBudgetCore::new(mock_storage);
+        core.handle_alarm().await.unwrap();
+        // After alarm, new request should be allowed (storage is reset)
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            dripped: None,
+            recent_drips: None,
+            alarm: None,
+            expect_puts: true,
+        });
+        let core = DripBudgetCore::new(mock_storage);
+        let result = core
+            .handle_request(&reserve_path(faucet_info, "wallet_1"), Utc::now())
+            .await
+            .unwrap()
+            .retry_after();
+        assert!(result.is_none());
+    }
+
+    /// Builds a budget reservation for `wallet_id` as stored by [`DripBudgetCore::handle_request`].
+    fn new_reservation(
+        faucet_info: FaucetInfo,
+        wallet_id: &str,
+        now: DateTime<Utc>,
+    ) -> BudgetReservation {
+        BudgetReservation {
+            id: wallet_id.to_string(),
+            amount: faucet_info.drip_amount(),
+            reserved_at: now.timestamp(),
+            expires_at: (now + Duration::seconds(RESERVATION_TTL_SECONDS)).timestamp(),
+        }
+    }
+
+    /// Checks that committing a budget reservation only removes the reservation.
+    #[tokio::test]
+    async fn test_commit_budget_reservation() {
+        let reservation = new_reservation(
+            "CalibnetFIL".parse::<FaucetInfo>().unwrap(),
+            "test_wallet",
+            Utc::now(),
+        );
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<BudgetReservation>()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .returning(move |_| Ok(Some(reservation.clone())));
+        mock_storage
+            .expect_delete()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .times(1)
+            .returning(|_| Ok(true));
+        mock_storage.expect_put::<DripAmount>().never();
+        let core = DripBudgetCore::new(mock_storage);
+        let committed = core
+            .handle_commit("http://do/commit/CalibnetFIL/test_wallet/abc")
+            .await
+            .unwrap();
+        assert!(committed);
+    }
+
+    /// Checks that releasing a budget reservation refunds the drip and frees the throughput slot.
+    #[tokio::test]
+    async fn test_release_budget_reservation() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let now = Utc::now();
+        let reservation = new_reservation(faucet_info, "test_wallet", now);
+        let previous = DripAmount::Token(TokenAmount::from_whole(3));
+        let after_reserve = &previous + &faucet_info.drip_amount();
+
+        let mut mock_storage = MockRateLimiterStorage::new();
+        let stored = reservation.clone();
+        mock_storage
+            .expect_get::<BudgetReservation>()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .returning(move |_| Ok(Some(stored.clone())));
+        mock_storage
+            .expect_delete()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .times(1)
+            .returning(|_| Ok(true));
+        mock_storage
+            .expect_get::<DripAmount>()
+            .with(mockall::predicate::eq("dripped"))
+            .returning(move |_| Ok(Some(after_reserve.clone())));
+        let reserved_at = reservation.reserved_at;
+        mock_storage
+            .expect_get::<Vec<i64>>()
+            .with(mockall::predicate::eq(RECENT_DRIPS_KEY))
+            .returning(move |_| Ok(Some(vec![reserved_at - 10, reserved_at])));
+        mock_storage
+            .expect_put::<DripAmount>()
+            .with(
+                mockall::predicate::eq("dripped"),
+                mockall::predicate::eq(previous),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_storage
+            .expect_put::<Vec<i64>>()
+            .with(
+                mockall::predicate::eq(RECENT_DRIPS_KEY),
+                mockall::predicate::eq(vec![reservation.reserved_at - 10]),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let core =
+            DripBudgetCore::with_max_drips_per_minute(mock_storage, TEST_MAX_DRIPS_PER_MINUTE);
+        let released = core
+            .handle_release(
+                &format!("http://do/release/{faucet_info}/test_wallet/abc"),
+                now,
+            )
+            .await
+            .unwrap();
+        assert!(released);
+    }
+
+    /// Checks that extending a budget reservation pushes its expiry back, so that it can still be
+    /// released.
+    #[tokio::test]
+    async fn test_extend_budget_reservation() {
+        let reserved_at = Utc::now() - Duration::seconds(RESERVATION_TTL_SECONDS + 1);
+        let reservation = new_reservation(
+            "CalibnetFIL".parse::<FaucetInfo>().unwrap(),
+            "test_wallet",
+            reserved_at,
+        );
+        let now = Utc::now();
+        let mut extended = reservation.clone();
+        extended.expires_at = (now + Duration::seconds(RESERVATION_TTL_SECONDS)).timestamp();
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<BudgetReservation>()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .returning(move |_| Ok(Some(reservation.clone())));
+        mock_storage
+            .expect_put::<BudgetReservation>()
+            .with(
+                mockall::predicate::eq("reservation_abc"),
+                mockall::predicate::eq(extended),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let core = DripBudgetCore::new(mock_storage);
+        let found = core
+            .handle_extend("http://do/extend/CalibnetFIL/test_wallet/abc", now)
+            .await
+            .unwrap();
+        assert!(found);
+    }
+
+    /// Checks that an expired budget reservation is dropped without refunding the drip.
+    #[tokio::test]
+    async fn test_release_expired_budget_reservation() {
+        let reserved_at = Utc::now() - Duration::seconds(RESERVATION_TTL_SECONDS + 1);
+        let reservation = new_reservation(
+            "CalibnetFIL".parse::<FaucetInfo>().unwrap(),
+            "test_wallet",
+            reserved_at,
+        );
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<BudgetReservation>()
+            .returning(move |_| Ok(Some(reservation.clone())));
+        mock_storage
+            .expect_delete()
+            .times(1)
+            .returning(|_| Ok(true));
+        mock_storage.expect_get::<DripAmount>().never();
+        mock_storage.expect_put::<DripAmount>().never();
+        mock_storage.expect_put::<Vec<i64>>().never();
+        let core = DripBudgetCore::new(mock_storage);
+        let released = core
+            .handle_release("http://do/release/CalibnetFIL/test_wallet/abc", Utc::now())
+            .await
+            .unwrap();
+        assert!(!released);
+    }
+}
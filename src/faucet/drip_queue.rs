@@ -0,0 +1,579 @@
+#![cfg(feature = "ssr")]
+//! Queue of the claims made through the claim API of a faucet.
+//!
+//! `claim_token` only checks a claim, reserves its quota and queues it, so that API clients get a
+//! claim ID right away instead of waiting for the message to be estimated, signed and pushed. The
+//! queue of each faucet is drained by its own alarm, which dispatches the queued claims in
+//! batches: each claim leases the next nonce of the faucet key and is pushed in order. The outcome
+//! of a claim is kept for [`STATUS_TTL_SECONDS`] so clients can poll it with `claim_status`.
+
+use super::constants::FaucetInfo;
+use super::rate_limiter::RateLimiterStorage;
+use super::server::Reservation;
+use super::server_api::ClaimState;
+use crate::utils::drip_amount::DripAmount;
+use crate::utils::lotus_json::LotusJson;
+use chrono::{DateTime, Utc};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use serde::{Deserialize, Serialize};
+use worker::*;
+
+/// Maximum number of claims dispatched by a single alarm. Remaining claims are dispatched by the
+/// next one.
+const BATCH_SIZE: usize = 10;
+
+/// Maximum number of claims waiting in the queue. Claims beyond that are rejected, as clients
+/// would wait too long for them to be dispatched.
+const MAX_QUEUED_CLAIMS: usize = 500;
+
+/// Delay before the alarm dispatching queued claims fires, so that claims arriving together are
+/// dispatched in the same batch.
+const DISPATCH_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Number of seconds the status of a dispatched claim is kept.
+const STATUS_TTL_SECONDS: i64 = 3600;
+
+/// Storage key of the IDs of the queued claims, in order.
+const QUEUE_KEY: &str = "queue";
+
+/// Storage key of the IDs of the dispatched claims, along with the Unix timestamp (seconds) at
+/// which they were dispatched.
+const DISPATCHED_KEY: &str = "dispatched";
+
+/// A claim admitted by the rate limiter, waiting to be dispatched.
+#[derive(Serialize, Deserialize)]
+pub struct QueuedClaim {
+    pub faucet_info: FaucetInfo,
+    pub recipient: LotusJson<Address>,
+    pub amount: DripAmount,
+    /// Quota reserved for the claim, settled once the claim is dispatched.
+    pub reservation: Reservation,
+}
+
+fn claim_key(id: &str) -> String {
+    format!("claim/{id}")
+}
+
+fn status_key(id: &str) -> String {
+    format!("status/{id}")
+}
+
+/// Storage key marking a claim whose dispatch has started. A claim still queued with this mark was
+/// interrupted, e.g., the alarm failed, and may have been pushed already.
+fn dispatching_key(id: &str) -> String {
+    format!("dispatching/{id}")
+}
+
+/// Error reported for a claim interrupted while being dispatched, which is not dispatched again.
+const INTERRUPTED_ERROR: &str = "Claim was interrupted while being dispatched";
+
+/// Core logic of the drip queue, generic over a storage backend. There is a single instance per
+/// faucet. It is used by the [`DripQueue`] durable object handler in production and by mocks in
+/// tests.
+struct DripQueueCore<S: RateLimiterStorage> {
+    storage: S,
+}
+
+impl<S: RateLimiterStorage> DripQueueCore<S> {
+    fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    async fn get_queue(&self) -> Result<Vec<String>> {
+        Ok(self
+            .storage
+            .get::<Vec<String>>(QUEUE_KEY)
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Queues a claim and schedules its dispatch. Returns the ID of the claim.
+    async fn enqueue(&self, claim: QueuedClaim) -> Result<String> {
+        let mut queue = self.get_queue().await?;
+        if queue.len() >= MAX_QUEUED_CLAIMS {
+            return Err(Error::RustError(format!(
+                "{} drip queue is full",
+                claim.faucet_info
+            )));
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        self.storage.put(&claim_key(&id), claim).await?;
+        queue.push(id.clone());
+        self.storage.put(QUEUE_KEY, queue).await?;
+        if self.storage.get_alarm().await?.is_none() {
+            self.storage.set_alarm(DISPATCH_DELAY).await?;
+        }
+        Ok(id)
+    }
+
+    /// Returns the next claims to dispatch, oldest first. They stay queued until they are
+    /// [`completed`](DripQueueCore::complete).
+    async fn next_batch(&self) -> Result<Vec<(String, QueuedClaim)>> {
+        let mut batch = Vec::new();
+        for id in self.get_queue().await?.into_iter().take(BATCH_SIZE) {
+            match self.storage.get::<QueuedClaim>(&claim_key(&id)).await? {
+                Some(claim) => batch.push((id, claim)),
+                None => log::error!("Queued claim {id} not found"),
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Removes a dispatched claim from the queue and records its outcome. The outcomes recorded
+    /// more than [`STATUS_TTL_SECONDS`] ago are dropped.
+    async fn complete(&self, id: &str, status: ClaimState, now: DateTime<Utc>) -> Result<()> {
+        let now = now.timestamp();
+        let mut queue = self.get_queue().await?;
+        queue.retain(|queued| queued != id);
+        self.storage.put(QUEUE_KEY, queue).await?;
+        self.storage.delete(&claim_key(id)).await?;
+        self.storage.delete(&dispatching_key(id)).await?;
+
+        let mut dispatched = self
+            .storage
+            .get::<Vec<(String, i64)>>(DISPATCHED_KEY)
+            .await?
+            .unwrap_or_default();
+        for (expired, _) in dispatched
+            .iter()
+            .filter(|(_, dispatched_at)| now - dispatched_at >= STATUS_TTL_SECONDS)
+        {
+            self.storage.delete(&status_key(expired)).await?;
+        }
+        dispatched.retain(|(_, dispatched_at)| now - dispatched_at < STATUS_TTL_SECONDS);
+        dispatched.push((id.to_string(), now));
+        self.storage.put(DISPATCHED_KEY, dispatched).await?;
+        self.storage.put(&status_key(id), status).await
+    }
+
+    /// Returns the status of a claim, or `None` if it is unknown or its outcome has expired.
+    async fn status(&self, id: &str) -> Result<Option<ClaimState>> {
+        if self.get_queue().await?.iter().any(|queued| queued == id) {
+            return Ok(Some(ClaimState::Queued));
+        }
+        self.storage.get::<ClaimState>(&status_key(id)).await
+    }
+
+    /// Dispatches the next batch of claims, and schedules the next dispatch if claims are still
+    /// queued. Each claim is marked before it is dispatched, so that a claim interrupted by a
+    /// failing alarm is never pushed twice when the alarm is retried. Interrupted claims fail
+    /// instead, and their reservation is left to expire, as their message may have been pushed.
+    async fn handle_alarm<F>(&self, dispatch: impl Fn(QueuedClaim) -> F) -> Result<()>
+    where
+        F: Future<Output = ClaimState>,
+    {
+        for (id, claim) in self.next_batch().await? {
+            let status = if self
+                .storage
+                .get::<bool>(&dispatching_key(&id))
+                .await?
+                .is_some()
+            {
+                log::error!(
+                    "{} claim {id} was interrupted, not dispatching it again",
+                    claim.faucet_info
+                );
+                ClaimState::Failed {
+                    error: INTERRUPTED_ERROR.to_string(),
+                }
+            } else {
+                self.storage.put(&dispatching_key(&id), true).await?;
+                dispatch(claim).await
+            };
+            self.complete(&id, status, Utc::now()).await?;
+        }
+        if !self.get_queue().await?.is_empty() {
+            self.storage.set_alarm(DISPATCH_DELAY).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Signs and pushes the message of a queued claim. The reservation of the claim is committed once
+/// the message is pushed, and released if anything fails before that. As the claim may have waited
+/// in the queue longer than the reservation lasts, the reservation is extended first.
+#[cfg(not(test))]
+async fn dispatch(env: &Env, claim: QueuedClaim) -> ClaimState {
+    use crate::utils::drip_amount::TokenType;
+
+    let QueuedClaim {
+        faucet_info,
+        recipient: LotusJson(recipient),
+        amount,
+        mut reservation,
+    } = claim;
+    reservation.extend_in(env).await;
+    fvm_shared::address::set_current_network(faucet_info.network().prefix);
+    let pushed = match (faucet_info.token_type(), amount) {
+        (TokenType::Erc20(contract_address), DripAmount::Token(amount)) => {
+            push_erc20(
+                env,
+                faucet_info,
+                contract_address,
+                recipient,
+                amount,
+                &mut reservation,
+            )
+            .await
+        }
+        (TokenType::Native, DripAmount::Token(amount)) => {
+            push_native(env, faucet_info, recipient, amount, &mut reservation).await
+        }
+        (TokenType::Datacap, DripAmount::Storage(allowance)) => {
+            push_datacap(env, faucet_info, recipient, allowance, &mut reservation).await
+        }
+        (_, amount) => Err(anyhow::anyhow!(
+            "Invalid drip amount {amount:?} for {faucet_info}"
+        )),
+    };
+    match pushed {
+        Ok(status) => {
+            reservation.commit_in(env).await;
+            status
+        }
+        Err(e) => {
+            log::error!("Failed to dispatch {faucet_info} claim to {recipient}: {e}");
+            reservation.release_in(env).await;
+            ClaimState::Failed {
+                error: crate::utils::error::error_message(&e),
+            }
+        }
+    }
+}
+
+#[cfg(not(test))]
+async fn push_native(
+    env: &Env,
+    faucet_info: FaucetInfo,
+    recipient: Address,
+    amount: TokenAmount,
+    reservation: &mut Reservation,
+) -> anyhow::Result<ClaimState> {
+    use super::server::{faucet_key, sign_message};
+    use crate::utils::message::{message_transfer, message_transfer_native};
+    use crate::utils::rpc_context::Provider;
+
+    let rpc = Provider::default_for(faucet_info.network());
+    let key = faucet_key(env, faucet_info)?;
+    let from = key.address;
+    let (to, mpool_nonce, estimated) = rpc
+        .prepare_message(message_transfer(from, recipient, amount.clone()))
+        .await?;
+    let nonce = reservation.lease_nonce_in(env, mpool_nonce).await?;
+    let msg = message_transfer_native(
+        from,
+        to,
+        amount,
+        estimated.gas_limit.min(faucet_info.max_gas_limit()),
+        estimated.gas_fee_cap.min(faucet_info.max_gas_fee_cap()),
+        estimated.gas_premium.min(faucet_info.max_gas_premium()),
+        nonce,
+    );
+    let cid = rpc.mpool_push(sign_message(&key, msg)?).await?;
+    log::info!("{faucet_info} Pushed {cid} to {recipient} with nonce {nonce}");
+    Ok(ClaimState::Pushed {
+        cid: Some(cid.to_string()),
+        tx_hash: None,
+    })
+}
+
+/// Grants datacap to the recipient of a claim. The verifier allowance and the status of the
+/// recipient are checked again, as they may have changed since the claim was queued.
+#[cfg(not(test))]
+async fn push_datacap(
+    env: &Env,
+    faucet_info: FaucetInfo,
+    recipient: Address,
+    allowance: fvm_shared::sector::StoragePower,
+    reservation: &mut Reservation,
+) -> anyhow::Result<ClaimState> {
+    use super::server::{faucet_key, sign_message};
+    use crate::utils::error::FaucetError;
+    use crate::utils::message::{
+        AddVerifiedClientParams, message_grant_datacap, message_grant_datacap_native,
+    };
+    use crate::utils::rpc_context::Provider;
+    use fvm_ipld_encoding::RawBytes;
+
+    let rpc = Provider::default_for(faucet_info.network());
+    let key = faucet_key(env, faucet_info)?;
+    let from = key.address;
+    rpc.datacap_status(from, recipient)
+        .await?
+        .check_allocation(&allowance)
+        .map_err(FaucetError::Datacap)?;
+    let params = RawBytes::new(fvm_ipld_encoding::to_vec(&AddVerifiedClientParams {
+        address: recipient,
+        allowance,
+    })?);
+    let (_, mpool_nonce, estimated) = rpc
+        .prepare_message(message_grant_datacap(from, params.clone()))
+        .await?;
+    let nonce = reservation.lease_nonce_in(env, mpool_nonce).await?;
+    let msg = message_grant_datacap_native(
+        from,
+        params,
+        estimated.gas_limit.min(faucet_info.max_gas_limit()),
+        estimated.gas_fee_cap.min(faucet_info.max_gas_fee_cap()),
+        estimated.gas_premium.min(faucet_info.max_gas_premium()),
+        nonce,
+    );
+    let cid = rpc.mpool_push(sign_message(&key, msg)?).await?;
+    log::info!("{faucet_info} Pushed {cid} granting datacap to {recipient} with nonce {nonce}");
+    Ok(ClaimState::Pushed {
+        cid: Some(cid.to_string()),
+        tx_hash: None,
+    })
+}
+
+#[cfg(not(test))]
+async fn push_erc20(
+    env: &Env,
+    faucet_info: FaucetInfo,
+    contract_address: alloy::primitives::Address,
+    recipient: Address,
+    amount: TokenAmount,
+    reservation: &mut Reservation,
+) -> anyhow::Result<ClaimState> {
+    use super::server::{erc20_transfer_request, faucet_secret, sign_transaction};
+    use crate::utils::address::{AddressAlloyExt as _, AnyAddress};
+    use crate::utils::conversions::TokenAmountAlloyExt as _;
+    use crate::utils::erc20;
+    use crate::utils::rpc_context::Provider;
+    use alloy::network::TransactionBuilder as _;
+    use alloy::signers::local::PrivateKeySigner;
+
+    let network = faucet_info.network();
+    let rpc = Provider::default_for(network);
+    let secret = faucet_secret(env, faucet_info)?;
+    let signer: PrivateKeySigner = secret.parse()?;
+    let from = AnyAddress::Ethereum(signer.address()).to_filecoin_address(network)?;
+    let to = recipient.into_eth_address()?;
+    let decimals = rpc.erc20_metadata(contract_address).await?.decimals;
+    let estimate = erc20::transfer_request(contract_address, to, amount.to_alloy_amount(decimals))
+        .with_from(signer.address());
+    let fees = rpc.estimate_eth_fees(&estimate).await?;
+    let mpool_nonce = rpc.mpool_get_nonce(from).await?;
+    let nonce = reservation.lease_nonce_in(env, mpool_nonce).await?;
+    let tx = erc20_transfer_request(
+        faucet_info,
+        contract_address,
+        decimals,
+        to,
+        &amount,
+        nonce,
+        &fees,
+    );
+    let tx_hash = rpc
+        .send_eth_transaction_signed(&sign_transaction(&secret, tx).await?)
+        .await?;
+    log::info!("{faucet_info} Sent {tx_hash} to {to} with nonce {nonce}");
+    Ok(ClaimState::Pushed {
+        cid: None,
+        tx_hash: Some(tx_hash),
+    })
+}
+
+/// Durable object queueing the API claims of a faucet. It is addressed by the faucet name.
+#[cfg(not(test))]
+#[durable_object]
+pub struct DripQueue {
+    #[cfg(not(test))]
+    state: State,
+    #[cfg(not(test))]
+    env: Env,
+}
+
+#[cfg(not(test))]
+impl DripQueue {
+    fn create_core(&self) -> DripQueueCore<super::rate_limiter::DurableObjectStorage<'_>> {
+        DripQueueCore::new(super::rate_limiter::DurableObjectStorage::new(&self.state))
+    }
+}
+
+#[cfg(not(test))]
+impl DurableObject for DripQueue {
+    fn new(state: State, env: Env) -> Self {
+        Self { state, env }
+    }
+
+    async fn fetch(&self, mut req: Request) -> Result<Response> {
+        let path = req.path();
+        let core = self.create_core();
+        match path.split('/').nth(1) {
+            Some("enqueue") => {
+                let claim = req.json::<QueuedClaim>().await?;
+                Response::from_json(&core.enqueue(claim).await?)
+            }
+            Some("status") => {
+                let id = path.rsplit('/').next().unwrap_or_default();
+                Response::from_json(&core.status(id).await?)
+            }
+            _ => Response::error("Not found", 404),
+        }
+    }
+
+    async fn alarm(&self) -> Result<Response> {
+        self.create_core()
+            .handle_alarm(|claim| dispatch(&self.env, claim))
+            .await?;
+        Response::ok("OK")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::faucet::rate_limiter::{MockRateLimiterStorage, MockStore, expect_stored_values};
+
+    /// Returns a mock storage backed by a shared store, so that consecutive calls see the effects
+    /// of each other. The alarm is never pending.
+    fn new_mock_storage(store: &MockStore) -> MockRateLimiterStorage {
+        let mut mock_storage = MockRateLimiterStorage::new();
+        expect_stored_values::<Vec<String>>(&mut mock_storage, store);
+        expect_stored_values::<Vec<(String, i64)>>(&mut mock_storage, store);
+        expect_stored_values::<QueuedClaim>(&mut mock_storage, store);
+        expect_stored_values::<ClaimState>(&mut mock_storage, store);
+        expect_stored_values::<bool>(&mut mock_storage, store);
+        let delete_store = store.clone();
+        mock_storage
+            .expect_delete()
+            .returning(move |key| Ok(delete_store.lock().unwrap().remove(key).is_some()));
+        mock_storage.expect_get_alarm().returning(|| Ok(None));
+        mock_storage.expect_set_alarm().returning(|_| Ok(()));
+        mock_storage
+    }
+
+    fn new_core() -> (DripQueueCore<MockRateLimiterStorage>, MockStore) {
+        let store = MockStore::default();
+        (DripQueueCore::new(new_mock_storage(&store)), store)
+    }
+
+    fn new_claim() -> QueuedClaim {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        QueuedClaim {
+            faucet_info,
+            recipient: LotusJson(Address::new_id(1234)),
+            amount: DripAmount::Token(TokenAmount::from_whole(5)),
+            reservation: Reservation::unlimited(faucet_info, "t01234".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_and_complete() {
+        let (core, _) = new_core();
+        let now = Utc::now();
+        let first = core.enqueue(new_claim()).await.unwrap();
+        let second = core.enqueue(new_claim()).await.unwrap();
+        assert_ne!(first, second);
+        assert_eq!(core.status(&first).await.unwrap(), Some(ClaimState::Queued));
+        assert_eq!(core.status("unknown").await.unwrap(), None);
+
+        let batch = core.next_batch().await.unwrap();
+        let ids: Vec<_> = batch.iter().map(|(id, _)| id.clone()).collect();
+        assert_eq!(ids, [first.clone(), second.clone()]);
+
+        let pushed = ClaimState::Pushed {
+            cid: Some("bafy2bzace".to_string()),
+            tx_hash: None,
+        };
+        core.complete(&first, pushed.clone(), now).await.unwrap();
+        assert_eq!(core.status(&first).await.unwrap(), Some(pushed));
+        assert_eq!(
+            core.status(&second).await.unwrap(),
+            Some(ClaimState::Queued)
+        );
+        let batch = core.next_batch().await.unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].0, second);
+    }
+
+    #[tokio::test]
+    async fn test_batch_size() {
+        let (core, _) = new_core();
+        for _ in 0..BATCH_SIZE + 2 {
+            core.enqueue(new_claim()).await.unwrap();
+        }
+        assert_eq!(core.next_batch().await.unwrap().len(), BATCH_SIZE);
+    }
+
+    #[tokio::test]
+    async fn test_alarm_dispatches_batch() {
+        let (core, _) = new_core();
+        let mut ids = Vec::new();
+        for _ in 0..=BATCH_SIZE {
+            ids.push(core.enqueue(new_claim()).await.unwrap());
+        }
+        let failed = ClaimState::Failed {
+            error: "Faucet is empty".to_string(),
+        };
+        core.handle_alarm(|_| std::future::ready(failed.clone()))
+            .await
+            .unwrap();
+        assert_eq!(core.status(&ids[0]).await.unwrap(), Some(failed));
+        assert_eq!(
+            core.status(&ids[BATCH_SIZE]).await.unwrap(),
+            Some(ClaimState::Queued)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_claim_is_not_dispatched_again() {
+        let (core, store) = new_core();
+        let interrupted = core.enqueue(new_claim()).await.unwrap();
+        let next = core.enqueue(new_claim()).await.unwrap();
+        // The previous alarm started dispatching the first claim, but failed before completing it.
+        store.lock().unwrap().insert(
+            dispatching_key(&interrupted),
+            serde_json::to_value(true).unwrap(),
+        );
+        let pushed = ClaimState::Pushed {
+            cid: Some("bafy2bzace".to_string()),
+            tx_hash: None,
+        };
+        let dispatched = std::sync::atomic::AtomicUsize::new(0);
+        core.handle_alarm(|_| {
+            dispatched.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            std::future::ready(pushed.clone())
+        })
+        .await
+        .unwrap();
+        assert_eq!(dispatched.into_inner(), 1);
+        assert_eq!(
+            core.status(&interrupted).await.unwrap(),
+            Some(ClaimState::Failed {
+                error: INTERRUPTED_ERROR.to_string()
+            })
+        );
+        assert_eq!(core.status(&next).await.unwrap(), Some(pushed));
+        assert!(!store.lock().unwrap().contains_key(&dispatching_key(&next)));
+    }
+
+    #[tokio::test]
+    async fn test_queue_full() {
+        let (core, store) = new_core();
+        let queue: Vec<String> = (0..MAX_QUEUED_CLAIMS).map(|i| i.to_string()).collect();
+        store
+            .lock()
+            .unwrap()
+            .insert(QUEUE_KEY.to_string(), serde_json::to_value(queue).unwrap());
+        assert!(core.enqueue(new_claim()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_status_expires() {
+        let (core, _) = new_core();
+        let now = Utc::now();
+        let first = core.enqueue(new_claim()).await.unwrap();
+        let second = core.enqueue(new_claim()).await.unwrap();
+        let failed = ClaimState::Failed {
+            error: "Faucet is empty".to_string(),
+        };
+        core.complete(&first, failed.clone(), now).await.unwrap();
+        let later = now + chrono::Duration::seconds(STATUS_TTL_SECONDS);
+        core.complete(&second, failed.clone(), later).await.unwrap();
+        assert_eq!(core.status(&first).await.unwrap(), None);
+        assert_eq!(core.status(&second).await.unwrap(), Some(failed));
+    }
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "ssr")]
+//! Verification that a browser drip was requested by a human, e.g., with a CAPTCHA.
+
+use crate::utils::error::FaucetError;
+use serde::Deserialize;
+
+/// Endpoint validating Turnstile tokens.
+const TURNSTILE_SITEVERIFY_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+
+/// Verifies the token produced by a human verification widget on the client side.
+#[async_trait::async_trait(?Send)]
+pub(super) trait HumanVerifier {
+    /// Returns whether the token proves that a human is present. `remote_ip` is the IP of the
+    /// client that submitted the token, if known.
+    async fn verify(&self, token: &str, remote_ip: Option<&str>) -> anyhow::Result<bool>;
+}
+
+/// [`HumanVerifier`] backed by [CloudFlare Turnstile](https://developers.cloudflare.com/turnstile/).
+pub(super) struct TurnstileVerifier {
+    secret_key: String,
+}
+
+impl TurnstileVerifier {
+    pub(super) fn new(secret_key: String) -> Self {
+        Self { secret_key }
+    }
+}
+
+/// Reply of the Turnstile `siteverify` endpoint. Only the fields of interest are parsed.
+#[derive(Debug, Deserialize)]
+struct SiteverifyResponse {
+    success: bool,
+    #[serde(rename = "error-codes", default)]
+    error_codes: Vec<String>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl HumanVerifier for TurnstileVerifier {
+    async fn verify(&self, token: &str, remote_ip: Option<&str>) -> anyhow::Result<bool> {
+        let response: SiteverifyResponse = reqwest::Client::new()
+            .post(TURNSTILE_SITEVERIFY_URL)
+            .json(&serde_json::json!({
+                "secret": self.secret_key,
+                "response": token,
+                "remoteip": remote_ip,
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        if !response.success {
+            log::warn!(
+                "Turnstile verification failed: {}",
+                response.error_codes.join(", ")
+            );
+        }
+        Ok(response.success)
+    }
+}
+
+/// Verifies the human verification token of a request. A missing token fails the verification.
+pub(super) async fn verify_human<V: HumanVerifier>(
+    verifier: &V,
+    token: Option<&str>,
+    remote_ip: Option<&str>,
+) -> Result<(), FaucetError> {
+    let Some(token) = token.filter(|token| !token.is_empty()) else {
+        return Err(FaucetError::HumanVerification(
+            "verification token is missing".to_string(),
+        ));
+    };
+    match verifier.verify(token, remote_ip).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(FaucetError::HumanVerification(
+            "verification token was rejected".to_string(),
+        )),
+        Err(e) => Err(FaucetError::Server(format!(
+            "Failed to verify the verification token: {e}"
+        ))),
+    }
+}
+
+/// [`HumanVerifier`] accepting a single, fixed token. Meant for tests.
+#[cfg(test)]
+pub(super) struct StubVerifier {
+    pub accepted_token: &'static str,
+}
+
+#[cfg(test)]
+#[async_trait::async_trait(?Send)]
+impl HumanVerifier for StubVerifier {
+    async fn verify(&self, token: &str, _remote_ip: Option<&str>) -> anyhow::Result<bool> {
+        Ok(token == self.accepted_token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STUB: StubVerifier = StubVerifier {
+        accepted_token: "human",
+    };
+
+    #[tokio::test]
+    async fn test_verify_human_accepts_valid_token() {
+        assert!(verify_human(&STUB, Some("human"), None).await.is_ok());
+        assert!(
+            verify_human(&STUB, Some("human"), Some("203.0.113.42"))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_human_rejects_invalid_token() {
+        let err = verify_human(&STUB, Some("bot"), None).await.unwrap_err();
+        assert!(matches!(err, FaucetError::HumanVerification(_)));
+    }
+
+    #[tokio::test]
+    async fn test_verify_human_rejects_missing_token() {
+        for token in [None, Some("")] {
+            let err = verify_human(&STUB, token, None).await.unwrap_err();
+            assert!(matches!(err, FaucetError::HumanVerification(_)));
+        }
+    }
+
+    #[test]
+    fn test_parse_siteverify_response() {
+        let success: SiteverifyResponse = serde_json::from_str(
+            r#"{"success":true,"challenge_ts":"2025-01-01T00:00:00.000Z","hostname":"example.com","error-codes":[]}"#,
+        )
+        .unwrap();
+        assert!(success.success);
+
+        let failure: SiteverifyResponse =
+            serde_json::from_str(r#"{"success":false,"error-codes":["invalid-input-response"]}"#)
+                .unwrap();
+        assert!(!failure.success);
+        assert_eq!(failure.error_codes, ["invalid-input-response"]);
+    }
+}
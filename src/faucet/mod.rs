@@ -1,7 +1,14 @@
+mod api_key;
+pub mod challenge;
 pub mod constants;
 mod controller;
+mod drip_budget;
+mod drip_queue;
+mod human_verifier;
 mod model;
+mod nonce_manager;
 mod rate_limiter;
+mod registry;
 mod server;
 pub mod server_api;
 pub mod views;
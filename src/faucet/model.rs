@@ -2,6 +2,7 @@ use leptos::prelude::*;
 use uuid::Uuid;
 
 use crate::utils::drip_amount::DripAmount;
+use crate::utils::erc20::Erc20Metadata;
 use crate::utils::transaction_id::TransactionId;
 
 #[derive(Clone)]
@@ -11,8 +12,18 @@ pub(super) struct FaucetModel {
     pub sent_messages: RwSignal<Vec<(TransactionId, bool)>>,
     pub error_messages: RwSignal<Vec<(Uuid, String)>>,
     pub balance_trigger: Trigger,
-    pub faucet_balance: LocalResource<DripAmount>,
-    pub target_balance: LocalResource<DripAmount>,
+    pub balances: LocalResource<Balances>,
+    /// Metadata of the token, if the faucet dispenses an ERC-20 token.
+    pub token_metadata: LocalResource<Option<Erc20Metadata>>,
     pub sender_address: RwSignal<String>,
     pub target_address: RwSignal<String>,
+    /// Token of the human verification of the next drip, if the faucet requires one.
+    pub human_token: RwSignal<Option<String>>,
+}
+
+/// Balances of the faucet and target addresses, fetched together.
+#[derive(Clone)]
+pub(super) struct Balances {
+    pub faucet: DripAmount,
+    pub target: DripAmount,
 }
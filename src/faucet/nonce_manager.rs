@@ -0,0 +1,244 @@
+#![cfg(feature = "ssr")]
+//! Coordinator handing out the nonces of a faucet key, so that concurrent drips of the same faucet
+//! do not sign messages with the same nonce.
+//!
+//! Each drip leases a nonce, given the nonce the mpool expects next, i.e., the result of
+//! `Filecoin.MpoolGetNonce`. Nonces below the mpool nonce have been used and are forgotten. The
+//! mpool nonce itself is the gap the messages queued behind it wait for: if it was released, or if
+//! its lease expired without the message showing up in the mpool, it is handed out again. Higher
+//! nonces are never handed out twice, as their messages may be waiting in the mpool. Should they
+//! be missing too, they become the mpool nonce once the gap is filled.
+
+use super::rate_limiter::RateLimiterStorage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use worker::*;
+
+/// Number of seconds a leased nonce is reserved for its drip. If the mpool has not reached the
+/// nonce by then, its message is considered lost and the nonce is handed out again.
+const LEASE_TTL_SECONDS: i64 = 60;
+
+/// Storage key of the [`NonceState`].
+const STATE_KEY: &str = "state";
+
+/// A nonce handed out to a drip.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Lease {
+    nonce: u64,
+    /// Unix timestamp (seconds) at which the nonce was leased.
+    leased_at: i64,
+}
+
+/// Nonces of a faucet key.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct NonceState {
+    /// Lowest nonce that was never handed out.
+    next: u64,
+    /// Leases of the nonces the mpool has not reached yet.
+    leases: Vec<Lease>,
+}
+
+/// Core logic of the nonce manager, generic over a storage backend. There is a single instance per
+/// faucet key. It is used by the [`NonceManager`] durable object handler in production and by
+/// mocks in tests.
+struct NonceManagerCore<S: RateLimiterStorage> {
+    storage: S,
+}
+
+impl<S: RateLimiterStorage> NonceManagerCore<S> {
+    fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    async fn get_state(&self) -> Result<NonceState> {
+        Ok(self
+            .storage
+            .get::<NonceState>(STATE_KEY)
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Leases `mpool_nonce` if it was handed out but is not held by a live lease, or else the next
+    /// nonce that was never handed out.
+    async fn handle_lease(&self, mpool_nonce: u64, now: DateTime<Utc>) -> Result<u64> {
+        let mut state = self.get_state().await?;
+        let now = now.timestamp();
+        // The mpool is ahead if messages were sent by other means or the state was lost.
+        state.next = state.next.max(mpool_nonce);
+        state.leases.retain(|lease| lease.nonce >= mpool_nonce);
+        let gap_is_live = state
+            .leases
+            .iter()
+            .any(|lease| lease.nonce == mpool_nonce && now - lease.leased_at < LEASE_TTL_SECONDS);
+        let nonce = if mpool_nonce < state.next && !gap_is_live {
+            mpool_nonce
+        } else {
+            state.next
+        };
+        if nonce < state.next {
+            log::warn!("Nonce {nonce} was not used in time, handing it out again");
+        }
+        state.leases.retain(|lease| lease.nonce != nonce);
+        state.leases.push(Lease {
+            nonce,
+            leased_at: now,
+        });
+        let after = nonce
+            .checked_add(1)
+            .ok_or_else(|| Error::RustError(format!("Nonce {nonce} overflows")))?;
+        state.next = state.next.max(after);
+        self.storage.put(STATE_KEY, state).await?;
+        Ok(nonce)
+    }
+
+    /// Gives a leased nonce back, so that it is handed out again right away. Returns `false` if
+    /// the nonce was not leased.
+    async fn handle_release(&self, nonce: u64) -> Result<bool> {
+        let mut state = self.get_state().await?;
+        let leases = state.leases.len();
+        state.leases.retain(|lease| lease.nonce != nonce);
+        if state.leases.len() == leases {
+            log::warn!("Nonce {nonce} not leased on release");
+            return Ok(false);
+        }
+        self.storage.put(STATE_KEY, state).await?;
+        log::info!("Nonce {nonce} released");
+        Ok(true)
+    }
+}
+
+/// Parses the nonce at the end of a request path, e.g., `/lease/{mpool_nonce}`.
+fn parse_nonce(path: &str) -> Result<u64> {
+    path.rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .parse()
+        .map_err(|e| Error::RustError(format!("Invalid nonce in {path}: {e}")))
+}
+
+/// Durable object handing out the nonces of a faucet key. It is addressed by the name of the
+/// faucet secret, see [`super::constants::FaucetInfo::secret_key_name`].
+#[cfg(not(test))]
+#[durable_object]
+pub struct NonceManager {
+    #[cfg(not(test))]
+    state: State,
+}
+
+#[cfg(not(test))]
+impl NonceManager {
+    fn create_core(&self) -> NonceManagerCore<super::rate_limiter::DurableObjectStorage<'_>> {
+        NonceManagerCore::new(super::rate_limiter::DurableObjectStorage::new(&self.state))
+    }
+}
+
+#[cfg(not(test))]
+impl DurableObject for NonceManager {
+    fn new(state: State, _env: Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&self, req: Request) -> Result<Response> {
+        let path = req.path();
+        let nonce = parse_nonce(&path)?;
+        let core = self.create_core();
+        match path.split('/').nth(1) {
+            Some("release") => Response::from_json(&core.handle_release(nonce).await?),
+            _ => Response::from_json(&core.handle_lease(nonce, Utc::now()).await?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::faucet::rate_limiter::{MockRateLimiterStorage, MockStore, expect_stored_values};
+
+    fn new_core() -> NonceManagerCore<MockRateLimiterStorage> {
+        let mut mock_storage = MockRateLimiterStorage::new();
+        expect_stored_values::<NonceState>(&mut mock_storage, &MockStore::default());
+        NonceManagerCore::new(mock_storage)
+    }
+
+    #[test]
+    fn test_parse_nonce() {
+        assert_eq!(parse_nonce("/lease/42").unwrap(), 42);
+        assert_eq!(parse_nonce("http://do/release/0").unwrap(), 0);
+        assert!(parse_nonce("/lease/abc").is_err());
+    }
+
+    /// Checks that concurrent drips seeing the same mpool nonce get distinct nonces.
+    #[tokio::test]
+    async fn test_concurrent_leases() {
+        let core = new_core();
+        let now = Utc::now();
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 5);
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 6);
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 7);
+        // The mpool caught up with the first two messages.
+        assert_eq!(core.handle_lease(7, now).await.unwrap(), 8);
+    }
+
+    /// Checks that the nonce manager re-syncs when the mpool is ahead of it.
+    #[tokio::test]
+    async fn test_lease_resyncs_with_mpool() {
+        let core = new_core();
+        let now = Utc::now();
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 5);
+        assert_eq!(core.handle_lease(20, now).await.unwrap(), 20);
+        assert_eq!(core.handle_lease(20, now).await.unwrap(), 21);
+    }
+
+    /// Checks that the last representable nonce cannot be leased, as there would be no next one.
+    #[tokio::test]
+    async fn test_lease_overflow() {
+        let core = new_core();
+        assert!(core.handle_lease(u64::MAX, Utc::now()).await.is_err());
+        assert_eq!(
+            core.handle_lease(u64::MAX - 1, Utc::now()).await.unwrap(),
+            u64::MAX - 1
+        );
+    }
+
+    /// Checks that a released nonce is handed out again right away.
+    #[tokio::test]
+    async fn test_release_fills_gap() {
+        let core = new_core();
+        let now = Utc::now();
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 5);
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 6);
+        assert!(core.handle_release(5).await.unwrap());
+        assert!(!core.handle_release(5).await.unwrap());
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 5);
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 7);
+    }
+
+    /// Checks that a nonce the mpool did not reach within the lease TTL is handed out again.
+    #[tokio::test]
+    async fn test_expired_lease_fills_gap() {
+        let core = new_core();
+        let now = Utc::now();
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 5);
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 6);
+        let later = now + chrono::Duration::seconds(LEASE_TTL_SECONDS);
+        // The message with nonce 5 never made it to the mpool, the one with nonce 6 is stuck
+        // behind it.
+        assert_eq!(core.handle_lease(5, later).await.unwrap(), 5);
+        // Once the gap is filled, the mpool moves past the stuck message.
+        assert_eq!(core.handle_lease(7, later).await.unwrap(), 7);
+    }
+
+    /// Checks that an expired lease above the mpool nonce is not handed out again, as its message
+    /// may be waiting in the mpool.
+    #[tokio::test]
+    async fn test_expired_lease_above_gap() {
+        let core = new_core();
+        let now = Utc::now();
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 5);
+        assert_eq!(core.handle_lease(5, now).await.unwrap(), 6);
+        let later = now + chrono::Duration::seconds(LEASE_TTL_SECONDS);
+        // The message with nonce 5 was lost, the one with nonce 6 waits in the mpool behind it.
+        assert_eq!(core.handle_lease(5, later).await.unwrap(), 5);
+        assert_eq!(core.handle_lease(5, later).await.unwrap(), 7);
+    }
+}
@@ -1,41 +1,82 @@
 #![cfg(feature = "ssr")]
 use std::str::FromStr as _;
 
+use super::api_key::{ApiKeyQuota, drip_amount, split_quota};
 use crate::faucet::constants::FaucetInfo;
 use crate::utils::drip_amount::DripAmount;
+use crate::utils::error::RateLimitReason;
 use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use worker::*;
 
 #[cfg(test)]
 use mockall::automock;
 
+/// Number of seconds a reservation holds the reserved quota. Reservations that are neither
+/// committed nor released within this window are treated as committed, i.e., the quota stays
+/// consumed. This errs on the side of the faucet in case the worker died after pushing a message.
+/// Claims waiting in the drip queue may outlive this window, so the queue extends their
+/// reservation when it dispatches them, see [`RateLimiterCore::handle_extend`].
+pub(super) const RESERVATION_TTL_SECONDS: i64 = 120;
+
 /// Abstraction for storage backend used by the rate limiter.
 /// This trait allows the rate limiter logic to be decoupled from the underlying storage implementation.
 /// Implementations may use [`DurableObjectStorage`], in-memory mocks, or other storage systems.
 #[cfg_attr(test, automock)]
 #[async_trait::async_trait(?Send)]
-trait RateLimiterStorage {
+pub(super) trait RateLimiterStorage {
     async fn get<T>(&self, key: &str) -> Result<Option<T>>
     where
         T: for<'de> serde::Deserialize<'de> + 'static;
     async fn put<T>(&self, key: &str, value: T) -> Result<()>
     where
         T: serde::Serialize + 'static;
+    async fn delete(&self, key: &str) -> Result<bool>;
     async fn get_alarm(&self) -> Result<Option<i64>>;
     async fn set_alarm(&self, duration: std::time::Duration) -> Result<()>;
     async fn delete_all(&self) -> Result<()>;
 }
 
+/// Store shared by the mock storages of [`expect_stored_values`].
+#[cfg(test)]
+pub(super) type MockStore =
+    std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, serde_json::Value>>>;
+
+/// Lets the mock storage get and put values of type `T` in the shared store, so that consecutive
+/// calls see the effects of each other.
+#[cfg(test)]
+pub(super) fn expect_stored_values<T>(mock_storage: &mut MockRateLimiterStorage, store: &MockStore)
+where
+    T: Serialize + serde::de::DeserializeOwned + 'static,
+{
+    let get_store = store.clone();
+    mock_storage.expect_get::<T>().returning(move |key| {
+        Ok(get_store
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|value| serde_json::from_value(value.clone()).unwrap()))
+    });
+    let put_store = store.clone();
+    mock_storage.expect_put::<T>().returning(move |key, value| {
+        put_store
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), serde_json::to_value(value).unwrap());
+        Ok(())
+    });
+}
+
 /// Storage backend for the rate limiter using Durable Objects.
 /// This struct implements the [`RateLimiterStorage`] trait and is used in production to persist rate limiting state.
 #[cfg(not(test))]
-struct DurableObjectStorage<'a> {
+pub(super) struct DurableObjectStorage<'a> {
     state: &'a State,
 }
 
 #[cfg(not(test))]
 impl<'a> DurableObjectStorage<'a> {
-    fn new(state: &'a State) -> Self {
+    pub(super) fn new(state: &'a State) -> Self {
         Self { state }
     }
 }
@@ -55,6 +96,9 @@ impl RateLimiterStorage for DurableObjectStorage<'_> {
     {
         self.state.storage().put(key, value).await
     }
+    async fn delete(&self, key: &str) -> Result<bool> {
+        self.state.storage().delete(key).await
+    }
     async fn get_alarm(&self) -> Result<Option<i64>> {
         self.state.storage().get_alarm().await
     }
@@ -66,8 +110,175 @@ impl RateLimiterStorage for DurableObjectStorage<'_> {
     }
 }
 
-/// Core logic for rate limiting, generic over a storage backend.
-/// This struct encapsulates all rate limiting logic and can be used with any storage backend that implements [`RateLimiterStorage`].
+/// Dimension a rate limiter object limits claims by. Both share the same logic and storage layout,
+/// but have their own caps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LimitScope {
+    /// Claims to a single wallet, addressed by `/rate_limiter/{faucet_info}/{id}`.
+    Wallet,
+    /// Claims from a single client IP (or IP prefix), addressed by
+    /// `/ip_limiter/{faucet_info}/{id}`. There is no cool-down per IP, as many claimants may share
+    /// one.
+    Ip,
+}
+
+impl LimitScope {
+    /// Determines the scope of a request from its path.
+    fn from_path(path: &str) -> Self {
+        match path.rsplit('/').nth(2) {
+            Some("ip_limiter") => LimitScope::Ip,
+            _ => LimitScope::Wallet,
+        }
+    }
+
+    /// Maximum amount that can be claimed per [`FaucetInfo::reset_limiter_seconds`]. Claims made
+    /// with an API key are capped per wallet by the key's quota.
+    fn cap(&self, faucet_info: &FaucetInfo, quota: Option<&ApiKeyQuota>) -> DripAmount {
+        match (self, quota) {
+            (LimitScope::Wallet, Some(quota)) => quota.wallet_cap(faucet_info),
+            (LimitScope::Wallet, None) => faucet_info.wallet_cap(),
+            (LimitScope::Ip, _) => faucet_info.ip_cap(),
+        }
+    }
+
+    /// The limit reported once [`LimitScope::cap`] is reached.
+    fn cap_reason(&self) -> RateLimitReason {
+        match self {
+            LimitScope::Wallet => RateLimitReason::WalletCap,
+            LimitScope::Ip => RateLimitReason::IpCap,
+        }
+    }
+
+    /// Number of seconds to wait between two claims.
+    fn cooldown_seconds(&self, faucet_info: &FaucetInfo) -> i64 {
+        match self {
+            LimitScope::Wallet => faucet_info.rate_limit_seconds(),
+            LimitScope::Ip => 0,
+        }
+    }
+}
+
+/// Parses a request path, e.g., `/rate_limiter/{faucet_info}/{id}`.
+pub(super) fn parse_request_path(path: &str) -> Result<(FaucetInfo, String)> {
+    let mut path_info = path.split('/');
+    let id = path_info.next_back().unwrap_or_default().to_string();
+    let faucet_info = FaucetInfo::from_str(path_info.next_back().unwrap_or_default())
+        .map_err(|e| Error::RustError(e.to_string()))?;
+    Ok((faucet_info, id))
+}
+
+/// Parses a reservation path, e.g., `/commit/{faucet_info}/{id}/{reservation_id}`.
+pub(super) fn parse_reservation_path(path: &str) -> Result<(FaucetInfo, String, String)> {
+    let (path, reservation_id) = path
+        .rsplit_once('/')
+        .ok_or_else(|| Error::RustError(format!("Invalid reservation path: {path}")))?;
+    let (faucet_info, id) = parse_request_path(path)?;
+    Ok((faucet_info, id, reservation_id.to_string()))
+}
+
+/// Returns the number of seconds until the alarm resetting the object fires.
+pub(super) async fn seconds_until_alarm<S: RateLimiterStorage>(
+    storage: &S,
+    now: DateTime<Utc>,
+) -> i64 {
+    storage
+        .get_alarm()
+        .await
+        .ok()
+        .flatten()
+        .map(|alarm| Duration::milliseconds(alarm - now.timestamp_millis()).num_seconds())
+        .unwrap_or(0)
+}
+
+/// Schedules the alarm resetting the object, unless one is already pending.
+pub(super) async fn ensure_reset_alarm<S: RateLimiterStorage>(
+    storage: &S,
+    faucet_info: &FaucetInfo,
+) -> Result<()> {
+    if storage.get_alarm().await?.is_none() {
+        storage
+            .set_alarm(std::time::Duration::from_secs(
+                faucet_info.reset_limiter_seconds() as u64,
+            ))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Quota held by the rate limiter on behalf of an admitted request until the drip is either
+/// committed (a message CID or transaction hash exists) or released (the drip failed).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Reservation {
+    /// Identifier of the claimant the quota was reserved for.
+    id: String,
+    /// Amount added to the `claimed_{id}` counter.
+    amount: DripAmount,
+    /// Unix timestamp (seconds) at which the reservation was made.
+    reserved_at: i64,
+    /// Unix timestamp (seconds) the `block_until_{id}` cool-down was set to by this reservation.
+    block_until: i64,
+    /// Unix timestamp (seconds) after which the reservation can no longer be released.
+    expires_at: i64,
+}
+
+/// Storage key of a reservation, shared by the rate limiter and the drip budget.
+pub(super) fn reservation_key(reservation_id: &str) -> String {
+    format!("reservation_{reservation_id}")
+}
+
+/// Storage key of the cool-down of the given claimant.
+fn block_until_key(id: &str) -> String {
+    format!("block_until_{id}")
+}
+
+/// Outcome of [`RateLimiterCore::get_rate_limit`].
+#[derive(Debug)]
+enum RateLimitStatus {
+    /// The request can proceed. Contains the amount claimed so far.
+    Allowed { claimed: DripAmount },
+    /// The request is rate limited by the given limit.
+    Limited {
+        reason: RateLimitReason,
+        retry_after: i64,
+    },
+}
+
+/// Reply of the rate limiter or the drip budget to a reservation request.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RateLimiterReply {
+    /// The request is admitted and the quota is held under the given reservation ID.
+    Reserved(String),
+    /// The request is rate limited. Contains the number of seconds to wait before retrying and
+    /// the limit that was hit.
+    Limited {
+        retry_after: i64,
+        reason: RateLimitReason,
+    },
+}
+
+#[cfg(test)]
+impl RateLimiterReply {
+    /// Returns the number of seconds to wait before retrying, if the request was rate limited.
+    pub fn retry_after(&self) -> Option<i64> {
+        match self {
+            RateLimiterReply::Reserved(_) => None,
+            RateLimiterReply::Limited { retry_after, .. } => Some(*retry_after),
+        }
+    }
+
+    /// Returns the limit that was hit, if the request was rate limited.
+    pub fn reason(&self) -> Option<RateLimitReason> {
+        match self {
+            RateLimiterReply::Reserved(_) => None,
+            RateLimiterReply::Limited { reason, .. } => Some(*reason),
+        }
+    }
+}
+
+/// Core logic for the per-wallet and per-IP rate limiting, generic over a storage backend.
+/// Each instance only holds the state of a single claimant (a wallet or a client IP, see
+/// [`LimitScope`]) of a single faucet, i.e., the amount claimed and the cool-down. The global drip cap is tracked by the drip budget coordinator in
+/// [`super::drip_budget`] instead.
 /// It is used by the [`RateLimiter`] durable object handler in production and by mocks in tests.
 struct RateLimiterCore<S: RateLimiterStorage> {
     storage: S,
@@ -78,27 +289,16 @@ impl<S: RateLimiterStorage> RateLimiterCore<S> {
         Self { storage }
     }
 
-    fn parse_request_path(path: &str) -> Result<(FaucetInfo, String)> {
-        let mut path_info = path.split('/');
-        let id = path_info.next_back().unwrap_or_default().to_string();
-        let faucet_info = FaucetInfo::from_str(path_info.next_back().unwrap_or_default())
-            .map_err(|e| Error::RustError(e.to_string()))?;
-        Ok((faucet_info, id))
-    }
-
+    /// Checks the cap of the claimant first and its cool-down second. The first limit hit is
+    /// reported.
     async fn get_rate_limit(
         &self,
         faucet_info: &FaucetInfo,
+        scope: LimitScope,
+        quota: Option<&ApiKeyQuota>,
         id: &str,
         now: DateTime<Utc>,
-    ) -> Result<(bool, Option<i64>, DripAmount, DripAmount)> {
-        let dripped = self
-            .storage
-            .get::<DripAmount>("dripped")
-            .await
-            .ok()
-            .flatten()
-            .unwrap_or(DripAmount::zero(faucet_info.token_type()));
+    ) -> Result<RateLimitStatus> {
         let claimed = self
             .storage
             .get::<DripAmount>(&format!("claimed_{id}"))
@@ -106,37 +306,19 @@ impl<S: RateLimiterStorage> RateLimiterCore<S> {
             .ok()
             .flatten()
             .unwrap_or(DripAmount::zero(faucet_info.token_type()));
-        if dripped >= faucet_info.drip_cap() {
-            let retry_after = self
-                .storage
-                .get_alarm()
-                .await
-                .ok()
-                .flatten()
-                .map(|alarm| Duration::milliseconds(alarm - now.timestamp_millis()).num_seconds())
-                .unwrap_or(0);
-            log::info!(
-                "{faucet_info} Rate limiter for {id} invoked: Drip capped now={now:?}, dripped={dripped:?}, retry_after={retry_after:?}"
-            );
-            return Ok((false, Some(retry_after), claimed, dripped));
-        }
-        if claimed >= faucet_info.wallet_cap() {
-            let retry_after = self
-                .storage
-                .get_alarm()
-                .await
-                .ok()
-                .flatten()
-                .map(|alarm| Duration::milliseconds(alarm - now.timestamp_millis()).num_seconds())
-                .unwrap_or(0);
+        if claimed >= scope.cap(faucet_info, quota) {
+            let retry_after = seconds_until_alarm(&self.storage, now).await;
             log::info!(
-                "{faucet_info} Rate limiter for {id} invoked: Wallet capped now={now:?}, claimed={claimed:?}, retry_after={retry_after:?}"
+                "{faucet_info} Rate limiter for {id} invoked: {scope:?} capped now={now:?}, claimed={claimed:?}, retry_after={retry_after:?}"
             );
-            return Ok((false, Some(retry_after), claimed, dripped));
+            return Ok(RateLimitStatus::Limited {
+                reason: scope.cap_reason(),
+                retry_after,
+            });
         }
         let block_until = self
             .storage
-            .get::<i64>("block_until")
+            .get::<i64>(&block_until_key(id))
             .await
             .ok()
             .flatten()
@@ -145,64 +327,209 @@ impl<S: RateLimiterStorage> RateLimiterCore<S> {
         if block_until > now {
             let retry_after = block_until.signed_duration_since(now).num_seconds();
             log::info!(
-                "{faucet_info} Rate limiter for {id} invoked: now={now:?}, claimed={claimed:?}, retry_after={retry_after:?}"
+                "{faucet_info} Rate limiter for {id} invoked: Cool-down now={now:?}, claimed={claimed:?}, retry_after={retry_after:?}"
             );
-            return Ok((false, Some(retry_after), claimed, dripped));
+            return Ok(RateLimitStatus::Limited {
+                reason: RateLimitReason::Cooldown,
+                retry_after,
+            });
         }
-        Ok((true, None, claimed, dripped))
+        Ok(RateLimitStatus::Allowed { claimed })
     }
 
     async fn update_rate_limit(
         &self,
         faucet_info: &FaucetInfo,
+        scope: LimitScope,
+        quota: Option<&ApiKeyQuota>,
         id: &str,
         now: DateTime<Utc>,
         claimed: &DripAmount,
-        dripped: &DripAmount,
     ) -> Result<()> {
-        let drip_amount = faucet_info.drip_amount();
-        let update_dripped = dripped + &drip_amount;
-        let updated_claimed = claimed + &drip_amount;
-        let next_block = now + Duration::seconds(faucet_info.rate_limit_seconds());
-        self.storage.put("dripped", update_dripped.clone()).await?;
+        let updated_claimed = claimed + &drip_amount(faucet_info, quota);
+        let next_block = now + Duration::seconds(scope.cooldown_seconds(faucet_info));
         self.storage
             .put(&format!("claimed_{id}"), updated_claimed.clone())
             .await?;
         self.storage
-            .put("block_until", next_block.timestamp())
+            .put(&block_until_key(id), next_block.timestamp())
             .await?;
-        if self.storage.get_alarm().await?.is_none() {
-            self.storage
-                .set_alarm(std::time::Duration::from_secs(
-                    faucet_info.reset_limiter_seconds() as u64,
-                ))
-                .await?;
-        }
+        ensure_reset_alarm(&self.storage, faucet_info).await?;
         log::info!(
-            "{faucet_info} Rate limiter for {id} set: now={now:?}, block_until={next_block:?}, claimed={updated_claimed:?}, dripped={update_dripped:?}"
+            "{faucet_info} Rate limiter for {id} set: now={now:?}, block_until={next_block:?}, claimed={updated_claimed:?}"
         );
         Ok(())
     }
 
-    #[allow(dead_code)]
-    async fn handle_request(&self, path: &str, now: DateTime<Utc>) -> Result<Option<i64>> {
-        let (faucet_info, id) = Self::parse_request_path(path)?;
-        let (is_allowed, retry_after, claimed, dripped) =
-            self.get_rate_limit(&faucet_info, &id, now).await?;
-        if is_allowed {
-            self.update_rate_limit(&faucet_info, &id, now, &claimed, &dripped)
+    /// Consumes the quota for an admitted request and records a [`Reservation`] for it.
+    /// Returns the reservation ID.
+    async fn reserve(
+        &self,
+        faucet_info: &FaucetInfo,
+        scope: LimitScope,
+        quota: Option<&ApiKeyQuota>,
+        id: &str,
+        now: DateTime<Utc>,
+        claimed: &DripAmount,
+    ) -> Result<String> {
+        self.update_rate_limit(faucet_info, scope, quota, id, now, claimed)
+            .await?;
+        let reservation_id = uuid::Uuid::new_v4().to_string();
+        let reservation = Reservation {
+            id: id.to_string(),
+            amount: drip_amount(faucet_info, quota),
+            reserved_at: now.timestamp(),
+            block_until: (now + Duration::seconds(scope.cooldown_seconds(faucet_info))).timestamp(),
+            expires_at: (now + Duration::seconds(RESERVATION_TTL_SECONDS)).timestamp(),
+        };
+        self.storage
+            .put(&reservation_key(&reservation_id), reservation)
+            .await?;
+        Ok(reservation_id)
+    }
+
+    /// Reserves the quota for a claim, e.g., `/rate_limiter/{faucet_info}/{id}`. Claims made with
+    /// an API key carry the key's quota in the query, see [`ApiKeyQuota::to_query`].
+    async fn handle_request(&self, url: &str, now: DateTime<Utc>) -> Result<RateLimiterReply> {
+        let (path, quota) = split_quota(url)?;
+        let (faucet_info, id) = parse_request_path(path)?;
+        let scope = LimitScope::from_path(path);
+        match self
+            .get_rate_limit(&faucet_info, scope, quota.as_ref(), &id, now)
+            .await?
+        {
+            RateLimitStatus::Allowed { claimed } => {
+                let reservation_id = self
+                    .reserve(&faucet_info, scope, quota.as_ref(), &id, now, &claimed)
+                    .await?;
+                Ok(RateLimiterReply::Reserved(reservation_id))
+            }
+            RateLimitStatus::Limited {
+                reason,
+                retry_after,
+            } => Ok(RateLimiterReply::Limited {
+                retry_after,
+                reason,
+            }),
+        }
+    }
+
+    /// Marks the reservation as final; the quota stays consumed. Returns `false` if the
+    /// reservation is unknown, e.g., it was already settled or the limiter was reset in between.
+    async fn handle_commit(&self, path: &str) -> Result<bool> {
+        let (faucet_info, id, reservation_id) = parse_reservation_path(path)?;
+        if self
+            .get_reservation(&faucet_info, &id, &reservation_id, "commit")
+            .await?
+            .is_none()
+        {
+            return Ok(false);
+        }
+        self.storage
+            .delete(&reservation_key(&reservation_id))
+            .await?;
+        log::info!("{faucet_info} Reservation {reservation_id} for {id} committed");
+        Ok(true)
+    }
+
+    /// Looks up a reservation, checking that it belongs to the claimant `id`. Returns `None` if
+    /// the reservation is unknown.
+    async fn get_reservation(
+        &self,
+        faucet_info: &FaucetInfo,
+        id: &str,
+        reservation_id: &str,
+        action: &str,
+    ) -> Result<Option<Reservation>> {
+        let Some(reservation) = self
+            .storage
+            .get::<Reservation>(&reservation_key(reservation_id))
+            .await?
+        else {
+            log::warn!("{faucet_info} Reservation {reservation_id} for {id} not found on {action}");
+            return Ok(None);
+        };
+        if reservation.id != id {
+            return Err(Error::RustError(format!(
+                "Reservation {reservation_id} does not belong to {id}"
+            )));
+        }
+        Ok(Some(reservation))
+    }
+
+    /// Extends the reservation to [`RESERVATION_TTL_SECONDS`] from now, so that it can still be
+    /// released. This is meant for claims of the drip queue, which are known not to have been
+    /// pushed until they are dispatched, however long they waited. Returns `false` if the
+    /// reservation is unknown.
+    async fn handle_extend(&self, path: &str, now: DateTime<Utc>) -> Result<bool> {
+        let (faucet_info, id, reservation_id) = parse_reservation_path(path)?;
+        let Some(mut reservation) = self
+            .get_reservation(&faucet_info, &id, &reservation_id, "extend")
+            .await?
+        else {
+            return Ok(false);
+        };
+        reservation.expires_at = (now + Duration::seconds(RESERVATION_TTL_SECONDS)).timestamp();
+        self.storage
+            .put(&reservation_key(&reservation_id), reservation)
+            .await?;
+        Ok(true)
+    }
+
+    /// Gives the reserved quota back and lifts the cool-down set by the reservation. Returns
+    /// `false` if nothing was refunded, either because the reservation is unknown or because it
+    /// expired and is therefore considered committed.
+    async fn handle_release(&self, path: &str, now: DateTime<Utc>) -> Result<bool> {
+        let (faucet_info, id, reservation_id) = parse_reservation_path(path)?;
+        let Some(reservation) = self
+            .get_reservation(&faucet_info, &id, &reservation_id, "release")
+            .await?
+        else {
+            return Ok(false);
+        };
+        self.storage
+            .delete(&reservation_key(&reservation_id))
+            .await?;
+        if reservation.expires_at <= now.timestamp() {
+            log::warn!(
+                "{faucet_info} Reservation {reservation_id} for {id} expired, quota stays consumed"
+            );
+            return Ok(false);
+        }
+
+        let zero = DripAmount::zero(faucet_info.token_type());
+        let claimed_key = format!("claimed_{id}");
+        let claimed = self
+            .storage
+            .get::<DripAmount>(&claimed_key)
+            .await?
+            .unwrap_or(zero.clone());
+        let refunded_claimed = (&claimed - &reservation.amount).max(zero);
+        self.storage
+            .put(&claimed_key, refunded_claimed.clone())
+            .await?;
+        // Only lift the cool-down if no other request has extended it in the meantime.
+        let block_until_key = block_until_key(&id);
+        if self.storage.get::<i64>(&block_until_key).await? == Some(reservation.block_until) {
+            self.storage
+                .put(&block_until_key, reservation.reserved_at)
                 .await?;
         }
-        Ok(retry_after)
+        log::info!(
+            "{faucet_info} Reservation {reservation_id} for {id} released: claimed={refunded_claimed:?}"
+        );
+        Ok(true)
     }
 
-    #[allow(dead_code)]
     async fn handle_alarm(&self) -> Result<()> {
         log::info!("Rate limiter alarm triggered. DurableObject will be deleted.");
         self.storage.delete_all().await
     }
 }
 
+/// Durable object holding the rate limiting state of a single claimant of a single faucet. It is
+/// addressed by `{faucet_info}/{id}` for wallets, by `{faucet_info}/key/{key_id}/{id}` for wallets
+/// claiming with an API key and by `{faucet_info}/ip/{id}` for client IPs.
 #[cfg(not(test))]
 #[durable_object]
 pub struct RateLimiter {
@@ -212,34 +539,9 @@ pub struct RateLimiter {
 
 #[cfg(not(test))]
 impl RateLimiter {
-    fn parse_request_path(path: &str) -> Result<(FaucetInfo, String)> {
-        RateLimiterCore::<DurableObjectStorage>::parse_request_path(path)
-    }
     fn create_core(&self) -> RateLimiterCore<DurableObjectStorage<'_>> {
         RateLimiterCore::new(DurableObjectStorage::new(&self.state))
     }
-    async fn get_rate_limit(
-        &self,
-        faucet_info: &FaucetInfo,
-        id: &str,
-        now: DateTime<Utc>,
-    ) -> Result<(bool, Option<i64>, DripAmount, DripAmount)> {
-        self.create_core()
-            .get_rate_limit(faucet_info, id, now)
-            .await
-    }
-    async fn update_rate_limit(
-        &self,
-        faucet_info: &FaucetInfo,
-        id: &str,
-        now: DateTime<Utc>,
-        claimed: &DripAmount,
-        dripped: &DripAmount,
-    ) -> Result<()> {
-        self.create_core()
-            .update_rate_limit(faucet_info, id, now, claimed, dripped)
-            .await
-    }
 }
 
 #[cfg(not(test))]
@@ -250,20 +552,18 @@ impl DurableObject for RateLimiter {
 
     async fn fetch(&self, req: Request) -> Result<Response> {
         let now = Utc::now();
-        let (faucet_info, id) = Self::parse_request_path(&req.path())?;
-        let (is_allowed, retry_after, claimed, dripped) =
-            self.get_rate_limit(&faucet_info, &id, now).await?;
-
-        if is_allowed {
-            self.update_rate_limit(&faucet_info, &id, now, &claimed, &dripped)
-                .await?;
+        let path = req.path();
+        let core = self.create_core();
+        match path.split('/').nth(1) {
+            Some("commit") => Response::from_json(&core.handle_commit(&path).await?),
+            Some("release") => Response::from_json(&core.handle_release(&path, now).await?),
+            Some("extend") => Response::from_json(&core.handle_extend(&path, now).await?),
+            _ => Response::from_json(&core.handle_request(req.url()?.as_str(), now).await?),
         }
-        Response::from_json(&retry_after)
     }
 
     async fn alarm(&self) -> Result<Response> {
-        console_log!("Rate limiter alarm triggered. DurableObject will be deleted.");
-        self.state.storage().delete_all().await.ok();
+        self.create_core().handle_alarm().await.ok();
         Response::ok("OK")
     }
 }
@@ -279,7 +579,6 @@ mod tests {
     /// Configuration for mock storage used in rate limiter tests.
     /// This struct allows tests to specify the initial state and expected behavior of the mock storage backend implementing [`RateLimiterStorage`].
     struct MockStorageConfig<'a> {
-        dripped: Option<DripAmount>,
         claimed: Option<DripAmount>,
         block_until: Option<i64>,
         alarm: Option<i64>,
@@ -290,10 +589,6 @@ mod tests {
 
     fn new_mock_storage(config: MockStorageConfig) -> MockRateLimiterStorage {
         let mut mock_storage = MockRateLimiterStorage::new();
-        mock_storage
-            .expect_get::<DripAmount>()
-            .with(mockall::predicate::eq("dripped"))
-            .returning(move |_| Ok(config.dripped.clone()));
         mock_storage
             .expect_get::<DripAmount>()
             .with(mockall::predicate::eq(format!(
@@ -303,7 +598,10 @@ mod tests {
             .returning(move |_| Ok(config.claimed.clone()));
         mock_storage
             .expect_get::<i64>()
-            .with(mockall::predicate::eq("block_until"))
+            .with(mockall::predicate::eq(format!(
+                "block_until_{}",
+                config.wallet_id
+            )))
             .returning(move |_| Ok(config.block_until));
         mock_storage
             .expect_get_alarm()
@@ -312,25 +610,25 @@ mod tests {
             mock_storage
                 .expect_put::<DripAmount>()
                 .with(
-                    mockall::predicate::eq("dripped"),
+                    mockall::predicate::eq(format!("claimed_{}", config.wallet_id)),
                     mockall::predicate::always(),
                 )
                 .returning(|_, _| Ok(()));
             mock_storage
-                .expect_put::<DripAmount>()
+                .expect_put::<i64>()
                 .with(
-                    mockall::predicate::eq(format!("claimed_{}", config.wallet_id)),
+                    mockall::predicate::eq(format!("block_until_{}", config.wallet_id)),
                     mockall::predicate::always(),
                 )
                 .returning(|_, _| Ok(()));
+            mock_storage.expect_set_alarm().returning(|_| Ok(()));
             mock_storage
-                .expect_put::<i64>()
+                .expect_put::<Reservation>()
                 .with(
-                    mockall::predicate::eq("block_until"),
+                    mockall::predicate::function(|key: &str| key.starts_with("reservation_")),
                     mockall::predicate::always(),
                 )
                 .returning(|_, _| Ok(()));
-            mock_storage.expect_set_alarm().returning(|_| Ok(()));
         }
         mock_storage
     }
@@ -340,7 +638,6 @@ mod tests {
     async fn test_rate_limiter_initial_request() {
         let wallet_id = "test_wallet";
         let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: None,
             claimed: None,
             block_until: None,
             alarm: None,
@@ -350,7 +647,7 @@ mod tests {
         let core = RateLimiterCore::new(mock_storage);
         let now = Utc::now();
         let path = "http://do/rate_limiter/CalibnetFIL/test_wallet";
-        let result = core.handle_request(path, now).await.unwrap();
+        let result = core.handle_request(path, now).await.unwrap().retry_after();
         assert!(result.is_none());
     }
 
@@ -361,7 +658,6 @@ mod tests {
         let now = Utc::now();
         let future_time = now + Duration::seconds(30);
         let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: None,
             claimed: None,
             block_until: Some(future_time.timestamp()),
             alarm: None,
@@ -370,7 +666,7 @@ mod tests {
         });
         let core = RateLimiterCore::new(mock_storage);
         let path = "http://do/rate_limiter/CalibnetFIL/test_wallet";
-        let result = core.handle_request(path, now).await.unwrap();
+        let result = core.handle_request(path, now).await.unwrap().retry_after();
         assert!(result.is_some());
         let retry_after = result.unwrap();
         assert!(retry_after > 0);
@@ -382,12 +678,11 @@ mod tests {
     async fn test_rate_limiter_wallet_cap_exceeded() {
         let now = Utc::now();
         let path = "http://do/rate_limiter/CalibnetFIL/test_wallet";
-        let faucet_info = FaucetInfo::CalibnetFIL;
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
         let exceeded_amount =
             &faucet_info.wallet_cap() + &DripAmount::Token(TokenAmount::from_whole(1));
         let alarm_time = now.timestamp_millis() + 3600 * 1000; // 1 hour from now
         let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: None,
             claimed: Some(exceeded_amount),
             block_until: None,
             alarm: Some(alarm_time),
@@ -395,45 +690,19 @@ mod tests {
             expect_puts: false,
         });
         let core = RateLimiterCore::new(mock_storage);
-        let result = core.handle_request(path, now).await.unwrap();
+        let result = core.handle_request(path, now).await.unwrap().retry_after();
         assert!(result.is_some());
         let retry_after = result.unwrap();
         assert!(retry_after > 0);
         assert!(retry_after <= 3600);
     }
 
-    /// Checks that a request is rate limited if the global drip cap is exceeded.
-    #[tokio::test]
-    async fn test_rate_limiter_drip_cap_exceeded() {
-        let now = Utc::now();
-        let path = "http://do/rate_limiter/CalibnetFIL/test_wallet";
-        let faucet_info = FaucetInfo::CalibnetFIL;
-        let exceeded_amount =
-            &faucet_info.drip_cap() + &DripAmount::Token(TokenAmount::from_whole(1));
-        let alarm_time = now.timestamp_millis() + 7200 * 1000; // 2 hours from now
-        let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: Some(exceeded_amount),
-            claimed: None,
-            block_until: None,
-            alarm: Some(alarm_time),
-            wallet_id: "test_wallet",
-            expect_puts: false,
-        });
-        let core = RateLimiterCore::new(mock_storage);
-        let result = core.handle_request(path, now).await.unwrap();
-        assert!(result.is_some());
-        let retry_after = result.unwrap();
-        assert!(retry_after > 0);
-        assert!(retry_after <= 7200);
-    }
-
     /// Checks that a successful request updates storage as expected.
     #[tokio::test]
     async fn test_rate_limiter_successful_request_updates_storage() {
         let now = Utc::now();
         let path = "http://do/rate_limiter/CalibnetFIL/test_wallet";
         let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: None,
             claimed: None,
             block_until: None,
             alarm: None,
@@ -441,7 +710,7 @@ mod tests {
             expect_puts: true,
         });
         let core = RateLimiterCore::new(mock_storage);
-        let result = core.handle_request(path, now).await.unwrap();
+        let result = core.handle_request(path, now).await.unwrap().retry_after();
         assert!(result.is_none());
     }
 
@@ -449,9 +718,8 @@ mod tests {
     #[tokio::test]
     async fn test_parse_request_path() {
         let path = "http://do/rate_limiter/CalibnetFIL/test_wallet_123";
-        let (faucet_info, id) =
-            RateLimiterCore::<MockRateLimiterStorage>::parse_request_path(path).unwrap();
-        assert_eq!(faucet_info, FaucetInfo::CalibnetFIL);
+        let (faucet_info, id) = parse_request_path(path).unwrap();
+        assert_eq!(faucet_info, "CalibnetFIL".parse::<FaucetInfo>().unwrap());
         assert_eq!(id, "test_wallet_123");
     }
 
@@ -459,7 +727,7 @@ mod tests {
     #[tokio::test]
     async fn test_parse_request_path_invalid_faucet() {
         let path = "http://do/rate_limiter/InvalidFaucet/test_wallet";
-        let result = RateLimiterCore::<MockRateLimiterStorage>::parse_request_path(path);
+        let result = parse_request_path(path);
         assert!(result.is_err());
     }
 
@@ -475,17 +743,15 @@ mod tests {
     /// Simulates a single user's journey to the wallet cap, ensuring rate limiting is enforced at the cap.
     #[tokio::test]
     async fn test_user_journey_to_wallet_cap() {
-        let faucet_info = FaucetInfo::CalibnetFIL;
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
         let wallet_id = "test_wallet_123";
         let path = format!("http://do/rate_limiter/{faucet_info}/{wallet_id}");
         let drip_amount = faucet_info.drip_amount();
         let mut claimed = DripAmount::zero(faucet_info.token_type());
-        let mut dripped = DripAmount::zero(faucet_info.token_type());
 
         // For each request up to wallet cap
         for _ in 0..CALIBNET_PER_WALLET_DRIP_MULTIPLIER {
             let mock_storage = new_mock_storage(MockStorageConfig {
-                dripped: None,
                 claimed: None,
                 block_until: None,
                 alarm: None,
@@ -494,19 +760,13 @@ mod tests {
             });
             let core = RateLimiterCore::new(mock_storage);
             let now = chrono::Utc::now();
-            let result = core.handle_request(&path, now).await.unwrap();
+            let result = core.handle_request(&path, now).await.unwrap().retry_after();
             assert!(result.is_none());
             claimed += &drip_amount;
-            dripped += &drip_amount;
         }
 
         // Final request should hit wallet cap
         let mut mock_storage = MockRateLimiterStorage::new();
-        let dripped_clone = dripped.clone();
-        mock_storage
-            .expect_get::<DripAmount>()
-            .with(mockall::predicate::eq("dripped"))
-            .returning(move |_| Ok(Some(dripped_clone.clone())));
         let claimed_clone = claimed.clone();
         mock_storage
             .expect_get::<DripAmount>()
@@ -519,237 +779,21 @@ mod tests {
             .returning(move || Ok(Some(alarm_time)));
         let core = RateLimiterCore::new(mock_storage);
         let now = chrono::Utc::now();
-        let result = core.handle_request(&path, now).await.unwrap();
-        assert!(result.is_some());
-        let retry_after = result.unwrap();
+        let reply = core.handle_request(&path, now).await.unwrap();
+        assert_eq!(reply.reason(), Some(RateLimitReason::WalletCap));
+        let retry_after = reply.retry_after().unwrap();
         assert!(retry_after > 0 && retry_after <= faucet_info.reset_limiter_seconds());
     }
 
-    /// Simulates multiple users claiming up to the global drip cap, ensuring all are rate limited at the cap.
-    #[tokio::test]
-    async fn test_multiple_user_journey_to_drip_cap() {
-        let faucet_info = FaucetInfo::CalibnetFIL;
-        let wallet_1 = "wallet_1";
-        let wallet_2 = "wallet_2";
-        let wallet_3 = "wallet_3";
-        let drip_amount = faucet_info.drip_amount();
-        let drip_cap = faucet_info.drip_cap();
-        let mut dripped = DripAmount::zero(faucet_info.token_type());
-        let mut claimed_1 = DripAmount::zero(faucet_info.token_type());
-        let mut claimed_2 = DripAmount::zero(faucet_info.token_type());
-        let mut claimed_3 = DripAmount::zero(faucet_info.token_type());
-
-        // Wallet 1: Up to wallet cap
-        for _ in 0..CALIBNET_PER_WALLET_DRIP_MULTIPLIER {
-            let mock_storage = new_mock_storage(MockStorageConfig {
-                dripped: None,
-                claimed: None,
-                block_until: None,
-                alarm: None,
-                wallet_id: wallet_1,
-                expect_puts: true,
-            });
-            let core = RateLimiterCore::new(mock_storage);
-            let now = chrono::Utc::now();
-            let result = core
-                .handle_request(
-                    &format!("http://do/rate_limiter/{faucet_info}/{wallet_1}"),
-                    now,
-                )
-                .await
-                .unwrap();
-            assert!(result.is_none());
-            claimed_1 += &drip_amount;
-            dripped += &drip_amount;
-        }
-        // Wallet 2: Up to wallet cap
-        for _ in 0..CALIBNET_PER_WALLET_DRIP_MULTIPLIER {
-            if dripped >= faucet_info.drip_cap() {
-                break;
-            }
-            let mock_storage = new_mock_storage(MockStorageConfig {
-                dripped: None,
-                claimed: None,
-                block_until: None,
-                alarm: None,
-                wallet_id: wallet_2,
-                expect_puts: true,
-            });
-            let core = RateLimiterCore::new(mock_storage);
-            let now = chrono::Utc::now();
-            let result = core
-                .handle_request(
-                    &format!("http://do/rate_limiter/{faucet_info}/{wallet_2}"),
-                    now,
-                )
-                .await
-                .unwrap();
-            assert!(result.is_none());
-            claimed_2 += &drip_amount;
-            dripped += &drip_amount;
-        }
-        // Wallet 3: Up to drip cap
-        while dripped < drip_cap {
-            let mock_storage = new_mock_storage(MockStorageConfig {
-                dripped: None,
-                claimed: None,
-                block_until: None,
-                alarm: None,
-                wallet_id: wallet_3,
-                expect_puts: true,
-            });
-            let core = RateLimiterCore::new(mock_storage);
-            let now = chrono::Utc::now();
-            let result = core
-                .handle_request(
-                    &format!("http://do/rate_limiter/{faucet_info}/{wallet_3}"),
-                    now,
-                )
-                .await
-                .unwrap();
-            assert!(result.is_none());
-            claimed_3 += &drip_amount;
-            dripped += &drip_amount;
-        }
-        // Now all wallets should be rate limited due to drip cap
-        for wallet in [wallet_1, wallet_2, wallet_3] {
-            let mut mock_storage = MockRateLimiterStorage::new();
-            let dripped_clone = dripped.clone();
-            let claimed_clone = match wallet {
-                w if w == wallet_1 => claimed_1.clone(),
-                w if w == wallet_2 => claimed_2.clone(),
-                _ => claimed_3.clone(),
-            };
-            mock_storage
-                .expect_get::<DripAmount>()
-                .with(mockall::predicate::eq("dripped"))
-                .returning(move |_| Ok(Some(dripped_clone.clone())));
-            mock_storage
-                .expect_get::<DripAmount>()
-                .with(mockall::predicate::eq(format!("claimed_{}", wallet)))
-                .returning(move |_| Ok(Some(claimed_clone.clone())));
-            let alarm_time =
-                chrono::Utc::now().timestamp_millis() + faucet_info.reset_limiter_seconds() * 1000;
-            mock_storage
-                .expect_get_alarm()
-                .returning(move || Ok(Some(alarm_time)));
-            let core = RateLimiterCore::new(mock_storage);
-            let now = chrono::Utc::now();
-            let result = core
-                .handle_request(
-                    &format!("http://do/rate_limiter/{faucet_info}/{wallet}"),
-                    now,
-                )
-                .await
-                .unwrap();
-            assert!(result.is_some());
-        }
-    }
-
-    /// Simulates reaching the drip cap, triggering the alarm reset, and verifies new requests are allowed after reset.
-    #[tokio::test]
-    async fn test_alarm_reset_cycle() {
-        let faucet_info = FaucetInfo::CalibnetFIL;
-        let wallets = ["wallet_1", "wallet_2", "wallet_3", "wallet_4", "wallet_5"];
-        let drip_amount = faucet_info.drip_amount();
-        let drip_cap = faucet_info.drip_cap();
-        let mut dripped = DripAmount::zero(faucet_info.token_type());
-        let mut claimed = std::collections::HashMap::new();
-        for &wallet in &wallets {
-            claimed.insert(wallet, DripAmount::zero(faucet_info.token_type()));
-        }
-        // Use multiple wallets to reach the global cap
-        'outer: for &wallet in &wallets {
-            for _ in 0..2 {
-                if dripped >= drip_cap {
-                    break 'outer;
-                }
-                let mock_storage = new_mock_storage(MockStorageConfig {
-                    dripped: None,
-                    claimed: None,
-                    block_until: None,
-                    alarm: None,
-                    wallet_id: wallet,
-                    expect_puts: true,
-                });
-                let core = RateLimiterCore::new(mock_storage);
-                let now = chrono::Utc::now();
-                let result = core
-                    .handle_request(
-                        &format!("http://do/rate_limiter/{faucet_info}/{}", wallet),
-                        now,
-                    )
-                    .await
-                    .unwrap();
-                assert!(result.is_none());
-                let claimed_amount = claimed.get_mut(wallet).unwrap();
-                *claimed_amount += &drip_amount;
-                dripped += &drip_amount;
-            }
-        }
-        // Verify drip cap is reached
-        let mut mock_storage = MockRateLimiterStorage::new();
-        let dripped_clone = dripped.clone();
-        let claimed_clone = claimed["wallet_1"].clone();
-        mock_storage
-            .expect_get::<DripAmount>()
-            .with(mockall::predicate::eq("dripped"))
-            .returning(move |_| Ok(Some(dripped_clone.clone())));
-        mock_storage
-            .expect_get::<DripAmount>()
-            .with(mockall::predicate::eq(format!("claimed_{}", "wallet_1")))
-            .returning(move |_| Ok(Some(claimed_clone.clone())));
-        let alarm_time =
-            chrono::Utc::now().timestamp_millis() + faucet_info.reset_limiter_seconds() * 1000;
-        mock_storage
-            .expect_get_alarm()
-            .returning(move || Ok(Some(alarm_time)));
-        let core = RateLimiterCore::new(mock_storage);
-        let now = chrono::Utc::now();
-        let result = core
-            .handle_request(
-                &format!("http://do/rate_limiter/{faucet_info}/wallet_1"),
-                now,
-            )
-            .await
-            .unwrap();
-        assert!(result.is_some());
-        // Simulate alarm handler
-        let mut mock_storage = MockRateLimiterStorage::new();
-        mock_storage.expect_delete_all().returning(|| Ok(()));
-        let core = RateLimiterCore::new(mock_storage);
-        core.handle_alarm().await.unwrap();
-        // After alarm, new request should be allowed (storage is reset)
-        let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: None,
-            claimed: None,
-            block_until: None,
-            alarm: None,
-            wallet_id: "wallet_1",
-            expect_puts: true,
-        });
-        let core = RateLimiterCore::new(mock_storage);
-        let now = chrono::Utc::now();
-        let result = core
-            .handle_request(
-                &format!("http://do/rate_limiter/{faucet_info}/wallet_1"),
-                now,
-            )
-            .await
-            .unwrap();
-        assert!(result.is_none());
-    }
-
     /// Simulates the cool-down stages: allowed, blocked, partially blocked, then allowed again.
     #[tokio::test]
     async fn test_cooldown_period_progression() {
-        let faucet_info = FaucetInfo::CalibnetFIL;
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
         let wallet_id = "cooldown_test_wallet";
         let path = format!("http://do/rate_limiter/{faucet_info}/{wallet_id}");
         let now = chrono::Utc::now();
         // Step 1: First request (should succeed)
         let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: None,
             claimed: None,
             block_until: None,
             alarm: None,
@@ -757,12 +801,11 @@ mod tests {
             expect_puts: true,
         });
         let core = RateLimiterCore::new(mock_storage);
-        let result_1 = core.handle_request(&path, now).await.unwrap();
+        let result_1 = core.handle_request(&path, now).await.unwrap().retry_after();
         assert!(result_1.is_none());
         // Step 2: Immediate retry (should be rate limited)
         let block_until = now + chrono::Duration::seconds(faucet_info.rate_limit_seconds());
         let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: None,
             claimed: None,
             block_until: Some(block_until.timestamp()),
             alarm: None,
@@ -770,14 +813,35 @@ mod tests {
             expect_puts: false,
         });
         let core = RateLimiterCore::new(mock_storage);
-        let result_2 = core.handle_request(&path, now).await.unwrap();
+        let reply_2 = core.handle_request(&path, now).await.unwrap();
+        assert_eq!(reply_2.reason(), Some(RateLimitReason::Cooldown));
+        let result_2 = reply_2.retry_after();
         assert!(result_2.is_some());
         let retry_2 = result_2.unwrap();
         assert!((0..=faucet_info.rate_limit_seconds()).contains(&retry_2));
+        // Step 2b: Another wallet is not affected by the cool-down of the first one, as its state
+        // lives in a separate object
+        let other_wallet_id = "other_cooldown_test_wallet";
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            claimed: None,
+            block_until: None,
+            alarm: None,
+            wallet_id: other_wallet_id,
+            expect_puts: true,
+        });
+        let core = RateLimiterCore::new(mock_storage);
+        let other_result = core
+            .handle_request(
+                &format!("http://do/rate_limiter/{faucet_info}/{other_wallet_id}"),
+                now,
+            )
+            .await
+            .unwrap()
+            .retry_after();
+        assert!(other_result.is_none());
         // Step 3: Partial cooldown (should still be rate limited, less time left)
         let partial_block_until = now + chrono::Duration::seconds(1); // 1 second in the future
         let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: None,
             claimed: None,
             block_until: Some(partial_block_until.timestamp()),
             alarm: None,
@@ -785,14 +849,16 @@ mod tests {
             expect_puts: false,
         });
         let core = RateLimiterCore::new(mock_storage);
-        let result_3 = core.handle_request(&path, now).await.unwrap();
+        let reply_3 = core.handle_request(&path, now).await.unwrap();
+        assert_eq!(reply_3.reason(), Some(RateLimitReason::Cooldown));
+        let result_3 = reply_3.retry_after();
         assert!(result_3.is_some());
         let retry_3 = result_3.unwrap();
         assert!((0..=1).contains(&retry_3));
         assert!(retry_3 < retry_2);
+        // Step 4: Cool-down passed (should succeed)
         let past_block_until = now - chrono::Duration::seconds(1);
         let mock_storage = new_mock_storage(MockStorageConfig {
-            dripped: None,
             claimed: None,
             block_until: Some(past_block_until.timestamp()),
             alarm: None,
@@ -800,7 +866,468 @@ mod tests {
             expect_puts: true,
         });
         let core = RateLimiterCore::new(mock_storage);
-        let result_4 = core.handle_request(&path, now).await.unwrap();
+        let result_4 = core.handle_request(&path, now).await.unwrap().retry_after();
         assert!(result_4.is_none());
     }
+
+    /// Checks that the scope of a request is derived from its path.
+    #[test]
+    fn test_limit_scope_from_path() {
+        assert_eq!(
+            LimitScope::from_path("/rate_limiter/CalibnetFIL/t1abc"),
+            LimitScope::Wallet
+        );
+        assert_eq!(
+            LimitScope::from_path("http://do/ip_limiter/CalibnetFIL/203.0.113.0"),
+            LimitScope::Ip
+        );
+        assert_eq!(
+            LimitScope::from_path("http://do/ip_limiter/CalibnetFIL/2001:db8:1:2::"),
+            LimitScope::Ip
+        );
+    }
+
+    /// Checks that a client IP can claim past the wallet cap, as it has a cap of its own.
+    #[tokio::test]
+    async fn test_ip_limiter_allows_beyond_wallet_cap() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let ip = "203.0.113.0";
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            claimed: Some(faucet_info.wallet_cap()),
+            block_until: None,
+            alarm: None,
+            wallet_id: ip,
+            expect_puts: true,
+        });
+        let core = RateLimiterCore::new(mock_storage);
+        let path = format!("http://do/ip_limiter/{faucet_info}/{ip}");
+        let reply = core.handle_request(&path, Utc::now()).await.unwrap();
+        assert!(reply.retry_after().is_none());
+    }
+
+    const QUOTA: ApiKeyQuota = ApiKeyQuota {
+        drip_multiplier: 2,
+        wallet_cap: 10,
+        drip_cap: 100,
+    };
+
+    /// Checks that a wallet claiming with an API key is capped by the key's quota rather than by
+    /// the anonymous wallet cap.
+    #[tokio::test]
+    async fn test_api_key_quota_allows_beyond_wallet_cap() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            claimed: Some(faucet_info.wallet_cap()),
+            block_until: None,
+            alarm: None,
+            wallet_id: "test_wallet",
+            expect_puts: true,
+        });
+        let core = RateLimiterCore::new(mock_storage);
+        let path = format!(
+            "http://do/rate_limiter/{faucet_info}/test_wallet?{}",
+            QUOTA.to_query()
+        );
+        let reply = core.handle_request(&path, Utc::now()).await.unwrap();
+        assert!(reply.retry_after().is_none());
+    }
+
+    /// Checks that a wallet claiming with an API key is rate limited once it reaches the key's
+    /// wallet cap.
+    #[tokio::test]
+    async fn test_api_key_wallet_cap_exceeded() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            claimed: Some(QUOTA.wallet_cap(&faucet_info)),
+            block_until: None,
+            alarm: None,
+            wallet_id: "test_wallet",
+            expect_puts: false,
+        });
+        let core = RateLimiterCore::new(mock_storage);
+        let path = format!(
+            "http://do/rate_limiter/{faucet_info}/test_wallet?{}",
+            QUOTA.to_query()
+        );
+        let reply = core.handle_request(&path, Utc::now()).await.unwrap();
+        assert_eq!(reply.reason(), Some(RateLimitReason::WalletCap));
+    }
+
+    /// Checks that a client IP is rate limited once it reaches the IP cap.
+    #[tokio::test]
+    async fn test_ip_limiter_cap_exceeded() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let ip = "2001:db8:1:2::";
+        let now = Utc::now();
+        let alarm_time = now.timestamp_millis() + 3600 * 1000; // 1 hour from now
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            claimed: Some(faucet_info.ip_cap()),
+            block_until: None,
+            alarm: Some(alarm_time),
+            wallet_id: ip,
+            expect_puts: false,
+        });
+        let core = RateLimiterCore::new(mock_storage);
+        let path = format!("http://do/ip_limiter/{faucet_info}/{ip}");
+        let reply = core.handle_request(&path, now).await.unwrap();
+        assert_eq!(reply.reason(), Some(RateLimitReason::IpCap));
+        let retry_after = reply.retry_after().unwrap();
+        assert!(retry_after > 0 && retry_after <= 3600);
+    }
+
+    /// Checks that a claim from a client IP does not start a cool-down for it.
+    #[tokio::test]
+    async fn test_ip_limiter_has_no_cooldown() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let ip = "203.0.113.0";
+        let now = Utc::now();
+        let mut mock_storage = new_mock_storage(MockStorageConfig {
+            claimed: None,
+            block_until: None,
+            alarm: None,
+            wallet_id: ip,
+            expect_puts: false,
+        });
+        mock_storage
+            .expect_put::<DripAmount>()
+            .with(
+                mockall::predicate::eq(format!("claimed_{ip}")),
+                mockall::predicate::eq(faucet_info.drip_amount()),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_storage
+            .expect_put::<i64>()
+            .with(
+                mockall::predicate::eq(format!("block_until_{ip}")),
+                mockall::predicate::eq(now.timestamp()),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_storage.expect_set_alarm().returning(|_| Ok(()));
+        mock_storage
+            .expect_put::<Reservation>()
+            .withf(move |key, reservation| {
+                key.starts_with("reservation_")
+                    && reservation.id == ip
+                    && reservation.block_until == reservation.reserved_at
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let core = RateLimiterCore::new(mock_storage);
+        let path = format!("http://do/ip_limiter/{faucet_info}/{ip}");
+        let reply = core.handle_request(&path, now).await.unwrap();
+        assert!(reply.retry_after().is_none());
+    }
+
+    /// Builds a reservation for `wallet_id` as stored by [`RateLimiterCore::reserve`] at `now`.
+    fn new_reservation(
+        faucet_info: FaucetInfo,
+        wallet_id: &str,
+        now: DateTime<Utc>,
+    ) -> Reservation {
+        Reservation {
+            id: wallet_id.to_string(),
+            amount: faucet_info.drip_amount(),
+            reserved_at: now.timestamp(),
+            block_until: (now + Duration::seconds(faucet_info.rate_limit_seconds())).timestamp(),
+            expires_at: (now + Duration::seconds(RESERVATION_TTL_SECONDS)).timestamp(),
+        }
+    }
+
+    /// Checks that an admitted request returns a reservation ID.
+    #[tokio::test]
+    async fn test_rate_limiter_returns_reservation() {
+        let mock_storage = new_mock_storage(MockStorageConfig {
+            claimed: None,
+            block_until: None,
+            alarm: None,
+            wallet_id: "test_wallet",
+            expect_puts: true,
+        });
+        let core = RateLimiterCore::new(mock_storage);
+        let path = "http://do/rate_limiter/CalibnetFIL/test_wallet";
+        let reply = core.handle_request(path, Utc::now()).await.unwrap();
+        let RateLimiterReply::Reserved(reservation_id) = reply else {
+            panic!("Expected a reservation, got {reply:?}");
+        };
+        assert!(!reservation_id.is_empty());
+    }
+
+    /// Checks that committing a reservation keeps the quota consumed and only removes the reservation.
+    #[tokio::test]
+    async fn test_commit_reservation() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let reservation = new_reservation(faucet_info, "test_wallet", Utc::now());
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<Reservation>()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .returning(move |_| Ok(Some(reservation.clone())));
+        mock_storage
+            .expect_delete()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .times(1)
+            .returning(|_| Ok(true));
+        mock_storage.expect_put::<DripAmount>().never();
+        mock_storage.expect_put::<i64>().never();
+        let core = RateLimiterCore::new(mock_storage);
+        let committed = core
+            .handle_commit("http://do/commit/CalibnetFIL/test_wallet/abc")
+            .await
+            .unwrap();
+        assert!(committed);
+    }
+
+    /// Checks that committing an unknown reservation is a no-op.
+    #[tokio::test]
+    async fn test_commit_unknown_reservation() {
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<Reservation>()
+            .returning(|_| Ok(None));
+        mock_storage.expect_delete().never();
+        let core = RateLimiterCore::new(mock_storage);
+        let committed = core
+            .handle_commit("http://do/commit/CalibnetFIL/test_wallet/abc")
+            .await
+            .unwrap();
+        assert!(!committed);
+    }
+
+    /// Checks that a reservation cannot be committed on behalf of another claimant.
+    #[tokio::test]
+    async fn test_commit_reservation_wrong_claimant() {
+        let reservation = new_reservation(
+            "CalibnetFIL".parse::<FaucetInfo>().unwrap(),
+            "test_wallet",
+            Utc::now(),
+        );
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<Reservation>()
+            .returning(move |_| Ok(Some(reservation.clone())));
+        mock_storage.expect_delete().never();
+        let core = RateLimiterCore::new(mock_storage);
+        let result = core
+            .handle_commit("http://do/commit/CalibnetFIL/other_wallet/abc")
+            .await;
+        assert!(result.is_err());
+    }
+
+    /// Checks that releasing a reservation refunds the quota and lifts the cool-down.
+    #[tokio::test]
+    async fn test_release_reservation() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let wallet_id = "test_wallet";
+        let now = Utc::now();
+        let reservation = new_reservation(faucet_info, wallet_id, now);
+        let drip_amount = faucet_info.drip_amount();
+
+        let mut mock_storage = MockRateLimiterStorage::new();
+        let stored = reservation.clone();
+        mock_storage
+            .expect_get::<Reservation>()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .returning(move |_| Ok(Some(stored.clone())));
+        mock_storage
+            .expect_delete()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .times(1)
+            .returning(|_| Ok(true));
+        let claimed = drip_amount.clone();
+        mock_storage
+            .expect_get::<DripAmount>()
+            .with(mockall::predicate::eq(format!("claimed_{wallet_id}")))
+            .returning(move |_| Ok(Some(claimed.clone())));
+        let block_until = reservation.block_until;
+        mock_storage
+            .expect_get::<i64>()
+            .with(mockall::predicate::eq(format!("block_until_{wallet_id}")))
+            .returning(move |_| Ok(Some(block_until)));
+        mock_storage
+            .expect_put::<DripAmount>()
+            .with(
+                mockall::predicate::eq(format!("claimed_{wallet_id}")),
+                mockall::predicate::eq(DripAmount::zero(faucet_info.token_type())),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_storage
+            .expect_put::<i64>()
+            .with(
+                mockall::predicate::eq(format!("block_until_{wallet_id}")),
+                mockall::predicate::eq(reservation.reserved_at),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let core = RateLimiterCore::new(mock_storage);
+        let released = core
+            .handle_release("http://do/release/CalibnetFIL/test_wallet/abc", now)
+            .await
+            .unwrap();
+        assert!(released);
+    }
+
+    /// Checks that releasing a reservation does not lift a cool-down extended by another request.
+    #[tokio::test]
+    async fn test_release_reservation_keeps_newer_cooldown() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let wallet_id = "test_wallet";
+        let now = Utc::now();
+        let reservation = new_reservation(faucet_info, wallet_id, now);
+        let drip_amount = faucet_info.drip_amount();
+
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<Reservation>()
+            .returning(move |_| Ok(Some(reservation.clone())));
+        mock_storage.expect_delete().returning(|_| Ok(true));
+        mock_storage
+            .expect_get::<DripAmount>()
+            .returning(move |_| Ok(Some(&drip_amount + &drip_amount)));
+        let newer_block_until = (now + Duration::seconds(90)).timestamp();
+        mock_storage
+            .expect_get::<i64>()
+            .with(mockall::predicate::eq(format!("block_until_{wallet_id}")))
+            .returning(move |_| Ok(Some(newer_block_until)));
+        mock_storage
+            .expect_put::<DripAmount>()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_storage.expect_put::<i64>().never();
+        let core = RateLimiterCore::new(mock_storage);
+        let released = core
+            .handle_release("http://do/release/CalibnetFIL/test_wallet/abc", now)
+            .await
+            .unwrap();
+        assert!(released);
+    }
+
+    /// Checks that an expired reservation is dropped without refunding the quota.
+    #[tokio::test]
+    async fn test_release_expired_reservation() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let reserved_at = Utc::now() - Duration::seconds(RESERVATION_TTL_SECONDS + 1);
+        let reservation = new_reservation(faucet_info, "test_wallet", reserved_at);
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<Reservation>()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .returning(move |_| Ok(Some(reservation.clone())));
+        mock_storage
+            .expect_delete()
+            .with(mockall::predicate::eq("reservation_abc"))
+            .times(1)
+            .returning(|_| Ok(true));
+        mock_storage.expect_get::<DripAmount>().never();
+        mock_storage.expect_put::<DripAmount>().never();
+        mock_storage.expect_put::<i64>().never();
+        let core = RateLimiterCore::new(mock_storage);
+        let released = core
+            .handle_release("http://do/release/CalibnetFIL/test_wallet/abc", Utc::now())
+            .await
+            .unwrap();
+        assert!(!released);
+    }
+
+    /// Checks that a reservation cannot be released on behalf of another claimant.
+    #[tokio::test]
+    async fn test_release_reservation_wrong_claimant() {
+        let reservation = new_reservation(
+            "CalibnetFIL".parse::<FaucetInfo>().unwrap(),
+            "test_wallet",
+            Utc::now(),
+        );
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<Reservation>()
+            .returning(move |_| Ok(Some(reservation.clone())));
+        mock_storage.expect_delete().never();
+        mock_storage.expect_put::<DripAmount>().never();
+        let core = RateLimiterCore::new(mock_storage);
+        let result = core
+            .handle_release("http://do/release/CalibnetFIL/other_wallet/abc", Utc::now())
+            .await;
+        assert!(result.is_err());
+    }
+
+    /// Checks that a reservation extended by the drip queue can still be released after its
+    /// original expiry, i.e., a queued claim dispatched after the TTL that fails gets its quota
+    /// back.
+    #[tokio::test]
+    async fn test_release_extended_reservation() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        let store = MockStore::default();
+        let mut mock_storage = MockRateLimiterStorage::new();
+        expect_stored_values::<DripAmount>(&mut mock_storage, &store);
+        expect_stored_values::<i64>(&mut mock_storage, &store);
+        expect_stored_values::<Reservation>(&mut mock_storage, &store);
+        let delete_store = store.clone();
+        mock_storage
+            .expect_delete()
+            .returning(move |key| Ok(delete_store.lock().unwrap().remove(key).is_some()));
+        mock_storage.expect_get_alarm().returning(|| Ok(None));
+        mock_storage.expect_set_alarm().returning(|_| Ok(()));
+        let core = RateLimiterCore::new(mock_storage);
+
+        let queued_at = Utc::now();
+        let reply = core
+            .handle_request("http://do/rate_limiter/CalibnetFIL/test_wallet", queued_at)
+            .await
+            .unwrap();
+        let RateLimiterReply::Reserved(reservation_id) = reply else {
+            panic!("Expected a reservation, got {reply:?}");
+        };
+        let dispatched_at = queued_at + Duration::seconds(RESERVATION_TTL_SECONDS + 60);
+        let extended = core
+            .handle_extend(
+                &format!("http://do/extend/CalibnetFIL/test_wallet/{reservation_id}"),
+                dispatched_at,
+            )
+            .await
+            .unwrap();
+        assert!(extended);
+        let released = core
+            .handle_release(
+                &format!("http://do/release/CalibnetFIL/test_wallet/{reservation_id}"),
+                dispatched_at + Duration::seconds(5),
+            )
+            .await
+            .unwrap();
+        assert!(released);
+        let claimed: DripAmount =
+            serde_json::from_value(store.lock().unwrap()["claimed_test_wallet"].clone()).unwrap();
+        assert_eq!(claimed, DripAmount::zero(faucet_info.token_type()));
+    }
+
+    /// Checks that a reservation cannot be extended on behalf of another claimant.
+    #[tokio::test]
+    async fn test_extend_reservation_wrong_claimant() {
+        let reservation = new_reservation(
+            "CalibnetFIL".parse::<FaucetInfo>().unwrap(),
+            "test_wallet",
+            Utc::now(),
+        );
+        let mut mock_storage = MockRateLimiterStorage::new();
+        mock_storage
+            .expect_get::<Reservation>()
+            .returning(move |_| Ok(Some(reservation.clone())));
+        mock_storage.expect_put::<Reservation>().never();
+        let core = RateLimiterCore::new(mock_storage);
+        let result = core
+            .handle_extend("http://do/extend/CalibnetFIL/other_wallet/abc", Utc::now())
+            .await;
+        assert!(result.is_err());
+    }
+
+    /// Checks reservation path parsing.
+    #[tokio::test]
+    async fn test_parse_reservation_path() {
+        let path = "http://do/release/CalibnetUSDFC/test_wallet/abc-123";
+        let (faucet_info, id, reservation_id) = parse_reservation_path(path).unwrap();
+        assert_eq!(faucet_info, "CalibnetUSDFC".parse::<FaucetInfo>().unwrap());
+        assert_eq!(id, "test_wallet");
+        assert_eq!(reservation_id, "abc-123");
+    }
 }
@@ -0,0 +1,237 @@
+//! Registry of the faucets, described declaratively in [`faucets.json`](./faucets.json).
+//!
+//! The registry is compiled in. It can be replaced at build time by a JSON array of
+//! [`FaucetConfig`], either inline in the `FAUCETS` environment variable or in the file given by
+//! `FAUCETS_FILE`. The pages of the faucets, the faucet list and the claim API are all generated
+//! from it, see [`super::constants::FaucetInfo`].
+
+use crate::utils::drip_amount::TokenType;
+use crate::utils::network::{CALIBNET, MAINNET, NetworkConfig};
+use anyhow::{bail, ensure};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr as _;
+use std::sync::LazyLock;
+
+/// Faucets compiled in by default.
+const DEFAULT_FAUCETS: &str = include_str!("faucets.json");
+
+/// A faucet, as described in the registry.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaucetConfig {
+    /// Identifier of the faucet, e.g., `CalibnetFIL`, as passed to the claim API.
+    pub id: String,
+    /// Path of the faucet page, under `/faucet/`.
+    pub slug: String,
+    /// Name of the network of the faucet, see [`NetworkConfig`].
+    pub network: String,
+    pub token: TokenType,
+    /// Unit of the token, e.g., `tFIL`. Defaults to the symbol of the token for ERC-20 faucets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// Amount of a drip, in nano units of the token, or in bytes for datacap.
+    pub drip_amount: u64,
+    /// Maximum number of drips per wallet every
+    /// [`super::constants::FaucetInfo::reset_limiter_seconds`].
+    pub wallet_cap_multiplier: i64,
+    /// Maximum number of drips per client IP (or IP prefix) every
+    /// [`super::constants::FaucetInfo::reset_limiter_seconds`].
+    pub ip_cap_multiplier: i64,
+    /// Maximum number of drips across all wallets every
+    /// [`super::constants::FaucetInfo::reset_limiter_seconds`].
+    pub drip_cap_multiplier: i64,
+    /// Cool-down duration in seconds between the drips of a wallet.
+    pub cooldown_seconds: i64,
+    /// Maximum number of drips per minute across all claimants. `0` disables the limit.
+    #[serde(default)]
+    pub max_drips_per_minute: u32,
+    /// Number of leading zero bits required from the proof of work of claims. `0` disables the
+    /// challenge.
+    #[serde(default)]
+    pub pow_difficulty: u8,
+    /// Whether browser drips require a Turnstile human verification. Only effective if a Turnstile
+    /// site key is configured.
+    #[serde(default)]
+    pub human_verification: bool,
+    /// Name of the worker secret holding the private key of the faucet.
+    pub secret_name: String,
+    /// Whether the faucet can be claimed from with the claim API, besides its page.
+    #[serde(default)]
+    pub claim_api: bool,
+    /// Whether the faucet is claimed from by `claim_token_all`, along with the other such faucets.
+    /// Requires `claim_api`.
+    #[serde(default)]
+    pub claim_all: bool,
+    /// Maximum gas limit including buffer.
+    pub max_gas_limit: u64,
+    /// Maximum gas fee cap including buffer, in attoFIL.
+    pub max_gas_fee_cap: u64,
+    /// Maximum gas premium including buffer, in attoFIL.
+    pub max_gas_premium: u64,
+    /// Emoji shown along with the title of the faucet.
+    pub icon: String,
+    /// Heading of the faucet page.
+    pub title: String,
+    /// Title of the faucet page in the browser.
+    pub page_title: String,
+    /// Label of the faucet in the faucet list.
+    pub list_label: String,
+    /// Description of the faucet page, for search engines.
+    pub description: String,
+    /// Additional paragraph shown on the faucet page, as HTML.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub about_html: Option<String>,
+}
+
+/// Parses and validates a registry.
+fn parse_faucets(config: &str) -> anyhow::Result<Vec<FaucetConfig>> {
+    let faucets = serde_json::from_str::<Vec<FaucetConfig>>(config)?;
+    let mut ids = HashSet::new();
+    let mut slugs = HashSet::new();
+    for faucet in &faucets {
+        ensure!(ids.insert(&faucet.id), "Duplicate faucet `{}`", faucet.id);
+        ensure!(
+            slugs.insert(&faucet.slug),
+            "Duplicate faucet slug `{}`",
+            faucet.slug
+        );
+        ensure!(
+            faucet.unit.is_some() || matches!(faucet.token, TokenType::Erc20(_)),
+            "Faucet `{}` has no unit",
+            faucet.id
+        );
+        ensure!(
+            faucet.claim_api || !faucet.claim_all,
+            "Faucet `{}` is claimed from by `claim_token_all` but not open to the claim API",
+            faucet.id
+        );
+        if NetworkConfig::by_name(&faucet.network).is_none() {
+            bail!(
+                "Unknown network `{}` of faucet `{}`",
+                faucet.network,
+                faucet.id
+            );
+        }
+    }
+    Ok(faucets)
+}
+
+/// Applies the environment variables overriding the settings of the default faucets, see the
+/// [environment variables](../../docs/env_variables.md).
+fn apply_env_overrides(mut faucet: FaucetConfig) -> FaucetConfig {
+    let drip_amount = match faucet.id.as_str() {
+        "MainnetFIL" => option_env!("MAINNET_DRIP_AMOUNT"),
+        "CalibnetFIL" => option_env!("CALIBNET_DRIP_AMOUNT"),
+        "CalibnetUSDFC" => option_env!("CALIBNET_USDFC_DRIP_AMOUNT"),
+        "CalibnetDatacap" => option_env!("CALIBNET_DATACAP_DRIP_AMOUNT"),
+        _ => None,
+    };
+    if let Some(drip_amount) = drip_amount.and_then(|s| s.parse().ok()) {
+        faucet.drip_amount = drip_amount;
+    }
+    if faucet.id == "CalibnetUSDFC"
+        && let Some(contract) = option_env!("CALIBNET_USDFC_CONTRACT_ADDRESS")
+            .and_then(|addr| alloy::primitives::Address::from_str(addr).ok())
+    {
+        faucet.token = TokenType::Erc20(contract);
+    }
+    let (max_drips_per_minute, pow_difficulty) = match faucet.network.as_str() {
+        MAINNET => (
+            option_env!("MAINNET_MAX_DRIPS_PER_MINUTE"),
+            option_env!("MAINNET_POW_DIFFICULTY"),
+        ),
+        CALIBNET => (
+            option_env!("CALIBNET_MAX_DRIPS_PER_MINUTE"),
+            option_env!("CALIBNET_POW_DIFFICULTY"),
+        ),
+        _ => (None, None),
+    };
+    if let Some(limit) = max_drips_per_minute.and_then(|s| s.parse().ok()) {
+        faucet.max_drips_per_minute = limit;
+    }
+    if let Some(difficulty) = pow_difficulty.and_then(|s| s.parse().ok()) {
+        faucet.pow_difficulty = difficulty;
+    }
+    faucet
+}
+
+static FAUCETS: LazyLock<Vec<FaucetConfig>> = LazyLock::new(|| {
+    let faucets = match option_env!("FAUCETS") {
+        Some(config) => parse_faucets(config).expect("faucets are validated by build.rs"),
+        None => parse_faucets(DEFAULT_FAUCETS).expect("invalid default faucets"),
+    };
+    faucets.into_iter().map(apply_env_overrides).collect()
+});
+
+/// All the faucets, in the order of the faucet list.
+pub fn faucets() -> &'static [FaucetConfig] {
+    &FAUCETS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_faucets() {
+        let faucets = parse_faucets(DEFAULT_FAUCETS).unwrap();
+        assert_eq!(
+            faucets
+                .iter()
+                .map(|faucet| faucet.id.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "CalibnetUSDFC",
+                "CalibnetFIL",
+                "CalibnetDatacap",
+                "MainnetFIL"
+            ]
+        );
+        assert!(matches!(faucets[0].token, TokenType::Erc20(_)));
+        assert_eq!(faucets[1].token, TokenType::Native);
+        assert_eq!(faucets[2].token, TokenType::Datacap);
+        assert!(faucets[0].about_html.is_some());
+        assert!(!faucets[3].claim_api);
+        // Datacap is only allocated to the recipients claiming it explicitly.
+        assert!(faucets[2].claim_api && !faucets[2].claim_all);
+        assert!(faucets.iter().all(|faucet| !faucet.human_verification));
+    }
+
+    #[test]
+    fn test_invalid_faucets() {
+        let mut faucets = parse_faucets(DEFAULT_FAUCETS).unwrap();
+        faucets.truncate(1);
+
+        let mut duplicate = faucets.clone();
+        duplicate.push(FaucetConfig {
+            slug: "other".to_string(),
+            ..faucets[0].clone()
+        });
+        let config = serde_json::to_string(&duplicate).unwrap();
+        assert!(parse_faucets(&config).is_err());
+
+        let mut unknown_network = faucets.clone();
+        unknown_network[0].network = "unknown".to_string();
+        let config = serde_json::to_string(&unknown_network).unwrap();
+        assert!(parse_faucets(&config).is_err());
+
+        // ERC-20 faucets default to the symbol of their token, other faucets need a unit.
+        let mut without_unit = parse_faucets(DEFAULT_FAUCETS).unwrap();
+        without_unit[0].unit = None;
+        let config = serde_json::to_string(&without_unit[..1]).unwrap();
+        assert!(parse_faucets(&config).is_ok());
+        let config = serde_json::to_string(&without_unit[1..2]).unwrap();
+        assert!(parse_faucets(&config).is_ok());
+        without_unit[1].unit = None;
+        let config = serde_json::to_string(&without_unit[1..2]).unwrap();
+        assert!(parse_faucets(&config).is_err());
+
+        let mut closed = faucets.clone();
+        closed[0].claim_api = false;
+        let config = serde_json::to_string(&closed).unwrap();
+        assert!(parse_faucets(&config).is_err());
+
+        let config = serde_json::to_string(&faucets).unwrap();
+        assert_eq!(parse_faucets(&config).unwrap(), faucets);
+    }
+}
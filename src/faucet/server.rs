@@ -1,20 +1,34 @@
 #![cfg(feature = "ssr")]
 
+use super::api_key::{API_KEYS_BINDING, ApiKey, ApiKeyConfig, api_key_id, parse_bearer};
+use super::challenge::{Challenge, ChallengeError, ChallengeSolution};
 use super::constants::FaucetInfo;
-use crate::utils::address::AnyAddress;
+use super::drip_queue::QueuedClaim;
+use super::human_verifier::{TurnstileVerifier, verify_human};
+use super::rate_limiter::RateLimiterReply;
+use super::server_api::ClaimState;
+use crate::utils::address::{AnyAddress, canonical_address, format_address};
+use crate::utils::erc20::{self, Eip1559Fees};
+use crate::utils::error::{FaucetError, RateLimitReason};
 use crate::utils::key::KeyInfo;
 use crate::utils::key::{Key, sign};
 use crate::utils::lotus_json::{
     LotusJson,
     signed_message::{SignedMessage, message_cid},
 };
+use crate::utils::rpc_context::Provider;
 use alloy::{network::NetworkTransactionBuilder, rpc::types::TransactionRequest};
 use anyhow::Result;
 use axum::Extension;
+use chrono::Utc;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
 use fvm_shared::message::Message;
 use leptos::prelude::ServerFnError;
 use leptos_axum::extract;
 use send_wrapper::SendWrapper;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::str::FromStr as _;
 use std::sync::Arc;
 use worker::*;
@@ -22,13 +36,37 @@ use worker::*;
 /// Reads the faucet secret key from the CloudFlare Worker secrets.
 pub async fn read_faucet_secret(faucet_info: FaucetInfo) -> Result<String, ServerFnError> {
     let Extension(env): Extension<Arc<Env>> = extract().await?;
-    env.secret(faucet_info.secret_key_name())
+    faucet_secret(&env, faucet_info).map_err(ServerFnError::new)
+}
+
+/// Reads the faucet secret key from the given worker environment, see [`read_faucet_secret`].
+pub(super) fn faucet_secret(env: &Env, faucet_info: FaucetInfo) -> Result<String> {
+    let secret = env.secret(faucet_info.secret_key_name())?.to_string();
+    anyhow::ensure!(!secret.is_empty(), "Faucet secret key is empty");
+    Ok(secret)
+}
+
+pub async fn secret_key(faucet_info: FaucetInfo) -> Result<Key, ServerFnError> {
+    let axum::Extension(env): axum::Extension<Arc<worker::Env>> = extract().await?;
+    faucet_key(&env, faucet_info).map_err(ServerFnError::new)
+}
+
+/// Reads the faucet key of a native token faucet from the given worker environment.
+pub(super) fn faucet_key(env: &Env, faucet_info: FaucetInfo) -> Result<Key> {
+    let key_info = KeyInfo::from_str(&env.secret(faucet_info.secret_key_name())?.to_string())?;
+    Key::try_from(key_info)
+}
+
+/// Reads the secret signing the proof-of-work challenges from the CloudFlare Worker secrets.
+async fn read_challenge_secret() -> Result<String, ServerFnError> {
+    let Extension(env): Extension<Arc<Env>> = extract().await?;
+    env.secret("CHALLENGE_SECRET")
         .map(|s| s.to_string())
         .map_err(ServerFnError::new)
         .and_then(|s| {
             if s.is_empty() {
                 Err(ServerFnError::ServerError(
-                    "Faucet secret key is empty".to_string(),
+                    "Challenge secret is empty".to_string(),
                 ))
             } else {
                 Ok(s)
@@ -36,11 +74,110 @@ pub async fn read_faucet_secret(faucet_info: FaucetInfo) -> Result<String, Serve
         })
 }
 
-pub async fn secret_key(faucet_info: FaucetInfo) -> Result<Key, ServerFnError> {
-    let axum::Extension(env): axum::Extension<Arc<worker::Env>> = extract().await?;
-    let key_info = KeyInfo::from_str(&env.secret(faucet_info.secret_key_name())?.to_string())
-        .map_err(ServerFnError::new)?;
-    Key::try_from(key_info).map_err(ServerFnError::new)
+/// Issues a proof-of-work challenge for drips of the given faucet to `address`. Fails if the
+/// faucet does not require one.
+pub async fn issue_challenge(
+    faucet_info: FaucetInfo,
+    address: &Address,
+) -> Result<Challenge, ServerFnError> {
+    let Some(difficulty) = faucet_info.pow_difficulty() else {
+        return Err(ServerFnError::ServerError(format!(
+            "{faucet_info} does not require a proof of work"
+        )));
+    };
+    let secret = SendWrapper::new(read_challenge_secret()).await?;
+    Ok(Challenge::issue(
+        address,
+        difficulty,
+        &secret,
+        Utc::now().timestamp(),
+    ))
+}
+
+/// Verifies the proof of work of a drip to `address`, if the faucet requires one. This is meant to
+/// be done before checking the rate limit, so that unsolved requests never reach the rate limiter.
+/// Claims made with an API key are exempt, as the key already identifies the claimer.
+pub async fn check_challenge(
+    faucet_info: FaucetInfo,
+    address: &Address,
+    solution: Option<&ChallengeSolution>,
+    api_key: Option<&ApiKey>,
+) -> Result<(), FaucetError> {
+    let Some(required) = faucet_info.pow_difficulty().filter(|_| api_key.is_none()) else {
+        return Ok(());
+    };
+    let solution = solution.ok_or(FaucetError::Challenge(ChallengeError::Missing.to_string()))?;
+    let secret = SendWrapper::new(read_challenge_secret()).await?;
+    solution
+        .verify(address, required, &secret, Utc::now().timestamp())
+        .map_err(|e| FaucetError::Challenge(e.to_string()))
+}
+
+/// Verifies that a browser drip was requested by a human, if the faucet requires it. The `token`
+/// is produced by the Turnstile widget of the faucet page.
+pub async fn check_human(faucet_info: FaucetInfo, token: Option<&str>) -> Result<(), FaucetError> {
+    if faucet_info.turnstile_site_key().is_none() {
+        return Ok(());
+    }
+    let token = token.map(str::to_string);
+    SendWrapper::new(async move {
+        let Extension(env): Extension<Arc<Env>> = extract()
+            .await
+            .map_err(|e| FaucetError::Server(e.to_string()))?;
+        let secret_key = env
+            .secret("TURNSTILE_SECRET_KEY")
+            .map_err(|e| FaucetError::Server(e.to_string()))?
+            .to_string();
+        let remote_ip = client_ip().await?.map(|ip| ip.to_string());
+        verify_human(
+            &TurnstileVerifier::new(secret_key),
+            token.as_deref(),
+            remote_ip.as_deref(),
+        )
+        .await
+    })
+    .await
+}
+
+/// Looks up the API key sent in the `Authorization` header of the current request, see
+/// [`super::api_key`]. Returns `None` for anonymous requests, and fails if the key is unknown or
+/// not valid for the faucet.
+pub async fn read_api_key(faucet_info: FaucetInfo) -> Result<Option<ApiKey>, FaucetError> {
+    SendWrapper::new(async move {
+        let headers: axum::http::HeaderMap = extract()
+            .await
+            .map_err(|e| FaucetError::Server(e.to_string()))?;
+        let Some(header) = headers.get(axum::http::header::AUTHORIZATION) else {
+            return Ok(None);
+        };
+        let key = header
+            .to_str()
+            .ok()
+            .and_then(parse_bearer)
+            .ok_or_else(|| FaucetError::ApiKey("expected `Bearer {key}`".to_string()))?;
+        let Extension(env): Extension<Arc<Env>> = extract()
+            .await
+            .map_err(|e| FaucetError::Server(e.to_string()))?;
+        let id = api_key_id(key);
+        let config = env
+            .kv(API_KEYS_BINDING)
+            .map_err(|e| FaucetError::Server(e.to_string()))?
+            .get(&id)
+            .text()
+            .await
+            .map_err(|e| FaucetError::Server(e.to_string()))?
+            .ok_or_else(|| FaucetError::ApiKey("unknown key".to_string()))?;
+        let config: ApiKeyConfig =
+            serde_json::from_str(&config).map_err(|e| FaucetError::Server(e.to_string()))?;
+        if !config.faucets.contains(&faucet_info) {
+            return Err(FaucetError::ApiKey(format!(
+                "key is not valid for {faucet_info}"
+            )));
+        }
+        log::info!("{faucet_info} Claim with the API key of {}", config.name);
+        Ok(Some(ApiKey { id, config }))
+    })
+    .await
 }
 
 /// Signs a message using the faucet's secret key.
@@ -52,22 +189,28 @@ pub async fn sign_with_secret_key(
     faucet_info: FaucetInfo,
 ) -> Result<LotusJson<SignedMessage>, ServerFnError> {
     SendWrapper::new(async move {
-        let cid = message_cid(&msg);
         let key = secret_key(faucet_info).await?;
-        let sig = sign(
-            key.key_info.r#type,
-            &key.key_info.private_key,
-            cid.to_bytes().as_slice(),
-        )
-        .map_err(ServerFnError::new)?;
-        Ok(LotusJson(SignedMessage {
-            message: msg,
-            signature: sig,
-        }))
+        sign_message(&key, msg)
+            .map(LotusJson)
+            .map_err(ServerFnError::new)
     })
     .await
 }
 
+/// Signs a message with the given key, see [`sign_with_secret_key`].
+pub(super) fn sign_message(key: &Key, msg: Message) -> Result<SignedMessage> {
+    let cid = message_cid(&msg);
+    let sig = sign(
+        key.key_info.r#type,
+        &key.key_info.private_key,
+        cid.to_bytes().as_slice(),
+    )?;
+    Ok(SignedMessage {
+        message: msg,
+        signature: sig,
+    })
+}
+
 /// Signs a transaction request using the faucet's secret key.
 ///
 /// Note: it is important to ensure that the `TransactionRequest` is fully controlled by the server
@@ -79,57 +222,781 @@ pub async fn sign_with_eth_secret_key(
 ) -> Result<Vec<u8>, ServerFnError> {
     SendWrapper::new(async move {
         let key = read_faucet_secret(faucet_info).await?;
-        let pk_signer: alloy::signers::local::PrivateKeySigner = std::str::FromStr::from_str(&key)?;
-        let wallet = alloy::network::EthereumWallet::new(pk_signer);
-        let tx_envolope = tx_request.build(&wallet).await;
-        let rlp =
-            alloy::eips::Encodable2718::encoded_2718(&tx_envolope.map_err(ServerFnError::new)?);
-        Ok(rlp)
+        sign_transaction(&key, tx_request)
+            .await
+            .map_err(ServerFnError::new)
     })
     .await
 }
 
-/// Queries the rate limiter for a specific faucet and wallet address.
-/// Returns:
-/// - `None` if no rate limit is set.
-/// - `Some(i32)` containing the remaining cool-down time in seconds.
-async fn query_rate_limiter(
+/// Signs a transaction request with the given secret key, see [`sign_with_eth_secret_key`].
+pub(super) async fn sign_transaction(key: &str, tx_request: TransactionRequest) -> Result<Vec<u8>> {
+    let pk_signer: alloy::signers::local::PrivateKeySigner = std::str::FromStr::from_str(key)?;
+    let wallet = alloy::network::EthereumWallet::new(pk_signer);
+    let tx_envolope = tx_request.build(&wallet).await?;
+    Ok(alloy::eips::Encodable2718::encoded_2718(&tx_envolope))
+}
+
+/// Builds the EIP-1559 transaction transferring `amount` of the ERC-20 token of the faucet to
+/// `recipient`. The amount is converted to the base units of the token, which has the given
+/// `decimals`. The gas limit and fees, which may be supplied by the client, are capped at the
+/// maximums of the faucet.
+pub(super) fn erc20_transfer_request(
     faucet_info: FaucetInfo,
-    wallet_addr: AnyAddress,
-) -> Result<Option<i32>, ServerFnError> {
+    contract_address: alloy::primitives::Address,
+    decimals: u8,
+    recipient: alloy::primitives::Address,
+    amount: &TokenAmount,
+    nonce: u64,
+    fees: &Eip1559Fees,
+) -> TransactionRequest {
+    use crate::utils::conversions::TokenAmountAlloyExt as _;
+    use alloy::network::TransactionBuilder as _;
+
+    // Make sure gas values aren't too high
+    let gas_limit = fees.gas_limit.min(faucet_info.max_gas_limit());
+    let max_fee_per_gas = (&fees.max_fee_per_gas)
+        .min(&faucet_info.max_gas_fee_cap())
+        .clone();
+    let max_priority_fee_per_gas = (&fees.max_priority_fee_per_gas)
+        .min(&faucet_info.max_gas_premium())
+        .min(&max_fee_per_gas)
+        .clone();
+    let per_gas = |fee: &TokenAmount| u128::try_from(fee.atto()).unwrap_or_default();
+    erc20::transfer_request(
+        contract_address,
+        recipient,
+        amount.to_alloy_amount(decimals),
+    )
+    .with_chain_id(faucet_info.chain_id())
+    .with_nonce(nonce)
+    .with_gas_limit(gas_limit)
+    .with_max_fee_per_gas(per_gas(&max_fee_per_gas))
+    .with_max_priority_fee_per_gas(per_gas(&max_priority_fee_per_gas))
+}
+
+/// Outcome of a rate limit check for a drip request.
+pub enum RateLimitCheck {
+    /// The request is admitted. The quota is held by the reservation until it is committed or
+    /// released.
+    Admitted(Reservation),
+    /// The request is rate limited. Contains the number of seconds to wait before retrying and the
+    /// limit that was hit.
+    Limited {
+        retry_after_secs: i32,
+        reason: RateLimitReason,
+    },
+}
+
+/// Quota reserved by the rate limiter for an admitted drip request.
+///
+/// Once a message CID or transaction hash exists, the reservation should be
+/// [`committed`](Reservation::commit). If the drip fails before that, it should be
+/// [`released`](Reservation::release) so the claimant does not lose their allowance. Reservations
+/// that are never settled expire and are treated as committed by the rate limiter.
+#[must_use]
+#[derive(Serialize, Deserialize)]
+pub struct Reservation {
+    faucet_info: FaucetInfo,
+    /// Canonical address of the claimant, see [`claimant_key`].
+    claimant: String,
+    /// ID of the API key the claim is accounted to, if any.
+    api_key: Option<String>,
+    /// `None` if the rate limiter is disabled.
+    id: Option<String>,
+    /// Client IP (or IP prefix) and the ID of the reservation held by its rate limiter. `None` if
+    /// the client IP is unknown.
+    ip: Option<(String, String)>,
+    /// Whether the drip budget of the faucet holds quota under the same ID.
+    in_budget: bool,
+    /// Nonce leased for the message of the drip, if it was signed by the server.
+    nonce: Option<NonceLease>,
+}
+
+impl Reservation {
+    /// Returns a reservation not holding any quota, as handed out when the rate limiter is
+    /// disabled.
+    pub(super) fn unlimited(faucet_info: FaucetInfo, claimant: String) -> Self {
+        Self {
+            faucet_info,
+            claimant,
+            api_key: None,
+            id: None,
+            ip: None,
+            in_budget: false,
+            nonce: None,
+        }
+    }
+
+    /// Keeps the reserved quota consumed.
+    pub async fn commit(self) {
+        SendWrapper::new(async move {
+            match extract::<Extension<Arc<Env>>>().await {
+                Ok(Extension(env)) => self.commit_in(&env).await,
+                Err(e) => log::error!("Failed to commit {} reservation: {e}", self.faucet_info),
+            }
+        })
+        .await
+    }
+
+    /// Gives the reserved quota back to the claimant, and the leased nonce back to the faucet.
+    pub async fn release(self) {
+        SendWrapper::new(async move {
+            match extract::<Extension<Arc<Env>>>().await {
+                Ok(Extension(env)) => self.release_in(&env).await,
+                Err(e) => log::error!("Failed to release {} reservation: {e}", self.faucet_info),
+            }
+        })
+        .await
+    }
+
+    /// Leases the nonce of the drip's message sent by `from` from the faucet's nonce manager, see
+    /// [`NonceLease`]. The nonce the mpool expects next is read from the node rather than taken
+    /// from the client, which could otherwise make the nonce manager skip nonces. The leased nonce
+    /// is given back if the reservation is released.
+    pub async fn lease_nonce(&mut self, from: Address) -> Result<u64, ServerFnError> {
+        SendWrapper::new(async move {
+            let Extension(env): Extension<Arc<Env>> = extract().await?;
+            let rpc = Provider::default_for(self.faucet_info.network());
+            let mpool_nonce = rpc
+                .mpool_get_nonce(from)
+                .await
+                .map_err(ServerFnError::new)?;
+            self.lease_nonce_in(&env, mpool_nonce)
+                .await
+                .map_err(ServerFnError::new)
+        })
+        .await
+    }
+
+    /// Same as [`Reservation::commit`], outside of a server function.
+    pub(super) async fn commit_in(self, env: &Env) {
+        self.settle(env, "commit").await
+    }
+
+    /// Same as [`Reservation::release`], outside of a server function.
+    pub(super) async fn release_in(mut self, env: &Env) {
+        if let Some(nonce) = self.nonce.take() {
+            nonce.release(env).await;
+        }
+        self.settle(env, "release").await
+    }
+
+    /// Extends the reservation, so that it can still be released once it is settled, see
+    /// [`super::rate_limiter::RESERVATION_TTL_SECONDS`]. Meant for claims waiting in the drip
+    /// queue, right before they are dispatched.
+    #[cfg(not(test))]
+    pub(super) async fn extend_in(&self, env: &Env) {
+        self.settle(env, "extend").await
+    }
+
+    /// Same as [`Reservation::lease_nonce`], outside of a server function.
+    pub(super) async fn lease_nonce_in(&mut self, env: &Env, mpool_nonce: u64) -> Result<u64> {
+        let lease = NonceLease::new(env, self.faucet_info, mpool_nonce).await?;
+        let nonce = lease.nonce;
+        self.nonce = Some(lease);
+        Ok(nonce)
+    }
+
+    /// Failing to settle is not fatal: the reservation expires and the rate limiter treats it as
+    /// committed, i.e., it behaves as if the quota had been consumed right away.
+    async fn settle(&self, env: &Env, action: &'static str) {
+        let Some(reservation_id) = &self.id else {
+            return;
+        };
+        let faucet_info = self.faucet_info;
+        let claimant = &self.claimant;
+        let api_key = self.api_key.as_deref();
+        let url = format!("http://do/{action}/{faucet_info}/{claimant}/{reservation_id}");
+        let mut objects = vec![(
+            RATE_LIMITER_BINDING,
+            rate_limiter_name(faucet_info, api_key, claimant),
+            url.clone(),
+        )];
+        if let Some((ip, ip_reservation_id)) = &self.ip {
+            objects.push((
+                RATE_LIMITER_BINDING,
+                ip_limiter_name(faucet_info, ip),
+                format!("http://do/{action}/{faucet_info}/{ip}/{ip_reservation_id}"),
+            ));
+        }
+        if self.in_budget {
+            objects.push((
+                DRIP_BUDGET_BINDING,
+                drip_budget_name(faucet_info, api_key),
+                url,
+            ));
+        }
+        for (binding, name, url) in objects {
+            if let Err(e) =
+                fetch_object::<bool>(env, binding, &name, &url, Method::Post, None).await
+            {
+                log::error!(
+                    "Failed to {action} {faucet_info} reservation for {claimant} in {binding}: {e}"
+                );
+            }
+        }
+    }
+}
+
+/// Durable object binding of the per-wallet and per-IP rate limiters.
+const RATE_LIMITER_BINDING: &str = "RATE_LIMITER";
+/// Durable object binding of the per-faucet drip budgets.
+const DRIP_BUDGET_BINDING: &str = "DRIP_BUDGET";
+/// Durable object binding of the per-key nonce managers.
+const NONCE_MANAGER_BINDING: &str = "NONCE_MANAGER";
+/// Durable object binding of the per-faucet drip queues.
+const DRIP_QUEUE_BINDING: &str = "DRIP_QUEUE";
+
+/// Nonce leased from the nonce manager of a faucet key, so that concurrent drips do not sign
+/// messages with the same nonce. If the message signed with it does not reach the mpool, the nonce
+/// should be released so it is handed out again right away; otherwise this happens once the lease
+/// expires.
+#[derive(Serialize, Deserialize)]
+struct NonceLease {
+    faucet_info: FaucetInfo,
+    nonce: u64,
+}
+
+impl NonceLease {
+    async fn new(env: &Env, faucet_info: FaucetInfo, mpool_nonce: u64) -> Result<Self> {
+        let nonce = fetch_object(
+            env,
+            NONCE_MANAGER_BINDING,
+            faucet_info.secret_key_name(),
+            &format!("http://do/lease/{mpool_nonce}"),
+            Method::Post,
+            None,
+        )
+        .await?;
+        Ok(Self { faucet_info, nonce })
+    }
+
+    async fn release(self, env: &Env) {
+        let Self { faucet_info, nonce } = self;
+        if let Err(e) = fetch_object::<bool>(
+            env,
+            NONCE_MANAGER_BINDING,
+            faucet_info.secret_key_name(),
+            &format!("http://do/release/{nonce}"),
+            Method::Post,
+            None,
+        )
+        .await
+        {
+            log::error!("Failed to release {faucet_info} nonce {nonce}: {e}");
+        }
+    }
+}
+
+/// Queues a claim admitted by the rate limiter on the drip queue of its faucet, see
+/// [`super::drip_queue`]. Returns the ID of the claim. The reservation of the claim is released if
+/// it cannot be queued.
+pub async fn enqueue_claim(claim: QueuedClaim) -> Result<String, ServerFnError> {
+    SendWrapper::new(async move {
+        let Extension(env): Extension<Arc<Env>> = extract().await?;
+        let name = claim.faucet_info.to_string();
+        let queued = match serde_json::to_string(&claim) {
+            Ok(body) => {
+                fetch_object::<String>(
+                    &env,
+                    DRIP_QUEUE_BINDING,
+                    &name,
+                    "http://do/enqueue",
+                    Method::Post,
+                    Some(body),
+                )
+                .await
+            }
+            Err(e) => Err(e.into()),
+        };
+        match queued {
+            Ok(id) => Ok(id),
+            Err(e) => {
+                claim.reservation.release_in(&env).await;
+                Err(ServerFnError::new(e))
+            }
+        }
+    })
+    .await
+}
+
+/// Looks up the status of a claim queued with [`enqueue_claim`]. Returns `None` if the claim is
+/// unknown, or was settled too long ago.
+pub async fn query_claim_status(
+    faucet_info: FaucetInfo,
+    claim_id: &str,
+) -> Result<Option<ClaimState>, ServerFnError> {
+    fetch_limiter(
+        DRIP_QUEUE_BINDING,
+        faucet_info.to_string(),
+        format!("http://do/status/{claim_id}"),
+        Method::Get,
+    )
+    .await
+}
+
+/// Name of the rate limiter object holding the state of the given claimant of the given faucet.
+/// Claims made with an API key are accounted separately for each key.
+fn rate_limiter_name(faucet_info: FaucetInfo, api_key: Option<&str>, claimant: &str) -> String {
+    match api_key {
+        Some(api_key) => format!("{faucet_info}/key/{api_key}/{claimant}"),
+        None => format!("{faucet_info}/{claimant}"),
+    }
+}
+
+/// Name of the drip budget object of the given faucet, or of the given API key for the faucet.
+fn drip_budget_name(faucet_info: FaucetInfo, api_key: Option<&str>) -> String {
+    match api_key {
+        Some(api_key) => format!("{faucet_info}/key/{api_key}"),
+        None => faucet_info.to_string(),
+    }
+}
+
+/// Query string passing the quota of the API key, if any, to the rate limiter and drip budget.
+fn quota_query(api_key: Option<&ApiKey>) -> String {
+    api_key
+        .map(|api_key| format!("?{}", api_key.config.quota.to_query()))
+        .unwrap_or_default()
+}
+
+/// Name of the rate limiter object holding the state of the given client IP (or IP prefix) of the
+/// given faucet.
+fn ip_limiter_name(faucet_info: FaucetInfo, ip: &str) -> String {
+    format!("{faucet_info}/ip/{ip}")
+}
+
+/// Returns the key a client IP is rate limited by. With `prefix_bucketing`, all IPv4 addresses of
+/// the same /24 and all IPv6 addresses of the same /64 share a key, as a single client usually
+/// controls the whole prefix.
+fn ip_key(ip: IpAddr, prefix_bucketing: bool) -> String {
+    let ip = ip.to_canonical();
+    if !prefix_bucketing {
+        return ip.to_string();
+    }
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            Ipv4Addr::new(a, b, c, 0).to_string()
+        }
+        IpAddr::V6(ip) => {
+            let [a, b, c, d, ..] = ip.segments();
+            Ipv6Addr::new(a, b, c, d, 0, 0, 0, 0).to_string()
+        }
+    }
+}
+
+/// Returns the IP of the client of the current request, based on the `CF-Connecting-IP` header set
+/// by CloudFlare. Returns `None` if the header is missing or invalid, e.g., in local setups.
+async fn client_ip() -> Result<Option<IpAddr>, ServerFnError> {
+    let headers: axum::http::HeaderMap = extract().await?;
+    Ok(headers
+        .get("CF-Connecting-IP")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| IpAddr::from_str(value.trim()).ok()))
+}
+
+/// Returns the key the client of the current request is rate limited by, see [`ip_key`].
+async fn client_ip_key(prefix_bucketing: bool) -> Result<Option<String>, ServerFnError> {
+    let ip = client_ip().await?;
+    if ip.is_none() {
+        log::warn!("Client IP unknown, skipping the IP rate limit");
+    }
+    Ok(ip.map(|ip| ip_key(ip, prefix_bucketing)))
+}
+
+/// Returns the key the claimant is rate limited by: its canonical address, formatted for the
+/// network of the faucet. All the forms of the claimant's address (`t0`, `t410`, `0x`, and the
+/// masked ID `0xff00…`) share the same key, so switching between them does not reset its limits.
+async fn claimant_key(
+    faucet_info: FaucetInfo,
+    wallet_addr: &AnyAddress,
+) -> Result<String, ServerFnError> {
+    let network = faucet_info.network();
+    let address = wallet_addr
+        .to_filecoin_address(network)
+        .map_err(ServerFnError::new)?;
+    let canonical = SendWrapper::new(async move {
+        canonical_address(&Provider::default_for(network), address).await
+    })
+    .await
+    .map_err(ServerFnError::new)?;
+    Ok(format_address(&canonical, network.prefix))
+}
+
+/// Sends a request to the named object of the given durable object binding and parses its JSON
+/// reply.
+async fn fetch_limiter<T>(
+    binding: &'static str,
+    name: String,
+    url: String,
+    method: Method,
+) -> Result<T, ServerFnError>
+where
+    T: serde::de::DeserializeOwned + 'static,
+{
     SendWrapper::new(async move {
         let Extension(env): Extension<Arc<Env>> = extract().await?;
-        let rate_limiter = env
-            .durable_object("RATE_LIMITER")?
-            .id_from_name(&faucet_info.to_string())?
-            .get_stub()?;
-        rate_limiter
-            .fetch_with_request(Request::new(
-                &format!("http://do/rate_limiter/{faucet_info}/{wallet_addr}"),
-                Method::Get,
-            )?)
-            .await?
-            .json::<Option<i32>>()
+        fetch_object(&env, binding, &name, &url, method, None)
             .await
             .map_err(ServerFnError::new)
     })
     .await
 }
 
-/// Checks if the request can proceed based on the rate limit for the given faucet.
+/// Same as [`fetch_limiter`], outside of a server function. The `body`, if any, is sent as JSON.
+async fn fetch_object<T>(
+    env: &Env,
+    binding: &str,
+    name: &str,
+    url: &str,
+    method: Method,
+    body: Option<String>,
+) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let stub = env
+        .durable_object(binding)?
+        .id_from_name(name)?
+        .get_stub()?;
+    let mut init = RequestInit::new();
+    init.with_method(method);
+    if let Some(body) = body {
+        let headers = Headers::new();
+        headers.set("Content-Type", "application/json")?;
+        init.with_headers(headers)
+            .with_body(Some(wasm_bindgen::JsValue::from_str(&body)));
+    }
+    let mut response = stub
+        .fetch_with_request(Request::new_with_init(url, &init)?)
+        .await?;
+    Ok(response.json::<T>().await?)
+}
+
+/// Queries the rate limiter for a specific faucet and wallet address. If the request is
+/// admitted, the rate limiter reserves the quota for it.
+async fn query_rate_limiter(
+    faucet_info: FaucetInfo,
+    api_key: Option<&ApiKey>,
+    claimant: &str,
+) -> Result<RateLimiterReply, ServerFnError> {
+    fetch_limiter(
+        RATE_LIMITER_BINDING,
+        rate_limiter_name(faucet_info, api_key.map(|key| key.id.as_str()), claimant),
+        format!(
+            "http://do/rate_limiter/{faucet_info}/{claimant}{}",
+            quota_query(api_key)
+        ),
+        Method::Get,
+    )
+    .await
+}
+
+/// Queries the rate limiter for a specific faucet and client IP. If the request is admitted, the
+/// rate limiter reserves the quota for it.
+async fn query_ip_limiter(
+    faucet_info: FaucetInfo,
+    ip: &str,
+) -> Result<RateLimiterReply, ServerFnError> {
+    fetch_limiter(
+        RATE_LIMITER_BINDING,
+        ip_limiter_name(faucet_info, ip),
+        format!("http://do/ip_limiter/{faucet_info}/{ip}"),
+        Method::Get,
+    )
+    .await
+}
+
+/// Queries the drip budget of a faucet for a request already admitted by the claimant's rate
+/// limiter. If the budget allows the drip, it is held under the same reservation ID.
+async fn query_drip_budget(
+    faucet_info: FaucetInfo,
+    api_key: Option<&ApiKey>,
+    claimant: &str,
+    reservation_id: &str,
+) -> Result<RateLimiterReply, ServerFnError> {
+    fetch_limiter(
+        DRIP_BUDGET_BINDING,
+        drip_budget_name(faucet_info, api_key.map(|key| key.id.as_str())),
+        format!(
+            "http://do/reserve/{faucet_info}/{claimant}/{reservation_id}{}",
+            quota_query(api_key)
+        ),
+        Method::Post,
+    )
+    .await
+}
+
+/// Checks if the request can proceed based on the rate limit for the given faucet. The claimant is
+/// identified by its canonical address, whichever form `wallet_addr` is given in. The
+/// claimant's own limits are checked first, the limits of the client IP second and the global drip
+/// budget of the faucet last. Admitted requests hold a [`Reservation`] that must be settled once
+/// the outcome of the drip is known.
+///
+/// Claims made with an API key are checked against the key's quota instead: the claimant's limits
+/// and the drip budget are those of the key, and the client IP is not limited, as automated
+/// claimers commonly share one.
 pub async fn check_rate_limit(
     faucet_info: FaucetInfo,
     wallet_addr: AnyAddress,
-) -> Result<Option<i32>, ServerFnError> {
+    api_key: Option<&ApiKey>,
+) -> Result<RateLimitCheck, ServerFnError> {
     let axum::Extension(env): axum::Extension<std::sync::Arc<worker::Env>> =
         leptos_axum::extract().await?;
-    let mut rate_limit = None;
     let rate_limiter_disabled = env
         .secret("RATE_LIMITER_DISABLED")
         .map(|v| v.to_string().to_lowercase() == "true")
         .unwrap_or(false);
-    if !rate_limiter_disabled {
-        rate_limit = query_rate_limiter(faucet_info, wallet_addr).await?;
+    if rate_limiter_disabled {
+        return Ok(RateLimitCheck::Admitted(Reservation::unlimited(
+            faucet_info,
+            wallet_addr.to_string(),
+        )));
+    }
+    let ip_prefix_bucketing = env
+        .secret("RATE_LIMITER_IP_BUCKETING")
+        .map(|v| v.to_string().to_lowercase() != "false")
+        .unwrap_or(true);
+    let client_ip = match api_key {
+        Some(_) => None,
+        None => client_ip_key(ip_prefix_bucketing).await?,
+    };
+    let claimant = claimant_key(faucet_info, &wallet_addr).await?;
+    let reservation_id = match query_rate_limiter(faucet_info, api_key, &claimant).await? {
+        RateLimiterReply::Reserved(id) => id,
+        RateLimiterReply::Limited {
+            retry_after,
+            reason,
+        } => {
+            return Ok(RateLimitCheck::Limited {
+                retry_after_secs: retry_after as i32,
+                reason,
+            });
+        }
+    };
+    let mut reservation = Reservation {
+        faucet_info,
+        claimant,
+        api_key: api_key.map(|key| key.id.clone()),
+        id: Some(reservation_id.clone()),
+        ip: None,
+        in_budget: false,
+        nonce: None,
+    };
+    // The claimant should not lose their allowance to a limit of the client IP or of the faucet,
+    // so the reservation is released whenever a subsequent check fails.
+    if let Some(ip) = client_ip {
+        match query_ip_limiter(faucet_info, &ip).await {
+            Ok(RateLimiterReply::Reserved(ip_reservation_id)) => {
+                reservation.ip = Some((ip, ip_reservation_id));
+            }
+            Ok(RateLimiterReply::Limited {
+                retry_after,
+                reason,
+            }) => {
+                reservation.release().await;
+                return Ok(RateLimitCheck::Limited {
+                    retry_after_secs: retry_after as i32,
+                    reason,
+                });
+            }
+            Err(e) => {
+                reservation.release().await;
+                return Err(e);
+            }
+        }
+    }
+    match query_drip_budget(faucet_info, api_key, &reservation.claimant, &reservation_id).await {
+        Ok(RateLimiterReply::Reserved(_)) => {
+            reservation.in_budget = true;
+            Ok(RateLimitCheck::Admitted(reservation))
+        }
+        Ok(RateLimiterReply::Limited {
+            retry_after,
+            reason,
+        }) => {
+            reservation.release().await;
+            Ok(RateLimitCheck::Limited {
+                retry_after_secs: retry_after as i32,
+                reason,
+            })
+        }
+        Err(e) => {
+            reservation.release().await;
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::faucet::api_key::ApiKeyQuota;
+
+    /// Checks that claims made with an API key do not need to solve the proof of work.
+    #[tokio::test]
+    async fn test_check_challenge_api_key_exemption() {
+        let faucet_info = "CalibnetFIL"
+            .parse::<FaucetInfo>()
+            .unwrap()
+            .with_config(|config| config.pow_difficulty = 8);
+        let address = Address::new_id(1234);
+        assert!(matches!(
+            check_challenge(faucet_info, &address, None, None).await,
+            Err(FaucetError::Challenge(_))
+        ));
+        let api_key = ApiKey {
+            id: api_key_id("key"),
+            config: ApiKeyConfig {
+                name: "ci".to_string(),
+                faucets: vec![faucet_info],
+                quota: ApiKeyQuota {
+                    drip_multiplier: 1,
+                    wallet_cap: 10,
+                    drip_cap: 100,
+                },
+            },
+        };
+        assert!(
+            check_challenge(faucet_info, &address, None, Some(&api_key))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_erc20_transfer_request_clamps_fees() {
+        let faucet_info = "CalibnetUSDFC".parse::<FaucetInfo>().unwrap();
+        let contract_address = alloy::primitives::Address::repeat_byte(1);
+        let recipient = alloy::primitives::Address::repeat_byte(2);
+        let amount = TokenAmount::from_whole(5);
+
+        let estimated = Eip1559Fees {
+            gas_limit: 3_000_000,
+            max_fee_per_gas: TokenAmount::from_atto(150_000),
+            max_priority_fee_per_gas: TokenAmount::from_atto(1_000),
+        };
+        let tx = erc20_transfer_request(
+            faucet_info,
+            contract_address,
+            6,
+            recipient,
+            &amount,
+            7,
+            &estimated,
+        );
+        assert_eq!(tx.gas, Some(3_000_000));
+        assert_eq!(tx.max_fee_per_gas, Some(150_000));
+        assert_eq!(tx.max_priority_fee_per_gas, Some(1_000));
+        assert_eq!(tx.gas_price, None);
+        assert_eq!(tx.nonce, Some(7));
+        assert_eq!(tx.chain_id, Some(314159));
+        assert_eq!(
+            tx.input.input().unwrap(),
+            &erc20::transfer_request(
+                contract_address,
+                recipient,
+                alloy::primitives::U256::from(5_000_000)
+            )
+            .input
+            .input()
+            .unwrap()
+            .clone()
+        );
+
+        // Values supplied by the client are capped at the maximums of the faucet.
+        let excessive = Eip1559Fees {
+            gas_limit: u64::MAX,
+            max_fee_per_gas: TokenAmount::from_whole(1),
+            max_priority_fee_per_gas: TokenAmount::from_whole(1),
+        };
+        let tx = erc20_transfer_request(
+            faucet_info,
+            contract_address,
+            6,
+            recipient,
+            &amount,
+            7,
+            &excessive,
+        );
+        assert_eq!(tx.gas, Some(faucet_info.max_gas_limit()));
+        assert_eq!(tx.max_fee_per_gas, Some(200_000));
+        assert_eq!(tx.max_priority_fee_per_gas, Some(200_000));
+
+        // The priority fee never exceeds the fee cap.
+        let inverted = Eip1559Fees {
+            max_priority_fee_per_gas: TokenAmount::from_atto(160_000),
+            ..estimated
+        };
+        let tx = erc20_transfer_request(
+            faucet_info,
+            contract_address,
+            6,
+            recipient,
+            &amount,
+            7,
+            &inverted,
+        );
+        assert_eq!(tx.max_priority_fee_per_gas, Some(150_000));
+
+        // Negative fees are zeroed.
+        let negative = Eip1559Fees {
+            max_fee_per_gas: TokenAmount::from_atto(-1),
+            ..estimated
+        };
+        let tx = erc20_transfer_request(
+            faucet_info,
+            contract_address,
+            6,
+            recipient,
+            &amount,
+            7,
+            &negative,
+        );
+        assert_eq!(tx.max_fee_per_gas, Some(0));
+        assert_eq!(tx.max_priority_fee_per_gas, Some(0));
+    }
+
+    #[test]
+    fn test_limiter_names() {
+        let faucet_info = "CalibnetFIL".parse::<FaucetInfo>().unwrap();
+        assert_eq!(
+            rate_limiter_name(faucet_info, None, "t1abc"),
+            "CalibnetFIL/t1abc"
+        );
+        assert_eq!(
+            rate_limiter_name(faucet_info, Some("ab12"), "t1abc"),
+            "CalibnetFIL/key/ab12/t1abc"
+        );
+        assert_eq!(drip_budget_name(faucet_info, None), "CalibnetFIL");
+        assert_eq!(
+            drip_budget_name(faucet_info, Some("ab12")),
+            "CalibnetFIL/key/ab12"
+        );
+    }
+
+    #[test]
+    fn test_ip_key_ipv4() {
+        let ip = IpAddr::from_str("203.0.113.42").unwrap();
+        assert_eq!(ip_key(ip, false), "203.0.113.42");
+        assert_eq!(ip_key(ip, true), "203.0.113.0");
+    }
+
+    #[test]
+    fn test_ip_key_ipv6() {
+        let ip = IpAddr::from_str("2001:db8:1:2:3:4:5:6").unwrap();
+        assert_eq!(ip_key(ip, false), "2001:db8:1:2:3:4:5:6");
+        assert_eq!(ip_key(ip, true), "2001:db8:1:2::");
+        // Addresses of the same /64 share a key.
+        let other = IpAddr::from_str("2001:db8:1:2:ffff::1").unwrap();
+        assert_eq!(ip_key(other, true), ip_key(ip, true));
+    }
+
+    #[test]
+    fn test_ip_key_ipv4_mapped() {
+        let ip = IpAddr::from_str("::ffff:203.0.113.42").unwrap();
+        assert_eq!(ip_key(ip, false), "203.0.113.42");
+        assert_eq!(ip_key(ip, true), "203.0.113.0");
     }
-    Ok(rate_limit)
 }
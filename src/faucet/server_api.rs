@@ -1,12 +1,11 @@
 //! This file contains the server-side API for the faucet functionality. More fine grained,
 //! server-side functions (that are not exposed to the client) are in the `server` module.
 
-use crate::utils::{
-    address::AnyAddress,
-    lotus_json::{LotusJson, signed_message::SignedMessage},
-};
+use super::challenge::{Challenge, ChallengeSolution};
+use crate::utils::{address::AnyAddress, lotus_json::LotusJson};
 use alloy::primitives::TxHash;
 use anyhow::Result;
+use cid::Cid;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use leptos::{prelude::ServerFnError, server, server_fn::codec::GetUrl};
@@ -18,21 +17,32 @@ use axum::http::StatusCode;
 #[cfg(feature = "ssr")]
 use leptos_axum::ResponseOptions;
 
-#[cfg(feature = "ssr")]
-use alloy::{sol, sol_types::SolCall};
-
 #[cfg(feature = "ssr")]
 use super::server::{
-    check_rate_limit, read_faucet_secret, secret_key, sign_with_eth_secret_key,
-    sign_with_secret_key,
+    RateLimitCheck, Reservation, check_challenge, check_human, check_rate_limit, enqueue_claim,
+    erc20_transfer_request, issue_challenge, query_claim_status, read_api_key, read_faucet_secret,
+    secret_key, sign_with_eth_secret_key, sign_with_secret_key,
 };
 
+#[cfg(feature = "ssr")]
+use super::drip_queue::QueuedClaim;
+
+#[cfg(feature = "ssr")]
+use crate::utils::lotus_json::signed_message::SignedMessage;
+
+#[cfg(feature = "ssr")]
+use super::api_key::{ApiKey, drip_amount};
+
 #[cfg(feature = "ssr")]
 use crate::utils::drip_amount::{DripAmount, TokenType};
 
 use super::constants::FaucetInfo;
+use crate::utils::erc20::Eip1559Fees;
 use crate::utils::error::FaucetError;
 
+#[cfg(feature = "ssr")]
+use crate::utils::error::DatacapRejection;
+
 /// Returns the faucet address. This assumes the faucet in place is a native token faucet.
 #[server]
 async fn faucet_fil_address(faucet_info: FaucetInfo) -> Result<LotusJson<Address>, ServerFnError> {
@@ -55,14 +65,7 @@ pub async fn faucet_address(faucet_info: FaucetInfo) -> Result<AnyAddress, Serve
     use fvm_shared::address;
     match faucet_info.token_type() {
         TokenType::Native | TokenType::Datacap => {
-            match faucet_info.network() {
-                address::Network::Mainnet => {
-                    address::set_current_network(address::Network::Mainnet);
-                }
-                address::Network::Testnet => {
-                    address::set_current_network(address::Network::Testnet);
-                }
-            }
+            address::set_current_network(faucet_info.network().prefix);
             let addr = faucet_fil_address(faucet_info).await?;
             Ok(AnyAddress::Filecoin(addr))
         }
@@ -94,21 +97,44 @@ async fn faucet_eth_address(
     Ok(pk_addr)
 }
 
-/// Signs a Filecoin transfer message to the specified recipient with the given parameters.
-/// The required params are needed so that the server doesn't have to call the provider.
+/// Signs a Filecoin transfer message to the specified recipient with the given parameters and
+/// pushes it to the mpool, returning its CID.
+/// The required params are needed so that the server doesn't have to estimate them.
 /// Note: it's important that the message is constructed server-side to avoid exposing the
 /// `message` to the client, which could lead to security issues if the client were to
 /// manipulate the message data.
 /// This function is used for native Filecoin token transfers.
+/// The message is signed with a nonce leased from the faucet's nonce manager, see
+/// [`Reservation::lease_nonce`].
 #[server]
-pub async fn signed_fil_transfer(
+pub async fn push_fil_transfer(
     to: LotusJson<Address>,
     gas_limit: u64,
     gas_fee_cap: LotusJson<TokenAmount>,
     gas_premium: LotusJson<TokenAmount>,
-    sequence: u64,
     faucet_info: FaucetInfo,
-) -> Result<LotusJson<SignedMessage>, FaucetError> {
+    challenge: Option<ChallengeSolution>,
+    human_token: Option<String>,
+) -> Result<LotusJson<Cid>, FaucetError> {
+    check_challenge(faucet_info, &to.0, challenge.as_ref(), None).await?;
+    check_human(faucet_info, human_token.as_deref()).await?;
+    let (smsg, reservation) =
+        sign_fil_transfer(to, gas_limit, gas_fee_cap, gas_premium, faucet_info).await?;
+    push_message(faucet_info, smsg, reservation)
+        .await
+        .map(LotusJson)
+}
+
+/// Reserves the rate limit quota for the recipient and signs a Filecoin transfer message. The
+/// reservation is released if signing fails, otherwise it is up to the caller to settle it.
+#[cfg(feature = "ssr")]
+async fn sign_fil_transfer(
+    to: LotusJson<Address>,
+    gas_limit: u64,
+    gas_fee_cap: LotusJson<TokenAmount>,
+    gas_premium: LotusJson<TokenAmount>,
+    faucet_info: FaucetInfo,
+) -> Result<(SignedMessage, Reservation), FaucetError> {
     use crate::utils::message::message_transfer_native;
     let LotusJson(to) = to;
     let LotusJson(gas_fee_cap) = gas_fee_cap;
@@ -119,69 +145,142 @@ pub async fn signed_fil_transfer(
             "Invalid DripAmount, Expected Token variant".to_string(),
         ));
     };
-    let rate_limit_seconds =
-        check_rate_limit(faucet_info, AnyAddress::Filecoin(LotusJson(to))).await?;
+    let mut reservation =
+        match check_rate_limit(faucet_info, AnyAddress::Filecoin(LotusJson(to)), None).await? {
+            RateLimitCheck::Admitted(reservation) => reservation,
+            RateLimitCheck::Limited {
+                retry_after_secs,
+                reason,
+            } => {
+                return Err(FaucetError::RateLimited {
+                    retry_after_secs,
+                    reason,
+                });
+            }
+        };
     // Make sure gas values aren't too high
     let gas_limit = gas_limit.min(faucet_info.max_gas_limit());
     let gas_fee_cap = gas_fee_cap.min(faucet_info.max_gas_fee_cap());
     let gas_premium = gas_premium.min(faucet_info.max_gas_premium());
-    if let Some(secs) = rate_limit_seconds {
-        return Err(FaucetError::RateLimited {
-            retry_after_secs: secs,
-        });
+    let signed = async {
+        let from = faucet_address(faucet_info)
+            .await?
+            .to_filecoin_address(faucet_info.network())
+            .map_err(|e| FaucetError::Server(e.to_string()))?;
+        let sequence = reservation.lease_nonce(from).await?;
+        let unsigned_msg = message_transfer_native(
+            from,
+            to,
+            drip_amount,
+            gas_limit,
+            gas_fee_cap,
+            gas_premium,
+            sequence,
+        );
+        let LotusJson(smsg) = sign_with_secret_key(unsigned_msg, faucet_info).await?;
+        Ok::<_, FaucetError>(smsg)
+    }
+    .await;
+    settle_on_error(signed, reservation).await
+}
+
+/// Hands the reservation back to the caller along with the signed payload, or releases it if
+/// signing failed.
+#[cfg(feature = "ssr")]
+async fn settle_on_error<T>(
+    signed: Result<T, FaucetError>,
+    reservation: Reservation,
+) -> Result<(T, Reservation), FaucetError> {
+    match signed {
+        Ok(signed) => Ok((signed, reservation)),
+        Err(e) => {
+            reservation.release().await;
+            Err(e)
+        }
     }
-    let from = faucet_address(faucet_info)
-        .await?
-        .to_filecoin_address(faucet_info.network())
-        .map_err(|e| FaucetError::Server(e.to_string()))?;
-    let unsigned_msg = message_transfer_native(
-        from,
-        to,
-        drip_amount,
-        gas_limit,
-        gas_fee_cap,
-        gas_premium,
-        sequence,
-    );
-    Ok(sign_with_secret_key(unsigned_msg, faucet_info).await?)
 }
 
-/// Signs an ERC-20 transfer transaction to the specified recipient with the given nonce and gas
-/// price. The required params are needed so that the server doesn't have to call the provider.
+/// Signs an ERC-20 transfer transaction to the specified recipient with the given gas limit and
+/// EIP-1559 fees, as estimated by the client, and sends it, returning its hash. The server
+/// caps the fees at the maximums of the faucet. The required params are needed so that the server
+/// doesn't have to estimate them. The decimals of the token are cached once read.
 ///
 /// Note: it's important that the transaction is constructed server-side to avoid exposing the
 /// `calldata` to the client, which could lead to security issues if the client were to
 /// manipulate the transaction data.
+///
+/// The transaction is signed with a nonce leased from the faucet's nonce manager, see
+/// [`Reservation::lease_nonce`].
 #[server]
-pub async fn signed_erc20_transfer(
+pub async fn push_erc20_transfer(
     recipient: alloy::primitives::Address,
-    nonce: u64,
-    gas_price: u64,
+    fees: Eip1559Fees,
+    faucet_info: FaucetInfo,
+    challenge: Option<ChallengeSolution>,
+    human_token: Option<String>,
+) -> Result<TxHash, FaucetError> {
+    use crate::utils::rpc_context::Provider;
+    use send_wrapper::SendWrapper;
+
+    let address = AnyAddress::Ethereum(recipient)
+        .to_filecoin_address(faucet_info.network())
+        .map_err(|e| FaucetError::Server(e.to_string()))?;
+    check_challenge(faucet_info, &address, challenge.as_ref(), None).await?;
+    check_human(faucet_info, human_token.as_deref()).await?;
+    let (signed, reservation) = sign_erc20_transfer(recipient, fees, faucet_info).await?;
+    let rpc = Provider::default_for(faucet_info.network());
+    let pushed =
+        SendWrapper::new(async move { rpc.send_eth_transaction_signed(&signed).await }).await;
+    settle_on_push(pushed, reservation).await
+}
+
+/// Pushes a message signed by the faucet to the mpool, see [`settle_on_push`].
+#[cfg(feature = "ssr")]
+async fn push_message(
     faucet_info: FaucetInfo,
-) -> Result<Vec<u8>, FaucetError> {
-    use crate::utils::conversions::TokenAmountAlloyExt as _;
-    use alloy::network::TransactionBuilder as _;
+    smsg: SignedMessage,
+    reservation: Reservation,
+) -> Result<Cid, FaucetError> {
+    use crate::utils::rpc_context::Provider;
+    use send_wrapper::SendWrapper;
+
+    let rpc = Provider::default_for(faucet_info.network());
+    let pushed = SendWrapper::new(async move { rpc.mpool_push(smsg).await }).await;
+    settle_on_push(pushed, reservation).await
+}
 
+/// Commits the reservation once the message CID or transaction hash of the drip exists, or
+/// releases it if pushing failed.
+#[cfg(feature = "ssr")]
+async fn settle_on_push<T>(
+    pushed: anyhow::Result<T>,
+    reservation: Reservation,
+) -> Result<T, FaucetError> {
+    match pushed {
+        Ok(id) => {
+            reservation.commit().await;
+            Ok(id)
+        }
+        Err(e) => {
+            reservation.release().await;
+            Err(FaucetError::from_rpc(&e))
+        }
+    }
+}
+
+/// Reserves the rate limit quota for the recipient and signs an ERC-20 transfer transaction. The
+/// reservation is released if signing fails, otherwise it is up to the caller to settle it.
+#[cfg(feature = "ssr")]
+async fn sign_erc20_transfer(
+    recipient: alloy::primitives::Address,
+    fees: Eip1559Fees,
+    faucet_info: FaucetInfo,
+) -> Result<(Vec<u8>, Reservation), FaucetError> {
     let DripAmount::Token(drip_amount) = faucet_info.drip_amount() else {
         return Err(FaucetError::Server(
             "Invalid DripAmount, Expected Token variant".to_string(),
         ));
     };
-    let rate_limit_seconds = check_rate_limit(faucet_info, AnyAddress::Ethereum(recipient)).await?;
-    if let Some(secs) = rate_limit_seconds {
-        return Err(FaucetError::RateLimited {
-            retry_after_secs: secs,
-        });
-    }
-    log::info!(
-        "Signing ERC-20 transfer transaction for {faucet_info} to {recipient} with nonce {nonce} and gas price {gas_price}"
-    );
-    sol! {
-        #[sol(rpc)]
-        contract ERC20 {
-            function transfer(address to, uint256 amount) public returns (bool);
-        }
-    }
     let contract_address = match faucet_info.token_type() {
         TokenType::Erc20(addr) => addr,
         _ => {
@@ -190,39 +289,107 @@ pub async fn signed_erc20_transfer(
             ));
         }
     };
-    let gas_limit = faucet_info.max_gas_limit();
-    let calldata =
-        ERC20::transferCall::new((recipient, drip_amount.to_alloy_amount())).abi_encode();
-    let tx = alloy::rpc::types::TransactionRequest::default()
-        .with_to(contract_address)
-        .with_chain_id(faucet_info.chain_id())
-        .with_nonce(nonce)
-        .with_gas_limit(gas_limit)
-        .with_gas_price(gas_price.into())
-        .with_input(calldata);
-    let signed = sign_with_eth_secret_key(tx.clone(), faucet_info).await?;
-    Ok(signed)
+    let decimals = erc20_decimals(faucet_info, contract_address).await?;
+    let mut reservation =
+        match check_rate_limit(faucet_info, AnyAddress::Ethereum(recipient), None).await? {
+            RateLimitCheck::Admitted(reservation) => reservation,
+            RateLimitCheck::Limited {
+                retry_after_secs,
+                reason,
+            } => {
+                return Err(FaucetError::RateLimited {
+                    retry_after_secs,
+                    reason,
+                });
+            }
+        };
+    let signed = async {
+        let from = faucet_address(faucet_info)
+            .await?
+            .to_filecoin_address(faucet_info.network())
+            .map_err(|e| FaucetError::Server(e.to_string()))?;
+        let nonce = reservation.lease_nonce(from).await?;
+        log::info!(
+            "Signing ERC-20 transfer transaction for {faucet_info} to {recipient} with nonce {nonce} and fees {fees:?}"
+        );
+        let tx = erc20_transfer_request(
+            faucet_info,
+            contract_address,
+            decimals,
+            recipient,
+            &drip_amount,
+            nonce,
+            &fees,
+        );
+        sign_with_eth_secret_key(tx, faucet_info)
+            .await
+            .map_err(FaucetError::from)
+    }
+    .await;
+    settle_on_error(signed, reservation).await
+}
+
+/// Returns the decimals of the ERC-20 token of the faucet, read from its contract.
+#[cfg(feature = "ssr")]
+async fn erc20_decimals(
+    faucet_info: FaucetInfo,
+    contract_address: alloy::primitives::Address,
+) -> Result<u8, FaucetError> {
+    use crate::utils::rpc_context::Provider;
+    use send_wrapper::SendWrapper;
+
+    let rpc = Provider::default_for(faucet_info.network());
+    SendWrapper::new(async move { rpc.erc20_metadata(contract_address).await })
+        .await
+        .map(|metadata| metadata.decimals)
+        .map_err(|e| FaucetError::from_rpc(&e))
+}
+
+/// Checks that the faucet verifier can allocate `allowance` of datacap to `recipient`, see
+/// [`crate::utils::datacap::DatacapStatus::check_allocation`].
+#[cfg(feature = "ssr")]
+async fn check_datacap_allocation(
+    faucet_info: FaucetInfo,
+    verifier: Address,
+    recipient: Address,
+    allowance: &fvm_shared::sector::StoragePower,
+) -> Result<(), FaucetError> {
+    use crate::utils::rpc_context::Provider;
+    use send_wrapper::SendWrapper;
+
+    let rpc = Provider::default_for(faucet_info.network());
+    SendWrapper::new(async move { rpc.datacap_status(verifier, recipient).await })
+        .await
+        .map_err(|e| FaucetError::from_rpc(&e))?
+        .check_allocation(allowance)
+        .map_err(FaucetError::Datacap)
 }
 
-/// Signs a Datacap allocation message to the verifreg address with the given parameters.
-/// The required params are needed so that the server doesn't have to call the provider.
+/// Signs a Datacap allocation message to the verifreg address with the given parameters and pushes
+/// it to the mpool, returning its CID.
+/// The gas parameters are estimated by the client. The server only checks the
+/// verifier allowance and the status of the recipient before signing.
 /// Note: it's important that the message is constructed server-side to avoid exposing the
 /// `message` to the client, which could lead to security issues if the client were to
 /// manipulate the message data.
 /// This function is used for granting Datacap to a verified client address.
+/// The nonce is handled as in [`push_fil_transfer`].
 #[server]
-pub async fn signed_datacap_allocation(
+pub async fn push_datacap_allocation(
     to: LotusJson<Address>,
     gas_limit: u64,
     gas_fee_cap: LotusJson<TokenAmount>,
     gas_premium: LotusJson<TokenAmount>,
-    sequence: u64,
     faucet_info: FaucetInfo,
-) -> Result<LotusJson<SignedMessage>, FaucetError> {
+    challenge: Option<ChallengeSolution>,
+    human_token: Option<String>,
+) -> Result<LotusJson<Cid>, FaucetError> {
     use crate::utils::message::{AddVerifiedClientParams, message_grant_datacap_native};
     use fvm_ipld_encoding::RawBytes;
 
     let LotusJson(to) = to;
+    check_challenge(faucet_info, &to, challenge.as_ref(), None).await?;
+    check_human(faucet_info, human_token.as_deref()).await?;
     let LotusJson(gas_fee_cap) = gas_fee_cap;
     let LotusJson(gas_premium) = gas_premium;
 
@@ -231,63 +398,144 @@ pub async fn signed_datacap_allocation(
             "Invalid DripAmount, Expected Storage variant".to_string(),
         ));
     };
-    let rate_limit_seconds =
-        check_rate_limit(faucet_info, AnyAddress::Filecoin(LotusJson(to))).await?;
+    let from = faucet_address(faucet_info)
+        .await?
+        .to_filecoin_address(faucet_info.network())
+        .map_err(|e| FaucetError::Server(e.to_string()))?;
+    check_datacap_allocation(faucet_info, from, to, &allowance).await?;
+    let mut reservation =
+        match check_rate_limit(faucet_info, AnyAddress::Filecoin(LotusJson(to)), None).await? {
+            RateLimitCheck::Admitted(reservation) => reservation,
+            RateLimitCheck::Limited {
+                retry_after_secs,
+                reason,
+            } => {
+                return Err(FaucetError::RateLimited {
+                    retry_after_secs,
+                    reason,
+                });
+            }
+        };
     // Make sure gas values aren't too high
     let gas_limit = gas_limit.min(faucet_info.max_gas_limit());
     let gas_fee_cap = gas_fee_cap.min(faucet_info.max_gas_fee_cap());
     let gas_premium = gas_premium.min(faucet_info.max_gas_premium());
-    if let Some(secs) = rate_limit_seconds {
-        return Err(FaucetError::RateLimited {
-            retry_after_secs: secs,
-        });
+    let signed = async {
+        let sequence = reservation.lease_nonce(from).await?;
+        let params = AddVerifiedClientParams {
+            address: to,
+            allowance,
+        };
+        let unsigned_msg = message_grant_datacap_native(
+            from,
+            RawBytes::new(
+                fvm_ipld_encoding::to_vec(&params)
+                    .map_err(|e| FaucetError::Server(e.to_string()))?,
+            ),
+            gas_limit,
+            gas_fee_cap,
+            gas_premium,
+            sequence,
+        );
+        let LotusJson(smsg) = sign_with_secret_key(unsigned_msg, faucet_info).await?;
+        Ok::<_, FaucetError>(smsg)
     }
-    let from = faucet_address(faucet_info)
-        .await?
-        .to_filecoin_address(faucet_info.network())
-        .map_err(|e| FaucetError::Server(e.to_string()))?;
-    let params = AddVerifiedClientParams {
-        address: to,
-        allowance,
-    };
-    let unsigned_msg = message_grant_datacap_native(
-        from,
-        RawBytes::new(
-            fvm_ipld_encoding::to_vec(&params).map_err(|e| FaucetError::Server(e.to_string()))?,
-        ),
-        gas_limit,
-        gas_fee_cap,
-        gas_premium,
-        sequence,
-    );
-    Ok(sign_with_secret_key(unsigned_msg, faucet_info).await?)
+    .await;
+    let (smsg, reservation) = settle_on_error(signed, reservation).await?;
+    push_message(faucet_info, smsg, reservation)
+        .await
+        .map(LotusJson)
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct ClaimResponse {
     pub faucet_info: FaucetInfo,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tx_hash: Option<TxHash>,
+    pub claim_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<ServerFnError>,
 }
 
+/// Status of a claim queued by [`claim_token`], as reported by [`claim_status`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ClaimState {
+    /// The claim waits to be dispatched.
+    Queued,
+    /// The message of the claim was pushed. Native token claims have a message CID, and a
+    /// transaction hash once the node has indexed the message. ERC-20 token claims have a
+    /// transaction hash.
+    Pushed {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cid: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tx_hash: Option<TxHash>,
+    },
+    /// The claim could not be dispatched. Its quota was given back to the claimant, unless the
+    /// dispatch was interrupted midway. The message of such a claim may have been pushed, so it is
+    /// not dispatched again and its quota stays consumed.
+    Failed { error: String },
+}
+
+/// Server API endpoint issuing the proof-of-work challenge for claims of `faucet_info` tokens to
+/// `address`. Only available for faucets requiring a proof of work, see
+/// [`FaucetInfo::pow_difficulty`].
+#[server(endpoint = "claim_challenge", input = GetUrl)]
+pub async fn claim_challenge(
+    faucet_info: FaucetInfo,
+    address: String,
+) -> Result<Challenge, ServerFnError> {
+    let address = parse_and_validate_address(&address, faucet_info)?;
+    if faucet_info.pow_difficulty().is_none() {
+        set_response_status(StatusCode::BAD_REQUEST);
+    }
+    issue_challenge(faucet_info, &address).await
+}
+
 /// Server API endpoint for claiming calibnet tokens from the faucet.
-/// Returns a transaction ID on successful token claim.
-/// Supports distribution of `CalibnetFIL` and `CalibnetUSDFC` tokens.
-/// Subject to rate limiting to prevent abuse.
+/// Returns a claim ID once the claim is queued, see [`claim_status`] for its outcome.
+/// Supports distribution of `CalibnetFIL` and `CalibnetUSDFC` tokens, and of `CalibnetDatacap`.
+/// Subject to rate limiting to prevent abuse. If the faucet requires a proof of work, the
+/// `challenge` obtained from [`claim_challenge`] and its `solution` are required. Requests with an
+/// API key in the `Authorization` header are exempt from the proof of work, and subject to the
+/// key's quota instead of the anonymous limits, see [`super::api_key`].
 #[server(endpoint = "claim_token", input = GetUrl)]
 pub async fn claim_token(
     faucet_info: FaucetInfo,
     address: String,
-) -> Result<TxHash, ServerFnError> {
+    challenge: Option<Challenge>,
+    solution: Option<u64>,
+) -> Result<String, ServerFnError> {
     use crate::utils::rpc_context::Provider;
     use fvm_shared::address::set_current_network;
     use send_wrapper::SendWrapper;
 
+    if !faucet_info.claim_api() {
+        set_response_status(StatusCode::IM_A_TEAPOT);
+        return Err(ServerFnError::ServerError(
+            "I'm a teapot - requested tokens are not available via this endpoint.".to_string(),
+        ));
+    }
     let network = faucet_info.network();
-    set_current_network(network);
+    set_current_network(network.prefix);
     let recipient = parse_and_validate_address(&address, faucet_info)?;
+    let challenge = challenge
+        .zip(solution)
+        .map(|(challenge, solution)| ChallengeSolution {
+            challenge,
+            solution,
+        });
+    let api_key = read_api_key(faucet_info)
+        .await
+        .map_err(handle_faucet_error)?;
+    check_challenge(
+        faucet_info,
+        &recipient,
+        challenge.as_ref(),
+        api_key.as_ref(),
+    )
+    .await
+    .map_err(handle_faucet_error)?;
     let rpc = Provider::default_for(network);
     let from = faucet_address(faucet_info)
         .await?
@@ -295,38 +543,75 @@ pub async fn claim_token(
         .map_err(ServerFnError::new)?;
 
     SendWrapper::new(async move {
-        match faucet_info {
-            FaucetInfo::MainnetFIL | FaucetInfo::CalibnetDatacap => {
-                set_response_status(StatusCode::IM_A_TEAPOT);
-                Err(ServerFnError::ServerError(
-                    "I'm a teapot - requested tokens are not available via this endpoint."
-                        .to_string(),
-                ))
-            }
-            FaucetInfo::CalibnetFIL => handle_native_claim(faucet_info, recipient, from, rpc).await,
-            FaucetInfo::CalibnetUSDFC => {
-                handle_erc20_claim(faucet_info, recipient, from, rpc).await
-            }
-        }
+        queue_claim(faucet_info, recipient, from, rpc, api_key.as_ref()).await
     })
     .await
 }
 
+/// Server API endpoint reporting the status of a claim queued by [`claim_token`]. Claims are
+/// known until an hour after they were dispatched.
+#[server(endpoint = "claim_status", input = GetUrl)]
+pub async fn claim_status(
+    faucet_info: FaucetInfo,
+    claim_id: String,
+) -> Result<ClaimState, ServerFnError> {
+    use crate::utils::rpc_context::Provider;
+    use send_wrapper::SendWrapper;
+    use std::str::FromStr as _;
+
+    let Some(status) = query_claim_status(faucet_info, &claim_id).await? else {
+        set_response_status(StatusCode::NOT_FOUND);
+        return Err(ServerFnError::ServerError(format!(
+            "Unknown claim: {claim_id}"
+        )));
+    };
+    // The transaction hash of a native token claim is only known once the node has indexed its
+    // message, which usually happens shortly after it was pushed.
+    if let ClaimState::Pushed {
+        cid: Some(cid),
+        tx_hash: None,
+    } = &status
+    {
+        let cid = cid::Cid::from_str(cid).map_err(ServerFnError::new)?;
+        let rpc = Provider::default_for(faucet_info.network());
+        let tx_hash =
+            SendWrapper::new(async move { rpc.eth_get_transaction_hash_by_cid(cid).await })
+                .await
+                .map_err(ServerFnError::new)?;
+        return Ok(ClaimState::Pushed {
+            cid: Some(cid.to_string()),
+            tx_hash,
+        });
+    }
+    Ok(status)
+}
+
+/// Server API endpoint for claiming the tokens of all the faucets marked as such in the registry at
+/// once, i.e., all but the datacap faucet by default.
+/// The optional proof of work is the same as for [`claim_token`], and a single solved challenge of
+/// any of these faucets is valid for all of them.
 #[server(endpoint = "claim_token_all", input = GetUrl)]
-pub async fn claim_token_all(address: String) -> Result<Vec<ClaimResponse>, ServerFnError> {
-    let faucets = [FaucetInfo::CalibnetUSDFC, FaucetInfo::CalibnetFIL];
+pub async fn claim_token_all(
+    address: String,
+    challenge: Option<Challenge>,
+    solution: Option<u64>,
+) -> Result<Vec<ClaimResponse>, ServerFnError> {
+    let faucets = FaucetInfo::all()
+        .filter(|faucet| faucet.claim_all())
+        .collect::<Vec<_>>();
     let mut results = Vec::with_capacity(faucets.len());
 
     for faucet in faucets {
-        let response = match claim_token(faucet, address.clone()).await {
-            Ok(tx_hash) => ClaimResponse {
+        let response = match claim_token(faucet, address.clone(), challenge.clone(), solution).await
+        {
+            Ok(claim_id) => ClaimResponse {
                 faucet_info: faucet,
-                tx_hash: Some(tx_hash),
+                claim_id: Some(claim_id),
                 error: None,
             },
             Err(e) => ClaimResponse {
                 faucet_info: faucet,
-                tx_hash: None,
+                claim_id: None,
                 error: Some(e),
             },
         };
@@ -341,12 +626,12 @@ pub async fn claim_token_all(address: String) -> Result<Vec<ClaimResponse>, Serv
 fn check_valid_address(address: Address, faucet_info: FaucetInfo) -> Result<(), ServerFnError> {
     use fvm_shared::address::Protocol;
 
-    if matches!(faucet_info, FaucetInfo::CalibnetUSDFC)
+    if matches!(faucet_info.token_type(), TokenType::Erc20(_))
         && (address.protocol() != Protocol::Delegated)
     {
         log::error!("Invalid address: {:?}", address);
         set_response_status(StatusCode::BAD_REQUEST);
-        return Err(ServerFnError::ServerError("Invalid address: Only Ethereum-compatible addresses (delegated t4 addresses or native Ethereum 0x addresses) are allowed for Calibnet USDFC token claims.".to_string()));
+        return Err(ServerFnError::ServerError("Invalid address: Only Ethereum-compatible addresses (delegated t4 addresses or native Ethereum 0x addresses) are allowed for ERC-20 token claims.".to_string()));
     }
     Ok(())
 }
@@ -356,7 +641,7 @@ fn parse_and_validate_address(
     address: &str,
     faucet_info: FaucetInfo,
 ) -> Result<Address, ServerFnError> {
-    match crate::utils::address::parse_address(address, faucet_info.network()) {
+    match crate::utils::address::parse_address(address, faucet_info.network().prefix) {
         Ok(addr) => {
             check_valid_address(addr, faucet_info)?;
             Ok(addr)
@@ -372,150 +657,161 @@ fn parse_and_validate_address(
     }
 }
 
+/// Checks that the faucet can cover the drip to `recipient` and its gas. Datacap faucets check the
+/// verifier allowance and the status of the recipient instead of a balance.
 #[cfg(feature = "ssr")]
 async fn ensure_faucet_has_funds(
     rpc: &crate::utils::rpc_context::Provider,
     wallet_address: &Address,
+    recipient: &Address,
     faucet_info: &FaucetInfo,
-) -> Result<(), ServerFnError> {
-    let faucet_balance = rpc
-        .wallet_balance(*wallet_address, &faucet_info.token_type())
-        .await
-        .map_err(ServerFnError::new)?;
-    if faucet_balance < faucet_info.drip_amount() {
-        return Err(ServerFnError::ServerError(
-            "Faucet is empty, Request top-up".to_string(),
-        ));
+    api_key: Option<&ApiKey>,
+) -> Result<(), FaucetError> {
+    let drip_amount = drip_amount(faucet_info, api_key.map(|key| &key.config.quota));
+    match (faucet_info.token_type(), &drip_amount) {
+        (TokenType::Datacap, DripAmount::Storage(allowance)) => {
+            rpc.datacap_status(*wallet_address, *recipient)
+                .await
+                .map_err(|e| FaucetError::from_rpc(&e))?
+                .check_allocation(allowance)
+                .map_err(FaucetError::Datacap)?;
+        }
+        (token_type, _) => {
+            let faucet_balance = rpc
+                .wallet_balance(*wallet_address, &token_type)
+                .await
+                .map_err(|e| FaucetError::from_rpc(&e))?;
+            if faucet_balance < drip_amount {
+                return Err(FaucetError::InsufficientFunds(
+                    "Faucet is empty, Request top-up".to_string(),
+                ));
+            }
+        }
     }
     let max_gas_estimate =
         DripAmount::Token(faucet_info.max_gas_limit() * faucet_info.max_gas_fee_cap());
     let native_balance = rpc
         .wallet_balance(*wallet_address, &TokenType::Native)
         .await
-        .map_err(ServerFnError::new)?;
+        .map_err(|e| FaucetError::from_rpc(&e))?;
     if native_balance < max_gas_estimate {
-        return Err(ServerFnError::ServerError(
+        return Err(FaucetError::InsufficientFunds(
             "Not enough balance to cover gas fees, Request top-up".to_string(),
         ));
     }
     Ok(())
 }
 
+/// Reserves the rate limit quota for the recipient and queues the claim on the drip queue of the
+/// faucet, see [`super::drip_queue`]. Returns the ID of the claim. Claims made with an API key drip
+/// the amount of the key's quota.
 #[cfg(feature = "ssr")]
-async fn handle_native_claim(
+async fn queue_claim(
     faucet_info: FaucetInfo,
     recipient: Address,
     from: Address,
     rpc: crate::utils::rpc_context::Provider,
-) -> Result<TxHash, ServerFnError> {
-    use crate::utils::message::message_transfer;
-
-    let DripAmount::Token(drip_amount) = faucet_info.drip_amount() else {
-        return Err(ServerFnError::ServerError(
-            "Invalid DripAmount, Expected Token variant".to_string(),
-        ));
-    };
-    ensure_faucet_has_funds(&rpc, &from, &faucet_info).await?;
-    let id_address = rpc.lookup_id(recipient).await.unwrap_or_else(|_| {
-        log::debug!("ID lookup failed, using recipient address: {:?}", recipient);
-        recipient
-    });
-    let nonce = rpc
-        .mpool_get_nonce(from)
+    api_key: Option<&ApiKey>,
+) -> Result<String, ServerFnError> {
+    let amount = drip_amount(&faucet_info, api_key.map(|key| &key.config.quota));
+    ensure_faucet_has_funds(&rpc, &from, &recipient, &faucet_info, api_key)
         .await
-        .map_err(ServerFnError::new)?;
-    let raw_msg = message_transfer(from, id_address, drip_amount);
-    let msg = rpc
-        .estimate_gas(raw_msg)
-        .await
-        .map_err(ServerFnError::new)?;
-    match signed_fil_transfer(
-        LotusJson(id_address),
-        msg.gas_limit,
-        LotusJson(msg.gas_fee_cap),
-        LotusJson(msg.gas_premium),
-        nonce,
+        .map_err(handle_faucet_error)?;
+    let reservation = match check_rate_limit(
         faucet_info,
+        AnyAddress::Filecoin(LotusJson(recipient)),
+        api_key,
     )
-    .await
+    .await?
     {
-        Ok(LotusJson(smsg)) => {
-            let cid = rpc.mpool_push(smsg).await.map_err(ServerFnError::new)?;
-            let tx_hash = poll_eth_tx_hash(&rpc, cid).await?;
-            Ok(tx_hash)
-        }
-        Err(err) => Err(handle_faucet_error(err)),
-    }
-}
-
-#[cfg(feature = "ssr")]
-async fn poll_eth_tx_hash(
-    rpc: &crate::utils::rpc_context::Provider,
-    cid: cid::Cid,
-) -> Result<TxHash, ServerFnError> {
-    use std::time::Duration;
-
-    const MAX_ATTEMPTS: usize = 3;
-    const DELAY: Duration = Duration::from_millis(500);
-
-    for attempt in 1..=MAX_ATTEMPTS {
-        match rpc
-            .eth_get_transaction_hash_by_cid(cid)
-            .await
-            .map_err(ServerFnError::new)?
-        {
-            Some(tx_hash) => return Ok(tx_hash),
-            None => {
-                log::debug!("polling tx hash ({attempt}/{MAX_ATTEMPTS})");
-                worker::Delay::from(DELAY).await;
-            }
-        }
-    }
-    Err(ServerFnError::ServerError(format!(
-        "Failed to get tx hash for submitted transaction {cid}"
-    )))
-}
-
-#[cfg(feature = "ssr")]
-async fn handle_erc20_claim(
-    faucet_info: FaucetInfo,
-    recipient: Address,
-    from: Address,
-    rpc: crate::utils::rpc_context::Provider,
-) -> Result<TxHash, ServerFnError> {
-    use crate::utils::address::AddressAlloyExt;
-
-    ensure_faucet_has_funds(&rpc, &from, &faucet_info).await?;
-    let eth_to = recipient.into_eth_address().map_err(ServerFnError::new)?;
-    let nonce = rpc
-        .mpool_get_nonce(from)
-        .await
-        .map_err(ServerFnError::new)?;
-    let gas_price = rpc.gas_price().await.map_err(ServerFnError::new)?;
-
-    match signed_erc20_transfer(eth_to, nonce, gas_price, faucet_info).await {
-        Ok(signed) => {
-            let tx_hash = rpc
-                .send_eth_transaction_signed(&signed)
-                .await
-                .map_err(ServerFnError::new)?;
-            Ok(tx_hash)
+        RateLimitCheck::Admitted(reservation) => reservation,
+        RateLimitCheck::Limited {
+            retry_after_secs,
+            reason,
+        } => {
+            return Err(handle_faucet_error(FaucetError::RateLimited {
+                retry_after_secs,
+                reason,
+            }));
         }
-        Err(err) => Err(handle_faucet_error(err)),
-    }
+    };
+    let claim_id = enqueue_claim(QueuedClaim {
+        faucet_info,
+        recipient: LotusJson(recipient),
+        amount,
+        reservation,
+    })
+    .await?;
+    log::info!("{faucet_info} Queued claim {claim_id} to {recipient}");
+    Ok(claim_id)
 }
 
 #[cfg(feature = "ssr")]
 fn handle_faucet_error(err: FaucetError) -> ServerFnError {
     match err {
-        FaucetError::RateLimited { retry_after_secs } => {
-            log::warn!("Rate limit exceeded: retry_after_secs={}", retry_after_secs);
+        FaucetError::RateLimited {
+            retry_after_secs,
+            reason,
+        } => {
+            log::warn!(
+                "Rate limit exceeded: reason={:?}, retry_after_secs={}",
+                reason,
+                retry_after_secs
+            );
             set_response_status(StatusCode::TOO_MANY_REQUESTS);
             ServerFnError::ServerError(format!(
-                "Too many requests: Rate limited. Try again in {} seconds.",
-                retry_after_secs
+                "Too many requests: Rate limited ({}). Try again in {} seconds.",
+                reason, retry_after_secs
             ))
         }
+        FaucetError::ApiKey(msg) => {
+            log::warn!("Invalid API key: {}", msg);
+            set_response_status(StatusCode::UNAUTHORIZED);
+            ServerFnError::ServerError(format!("Invalid API key: {}", msg))
+        }
+        FaucetError::HumanVerification(msg) => {
+            log::warn!("Human verification failed: {}", msg);
+            set_response_status(StatusCode::FORBIDDEN);
+            ServerFnError::ServerError(format!("Human verification failed: {}", msg))
+        }
+        FaucetError::Challenge(msg) => {
+            log::warn!("Proof of work rejected: {}", msg);
+            set_response_status(StatusCode::FORBIDDEN);
+            ServerFnError::ServerError(format!("Proof of work rejected: {}", msg))
+        }
+        FaucetError::InsufficientFunds(msg) => {
+            log::error!("Faucet is out of funds: {}", msg);
+            set_response_status(StatusCode::SERVICE_UNAVAILABLE);
+            ServerFnError::ServerError(format!("Insufficient funds: {}", msg))
+        }
+        FaucetError::Datacap(rejection) => {
+            if matches!(
+                rejection,
+                DatacapRejection::NotVerifier | DatacapRejection::InsufficientAllowance
+            ) {
+                log::error!("Faucet cannot allocate datacap: {}", rejection);
+                set_response_status(StatusCode::SERVICE_UNAVAILABLE);
+            } else {
+                log::warn!("Datacap allocation rejected: {}", rejection);
+                set_response_status(StatusCode::BAD_REQUEST);
+            }
+            ServerFnError::ServerError(format!("Datacap allocation rejected: {}", rejection))
+        }
+        FaucetError::NonceConflict(msg) => {
+            log::warn!("Nonce conflict: {}", msg);
+            set_response_status(StatusCode::CONFLICT);
+            ServerFnError::ServerError(format!("Nonce conflict: {}. Please try again.", msg))
+        }
+        FaucetError::ActorNotFound(msg) => {
+            log::warn!("Actor not found: {}", msg);
+            set_response_status(StatusCode::NOT_FOUND);
+            ServerFnError::ServerError(format!("Actor not found: {}", msg))
+        }
+        FaucetError::Node(msg) => {
+            log::error!("Node rejected the request: {}", msg);
+            set_response_status(StatusCode::BAD_GATEWAY);
+            ServerFnError::ServerError(format!("Node error: {}", msg))
+        }
         FaucetError::Server(msg) => {
             log::error!("Failed to drip tokens: {}", msg);
             set_response_status(StatusCode::INTERNAL_SERVER_ERROR);
@@ -536,14 +832,14 @@ mod tests {
     use crate::faucet::server_api::*;
 
     fn assert_valid_address(address: &str, faucet: FaucetInfo) {
-        let network = faucet.network();
+        let network = faucet.network().prefix;
         let addr = crate::utils::address::parse_address(address, network).unwrap();
         assert!(check_valid_address(addr, faucet).is_ok());
         assert!(parse_and_validate_address(address, faucet).is_ok());
     }
 
     fn assert_invalid_address(address: &str, faucet: FaucetInfo) {
-        let network = faucet.network();
+        let network = faucet.network().prefix;
         let addr = crate::utils::address::parse_address(address, network).unwrap();
         assert!(check_valid_address(addr, faucet).is_err());
         assert!(parse_and_validate_address(address, faucet).is_err());
@@ -561,7 +857,7 @@ mod tests {
             "0xAe9C4b9508c929966ef37209b336E5796D632CDc",
         ];
         for addr in addresses.iter() {
-            assert_valid_address(addr, FaucetInfo::MainnetFIL);
+            assert_valid_address(addr, "MainnetFIL".parse::<FaucetInfo>().unwrap());
         }
     }
 
@@ -577,7 +873,7 @@ mod tests {
             "0xAe9C4b9508c929966ef37209b336E5796D632CDc",
         ];
         for addr in addresses.iter() {
-            assert_valid_address(addr, FaucetInfo::CalibnetFIL);
+            assert_valid_address(addr, "CalibnetFIL".parse::<FaucetInfo>().unwrap());
         }
     }
 
@@ -596,11 +892,11 @@ mod tests {
         ];
 
         for addr in valid_addresses.iter() {
-            assert_valid_address(addr, FaucetInfo::CalibnetUSDFC);
+            assert_valid_address(addr, "CalibnetUSDFC".parse::<FaucetInfo>().unwrap());
         }
 
         for addr in invalid_addresses.iter() {
-            assert_invalid_address(addr, FaucetInfo::CalibnetUSDFC);
+            assert_invalid_address(addr, "CalibnetUSDFC".parse::<FaucetInfo>().unwrap());
         }
     }
 }
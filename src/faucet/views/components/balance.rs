@@ -25,7 +25,11 @@ pub fn FaucetBalance(faucet: RwSignal<FaucetController>) -> impl IntoView {
                     } else {
                         view! {
                             <p class="balance">
-                                {format_balance(&faucet.get().get_faucet_balance(), &faucet.get().get_fil_unit())}
+                                {format_balance(
+                                    &faucet.get().get_faucet_balance(),
+                                    &faucet.get().get_fil_unit(),
+                                    faucet.get().get_decimals(),
+                                )}
                             </p>
                         }
                             .into_any()
@@ -43,7 +47,13 @@ pub fn TargetBalance(faucet: RwSignal<FaucetController>) -> impl IntoView {
             <strong class="title">Target Balance:</strong>
             <Transition fallback=move || view! { <p>Loading target balance...</p> }>
                 <p class="balance">
-                    {move || format_balance(&faucet.get().get_target_balance(), &faucet.get().get_fil_unit())}
+                    {move || {
+                        format_balance(
+                            &faucet.get().get_target_balance(),
+                            &faucet.get().get_fil_unit(),
+                            faucet.get().get_decimals(),
+                        )
+                    }}
                 </p>
             </Transition>
         </div>
@@ -1,4 +1,5 @@
-use crate::faucet::constants::FaucetInfo;
+use crate::faucet::controller::FaucetController;
+use crate::utils::drip_amount::TokenType;
 use crate::utils::format::format_balance;
 use chrono::Duration;
 use leptos::prelude::*;
@@ -6,19 +7,24 @@ use leptos::{IntoView, component, view};
 
 /// This component renders a user-friendly description of the faucet's token distribution rules and limitations.
 #[component]
-pub fn FaucetDescription(faucet_info: FaucetInfo) -> impl IntoView {
-    let drip_amount = faucet_info.drip_amount();
-    let token_unit = faucet_info.unit();
-    let wallet_cap = faucet_info.wallet_cap();
-    let drip_cap = faucet_info.drip_cap();
+pub fn FaucetDescription(faucet: RwSignal<FaucetController>) -> impl IntoView {
+    let faucet_info = faucet.get_untracked().get_faucet_info();
+    // The unit and decimals of ERC-20 tokens are only known once read from the contract.
+    let format = move |amount| {
+        let faucet = faucet.get();
+        format_balance(&amount, &faucet.get_fil_unit(), faucet.get_decimals())
+    };
+    let drip_amount = move || format(faucet_info.drip_amount());
+    let wallet_cap = move || format(faucet_info.wallet_cap());
+    let drip_cap = move || format(faucet_info.drip_cap());
     let rate_limit_seconds = faucet_info.rate_limit_seconds();
     let reset_limiter_hours = Duration::seconds(faucet_info.reset_limiter_seconds()).num_hours();
     view! {
         <div class="description">
             <p>
-                "This faucet distributes " {format_balance(&drip_amount, token_unit)}
+                "This faucet distributes " {drip_amount}
                 " per request and is rate-limited to 1 request per " {rate_limit_seconds}
-                " seconds. Each wallet address is subject to receive " {format_balance(&wallet_cap, token_unit)}
+                " seconds. Each wallet address is subject to receive " {wallet_cap}
                 " every " {reset_limiter_hours} " hours, and exceeding this limit may result in temporary restrictions."
             </p>
             <p>
@@ -26,9 +32,27 @@ pub fn FaucetDescription(faucet_info: FaucetInfo) -> impl IntoView {
             </p>
             <p>
                 "Faucet funds are limited, so there is a maximum distribution cap of "
-                {format_balance(&drip_cap, token_unit)} " for all users combined every " {reset_limiter_hours}
+                {drip_cap} " for all users combined every " {reset_limiter_hours}
                 " hours. Refills are not guaranteed and occur periodically based on availability."
             </p>
+            {move || {
+                let contract_address = match faucet_info.token_type() {
+                    TokenType::Erc20(contract_address) => contract_address,
+                    _ => return None,
+                };
+                faucet
+                    .get()
+                    .get_token_metadata()
+                    .map(|metadata| {
+                        view! {
+                            <p>
+                                "The " {metadata.symbol} " token is " {metadata.name}
+                                ", an ERC-20 token with " {metadata.decimals} " decimals issued by the contract "
+                                {contract_address.to_string()} "."
+                            </p>
+                        }
+                    })
+            }}
         </div>
     }
 }
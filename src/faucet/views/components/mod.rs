@@ -6,3 +6,4 @@ pub mod layout;
 pub mod nav;
 pub mod rpc_selector;
 pub mod transaction;
+pub mod turnstile;
@@ -1,16 +1,11 @@
 use crate::faucet::views::components::icons::Loader;
+use crate::utils::custom_endpoint::{self, CustomEndpoint};
+use crate::utils::network::{NetworkConfig, networks};
 use crate::utils::rpc_context::{RpcContext, providers_for};
-use fvm_shared::address::Network;
 use leptos::prelude::*;
+use leptos::task::spawn_local;
 use leptos::{IntoView, component, leptos_dom::helpers::event_target_value, view};
 
-fn parse_network(value: &str) -> Network {
-    match value {
-        "mainnet" => Network::Mainnet,
-        _ => Network::Testnet,
-    }
-}
-
 #[component]
 pub fn NetworkSelection(rpc_context: RpcContext) -> impl IntoView {
     view! {
@@ -20,15 +15,26 @@ pub fn NetworkSelection(rpc_context: RpcContext) -> impl IntoView {
             </label>
             <select
                 id="network-select"
-                on:change=move |ev| { rpc_context.set_network(parse_network(&event_target_value(&ev))) }
+                on:change=move |ev| {
+                    if let Some(network) = NetworkConfig::by_name(&event_target_value(&ev)) {
+                        rpc_context.set_network(network);
+                    }
+                }
                 class="dropdown-items"
             >
-                <option value="calibnet" selected=move || rpc_context.network().get() == Network::Testnet>
-                    Calibnet
-                </option>
-                <option value="mainnet" selected=move || rpc_context.network().get() == Network::Mainnet>
-                    Mainnet
-                </option>
+                {networks()
+                    .iter()
+                    .map(|network| {
+                        view! {
+                            <option
+                                value=network.name.clone()
+                                selected=move || rpc_context.network().get().name == network.name
+                            >
+                                {network.label()}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
             </select>
             <div class="dropdown-icon">
                 <svg class="h-4 w-4" fill="none" stroke="currentColor" viewBox="0 0 24 24">
@@ -39,8 +45,14 @@ pub fn NetworkSelection(rpc_context: RpcContext) -> impl IntoView {
     }
 }
 
+/// Value of the option showing the [`CustomEndpointForm`].
+const ADD_CUSTOM_ENDPOINT: &str = "add-custom";
+
 #[component]
-pub fn ProviderSelection(rpc_context: RpcContext) -> impl IntoView {
+pub fn ProviderSelection(
+    rpc_context: RpcContext,
+    show_custom_form: RwSignal<bool>,
+) -> impl IntoView {
     view! {
         <div class="dropdown">
             <label for="provider-select" class="sr-only">
@@ -49,7 +61,11 @@ pub fn ProviderSelection(rpc_context: RpcContext) -> impl IntoView {
             <select
                 id="provider-select"
                 on:change=move |ev| {
-                    if let Ok(url) = event_target_value(&ev).parse() {
+                    let value = event_target_value(&ev);
+                    if value == ADD_CUSTOM_ENDPOINT {
+                        show_custom_form.set(true);
+                    } else if let Ok(url) = value.parse() {
+                        show_custom_form.set(false);
                         rpc_context.set_provider_url(url);
                     }
                 }
@@ -57,20 +73,28 @@ pub fn ProviderSelection(rpc_context: RpcContext) -> impl IntoView {
             >
                 {move || {
                     let network = rpc_context.network().get();
-                    let current_url = rpc_context.get().url.to_string();
+                    let current_url = rpc_context.get().url().to_string();
+                    let adding = show_custom_form.get();
+                    let custom = rpc_context
+                        .custom_endpoint()
+                        .map(|endpoint| (endpoint.url.to_string(), endpoint.label()));
                     providers_for(network)
                         .iter()
-                        .map(|endpoint| {
-                            let url = endpoint.url.to_string();
-                            let selected = url == current_url;
+                        .map(|endpoint| (endpoint.url.to_string(), endpoint.label.to_string()))
+                        .chain(custom)
+                        .map(|(url, label)| {
+                            let selected = !adding && url == current_url;
                             view! {
                                 <option value=url selected=selected>
-                                    {endpoint.label}
+                                    {label}
                                 </option>
                             }
                         })
                         .collect_view()
                 }}
+                <option value=ADD_CUSTOM_ENDPOINT selected=move || show_custom_form.get()>
+                    "Custom endpoint..."
+                </option>
             </select>
             <div class="dropdown-icon">
                 <svg class="h-4 w-4" fill="none" stroke="currentColor" viewBox="0 0 24 24">
@@ -81,6 +105,78 @@ pub fn ProviderSelection(rpc_context: RpcContext) -> impl IntoView {
     }
 }
 
+/// Form adding a custom RPC endpoint to the selected network. The endpoint is only added once it
+/// is found to serve that network.
+#[component]
+pub fn CustomEndpointForm(rpc_context: RpcContext, show: RwSignal<bool>) -> impl IntoView {
+    let url = RwSignal::new(String::new());
+    let token = RwSignal::new(String::new());
+    let error = RwSignal::new(None::<String>);
+    let validating = RwSignal::new(false);
+    let add = move |_| {
+        let network = rpc_context.network().get_untracked();
+        let endpoint = match CustomEndpoint::parse(&url.get_untracked(), &token.get_untracked()) {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                error.set(Some(e.to_string()));
+                return;
+            }
+        };
+        error.set(None);
+        validating.set(true);
+        spawn_local(async move {
+            match custom_endpoint::validate(&endpoint, network).await {
+                Ok(()) => {
+                    rpc_context.add_custom_endpoint(endpoint);
+                    url.set(String::new());
+                    token.set(String::new());
+                    show.set(false);
+                }
+                Err(e) => error.set(Some(e.to_string())),
+            }
+            validating.set(false);
+        });
+    };
+    let remove = move |_| {
+        rpc_context.remove_custom_endpoint();
+        show.set(false);
+    };
+    view! {
+        <Show when=move || show.get()>
+            <div class="custom-endpoint-form">
+                <input
+                    type="url"
+                    placeholder="Custom RPC endpoint, e.g., https://my-node.example.com/rpc/v1"
+                    prop:value=url
+                    on:input=move |ev| url.set(event_target_value(&ev))
+                    class="input"
+                />
+                <input
+                    type="password"
+                    placeholder="Bearer token (optional)"
+                    prop:value=token
+                    on:input=move |ev| token.set(event_target_value(&ev))
+                    class="input"
+                />
+                <div class="custom-endpoint-actions">
+                    <button class="btn" disabled=validating on:click=add>
+                        {move || if validating.get() { "Validating..." } else { "Add endpoint" }}
+                    </button>
+                    <button class="btn-secondary" on:click=move |_| show.set(false)>
+                        "Cancel"
+                    </button>
+                    <Show when=move || rpc_context.custom_endpoint().is_some()>
+                        <button class="btn-secondary" on:click=remove>
+                            "Remove custom endpoint"
+                        </button>
+                    </Show>
+                </div>
+                {move || error.get().map(|error| view! { <p class="custom-endpoint-error">{error}</p> })}
+            </div>
+        </Show>
+    }
+}
+
 #[component]
 pub fn RpcStatus(
     rpc_context: RpcContext,
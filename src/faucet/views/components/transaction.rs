@@ -1,3 +1,4 @@
+use crate::explorer::views::components::MessageLink;
 use crate::utils::format::{SearchPath, format_url};
 use crate::utils::transaction_id::TransactionId;
 use leptos::prelude::*;
@@ -18,7 +19,10 @@ pub fn TransactionList(
                 {messages
                     .into_iter()
                     .map(|(msg, sent)| {
-                        let (tx, status) = if sent {
+                        let (tx, status) = if let (true, TransactionId::Native(cid)) = (sent, &msg) {
+                            let tx = view! { <MessageLink cid=*cid /> }.into_any();
+                            (tx, "(confirmed)")
+                        } else if sent {
                             let tx = faucet_tx_base_url
                                 .get()
                                 .as_ref()
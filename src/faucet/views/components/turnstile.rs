@@ -0,0 +1,56 @@
+use leptos::prelude::*;
+use leptos::{IntoView, component, view};
+use leptos_meta::Script;
+
+/// Script rendering the Turnstile widgets of the page.
+const TURNSTILE_SCRIPT_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/api.js";
+
+#[cfg(feature = "hydrate")]
+mod bindings {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(catch, js_namespace = turnstile, js_name = getResponse)]
+        pub fn get_response() -> Result<Option<String>, JsValue>;
+
+        #[wasm_bindgen(catch, js_namespace = turnstile)]
+        pub fn reset() -> Result<(), JsValue>;
+    }
+}
+
+/// Displays a [Turnstile](https://developers.cloudflare.com/turnstile/) human verification
+/// widget for the given site key.
+#[component]
+pub fn Turnstile(site_key: &'static str) -> impl IntoView {
+    view! {
+        <Script src=TURNSTILE_SCRIPT_URL async_="true" defer="true" />
+        <div class="cf-turnstile" data-sitekey=site_key></div>
+    }
+}
+
+/// Returns the token of the Turnstile widget of the page, if the human verification succeeded.
+pub fn turnstile_token() -> Option<String> {
+    #[cfg(feature = "hydrate")]
+    {
+        bindings::get_response()
+            .ok()
+            .flatten()
+            .filter(|token| !token.is_empty())
+    }
+    #[cfg(not(feature = "hydrate"))]
+    {
+        None
+    }
+}
+
+/// Resets the Turnstile widget of the page. Tokens can only be verified once, so the widget has to
+/// issue a new one for every drip.
+pub fn reset_turnstile() {
+    #[cfg(feature = "hydrate")]
+    {
+        if let Err(e) = bindings::reset() {
+            log::warn!("Failed to reset the Turnstile widget: {e:?}");
+        }
+    }
+}
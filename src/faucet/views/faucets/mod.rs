@@ -1,27 +1,39 @@
-pub mod calibnet;
-pub mod calibnet_datacap;
-pub mod calibnet_usdfc;
-pub mod mainnet;
-
 use leptos::prelude::*;
 use leptos::{IntoView, component, leptos_dom::helpers::event_target_value, view};
 use leptos_meta::{Meta, Title};
+use leptos_router::hooks::use_params_map;
 
 use crate::faucet::constants::FaucetInfo;
 use crate::faucet::controller::FaucetController;
 use crate::faucet::views::components::alert::ErrorMessages;
 use crate::faucet::views::components::balance::{FaucetBalance, TargetBalance};
+use crate::faucet::views::components::faucet_description::FaucetDescription;
 use crate::faucet::views::components::nav::{GotoFaucetList, GotoHome};
-use crate::faucet::views::components::rpc_selector::ProviderSelection;
+use crate::faucet::views::components::rpc_selector::{CustomEndpointForm, ProviderSelection};
 use crate::faucet::views::components::transaction::{TransactionHistoryButton, TransactionList};
+use crate::faucet::views::components::turnstile::{Turnstile, reset_turnstile, turnstile_token};
 use crate::utils::rpc_context::RpcContext;
 
 #[component]
 fn FaucetInput(faucet: RwSignal<FaucetController>) -> impl IntoView {
     let rpc_context = RpcContext::use_context();
+    let show_custom_form = RwSignal::new(false);
+    let site_key = faucet.get_untracked().get_turnstile_site_key();
+    // Collects the human verification token, if required, and passes it along with the drip.
+    let claim = move || {
+        let faucet = faucet.get();
+        if site_key.is_some() {
+            faucet.set_human_token(turnstile_token());
+        }
+        faucet.drip();
+        if site_key.is_some() {
+            faucet.set_human_token(None);
+            reset_turnstile();
+        }
+    };
     view! {
         <div class="input-container">
-            <ProviderSelection rpc_context=rpc_context />
+            <ProviderSelection rpc_context=rpc_context show_custom_form=show_custom_form />
             <input
                 type="text"
                 placeholder="Enter target address (Filecoin or Ethereum style)"
@@ -31,11 +43,12 @@ fn FaucetInput(faucet: RwSignal<FaucetController>) -> impl IntoView {
                     if ev.key() == "Enter" && !faucet.get().is_send_disabled()
                         && faucet.get().get_send_rate_limit_remaining() <= 0
                     {
-                        faucet.get().drip();
+                        claim();
                     }
                 }
                 class="input"
             />
+            {site_key.map(|site_key| view! { <Turnstile site_key=site_key /> })}
             {move || {
                 if faucet.get().is_send_disabled() {
                     view! {
@@ -68,7 +81,7 @@ fn FaucetInput(faucet: RwSignal<FaucetController>) -> impl IntoView {
                             <button
                                 class=btn_class
                                 on:click=move |_| {
-                                    faucet.get().drip();
+                                    claim();
                                 }
                             >
                                 {format!("Claim {unit}")}
@@ -79,11 +92,15 @@ fn FaucetInput(faucet: RwSignal<FaucetController>) -> impl IntoView {
                 }
             }}
         </div>
+        <CustomEndpointForm rpc_context=rpc_context show=show_custom_form />
     }
 }
 
 #[cfg(feature = "hydrate")]
 fn use_faucet_polling(faucet: RwSignal<FaucetController>) {
+    use crate::utils::rpc_context::RpcContext;
+    use futures::StreamExt as _;
+    use leptos::task::spawn_local;
     use leptos_use::use_interval_fn;
     use leptos_use::utils::Pausable;
 
@@ -100,8 +117,10 @@ fn use_faucet_polling(faucet: RwSignal<FaucetController>) {
         1000,
     );
 
+    // Polls until the chain head subscription is up, and again whenever it is down.
     let Pausable {
         pause: pause_refetch_balances,
+        resume: resume_refetch_balances,
         ..
     } = use_interval_fn(
         move || {
@@ -110,6 +129,37 @@ fn use_faucet_polling(faucet: RwSignal<FaucetController>) {
         5000,
     );
 
+    let rpc_context = RpcContext::use_context();
+    let subscription = StoredValue::new(0_u64);
+    let pause_polling = pause_refetch_balances.clone();
+    Effect::new(move |_| {
+        let provider = rpc_context.provider().get();
+        let generation = subscription.get_value() + 1;
+        subscription.set_value(generation);
+        let is_current = move || subscription.try_get_value() == Some(generation);
+        let pause_polling = pause_polling.clone();
+        let resume_polling = resume_refetch_balances.clone();
+        spawn_local(async move {
+            match provider.subscribe_heads().await {
+                Ok(heads) => {
+                    let mut heads = std::pin::pin!(heads);
+                    if is_current() {
+                        log::info!("Subscribed to chain head of {}", provider.url());
+                        pause_polling();
+                    }
+                    while heads.next().await.is_some() && is_current() {
+                        faucet.try_update_untracked(|faucet| faucet.refetch_balances());
+                    }
+                    log::warn!("Chain head subscription of {} closed", provider.url());
+                }
+                Err(e) => log::info!("Polling instead of subscribing to chain head: {e}"),
+            }
+            if is_current() {
+                resume_polling();
+            }
+        });
+    });
+
     on_cleanup(move || {
         pause_rate_limiter();
         pause_refetch_balances();
@@ -117,8 +167,8 @@ fn use_faucet_polling(faucet: RwSignal<FaucetController>) {
 }
 
 #[component]
-pub fn Faucet(faucet_info: FaucetInfo) -> impl IntoView {
-    let faucet = RwSignal::new(FaucetController::new(faucet_info));
+pub fn Faucet(faucet: RwSignal<FaucetController>) -> impl IntoView {
+    let faucet_info = faucet.get_untracked().get_faucet_info();
 
     #[cfg(feature = "hydrate")]
     {
@@ -159,6 +209,43 @@ pub fn Faucet(faucet_info: FaucetInfo) -> impl IntoView {
     }
 }
 
+/// Displays the page of the faucet of the registry at `/faucet/{slug}`.
+/// Sets the RPC context to the network of the faucet and renders the faucet and its description.
+#[component]
+pub fn FaucetPage() -> impl IntoView {
+    let params = use_params_map();
+    let rpc_context = RpcContext::use_context();
+
+    move || {
+        let slug = params.read().get("slug").unwrap_or_default();
+        let Some(faucet_info) = FaucetInfo::by_slug(&slug) else {
+            return "Not found.".into_any();
+        };
+        rpc_context.set_network(faucet_info.network());
+        let faucet = RwSignal::new(FaucetController::new(faucet_info));
+
+        view! {
+            <Title text=faucet_info.page_title() />
+            <Meta name="description" content=faucet_info.description() />
+            <h1 class="header">{faucet_info.title()}</h1>
+            <div class="main-container">
+                <Faucet faucet=faucet />
+                <FaucetDescription faucet=faucet />
+                {faucet_info
+                    .about_html()
+                    .map(|about| {
+                        view! {
+                            <div class="description">
+                                <p inner_html=about></p>
+                            </div>
+                        }
+                    })}
+            </div>
+        }
+        .into_any()
+    }
+}
+
 #[component]
 pub fn Faucets() -> impl IntoView {
     view! {
@@ -167,18 +254,15 @@ pub fn Faucets() -> impl IntoView {
         <h1 class="header">Filecoin Faucet List</h1>
         <div class="main-container">
             <div class="faucet-list-items">
-                <a class="link-text" href="/faucet/calibnet_usdfc">
-                    "💰 Calibration Network USDFC Faucet"
-                </a>
-                <a class="link-text" href="/faucet/calibnet">
-                    "🧪 Calibration Network Faucet"
-                </a>
-                <a class="link-text" href="/faucet/calibnet_datacap">
-                    "⚡️ Calibration Network Datacap Faucet"
-                </a>
-                <a class="link-text" href="/faucet/mainnet">
-                    "🌐 Mainnet Network Faucet"
-                </a>
+                {FaucetInfo::all()
+                    .map(|faucet_info| {
+                        view! {
+                            <a class="link-text" href=format!("/faucet/{}", faucet_info.slug())>
+                                {faucet_info.list_label()}
+                            </a>
+                        }
+                    })
+                    .collect_view()}
             </div>
             <GotoHome />
         </div>
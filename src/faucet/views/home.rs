@@ -1,3 +1,5 @@
+use crate::explorer::views::search::SearchBar;
+use crate::explorer::views::tipset::LatestTipset;
 use crate::faucet::views::components::icons::{CheckIcon, LightningIcon};
 use crate::faucet::views::components::layout::Header;
 use crate::faucet::views::components::nav::GotoFaucetList;
@@ -90,6 +92,8 @@ pub fn Explorer() -> impl IntoView {
             <Header />
             <FaucetOverview />
             <RpcSelectors rpc_context=rpc_context network_name=network_name network_version=network_version />
+            <SearchBar />
+            <LatestTipset />
             <GotoFaucetList />
         </main>
     }
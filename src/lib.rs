@@ -1,6 +1,7 @@
 #![recursion_limit = "1024"]
 
 mod app;
+mod explorer;
 mod utils;
 #[cfg(feature = "hydrate")]
 use app::App;
@@ -18,7 +19,7 @@ pub fn hydrate() {
 mod ssr_imports {
     use std::sync::Arc;
 
-    use crate::{app::App, faucet};
+    use crate::{app::App, faucet, utils};
     use axum::{Extension, Router, http::Method, routing::post};
     use leptos::prelude::*;
     use leptos_axum::{LeptosRoutes, generate_route_list};
@@ -73,11 +74,15 @@ mod ssr_imports {
 
     #[event(start)]
     fn register() {
-        server_fn::axum::register_explicit::<faucet::server_api::SignedFilTransfer>();
-        server_fn::axum::register_explicit::<faucet::server_api::SignedErc20Transfer>();
+        server_fn::axum::register_explicit::<faucet::server_api::PushFilTransfer>();
+        server_fn::axum::register_explicit::<faucet::server_api::PushErc20Transfer>();
+        server_fn::axum::register_explicit::<faucet::server_api::PushDatacapAllocation>();
         server_fn::axum::register_explicit::<faucet::server_api::FaucetAddress>();
         server_fn::axum::register_explicit::<faucet::server_api::ClaimToken>();
         server_fn::axum::register_explicit::<faucet::server_api::ClaimTokenAll>();
+        server_fn::axum::register_explicit::<faucet::server_api::ClaimChallenge>();
+        server_fn::axum::register_explicit::<faucet::server_api::ClaimStatus>();
+        server_fn::axum::register_explicit::<utils::rpc_cache::RpcCacheStats>();
     }
 
     #[event(fetch)]
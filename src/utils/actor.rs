@@ -0,0 +1,159 @@
+//! Actors of the explorer address page: their type and the equivalent forms of their address.
+
+use cid::Cid;
+use derive_more::Display;
+use fvm_shared::address::{Address, Protocol};
+
+use crate::utils::address::AddressAlloyExt as _;
+use crate::utils::lotus_json::{ActorCodeCids, ActorState};
+
+/// Type of a builtin actor, named after its entry in the builtin actors bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum ActorType {
+    #[display("Account")]
+    Account,
+    #[display("Ethereum account")]
+    EthAccount,
+    #[display("Placeholder")]
+    Placeholder,
+    #[display("EVM")]
+    Evm,
+    #[display("Multisig")]
+    Multisig,
+    #[display("Miner")]
+    Miner,
+    /// Any other builtin actor, e.g., the system or singleton actors.
+    #[display("{_0}")]
+    Other(String),
+}
+
+impl ActorType {
+    /// Returns the type of the builtin actor with the given name in the bundle manifest.
+    pub fn from_manifest_name(name: &str) -> Self {
+        match name {
+            "account" => Self::Account,
+            "ethaccount" => Self::EthAccount,
+            "placeholder" => Self::Placeholder,
+            "evm" => Self::Evm,
+            "multisig" => Self::Multisig,
+            "storageminer" => Self::Miner,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    /// Resolves the code CID of an actor to its type, `None` if it is not a builtin actor of the
+    /// current actors bundle.
+    pub fn resolve(code_cids: &ActorCodeCids, code: &Cid) -> Option<Self> {
+        code_cids
+            .0
+            .iter()
+            .find(|(_, cid)| cid.0 == *code)
+            .map(|(name, _)| Self::from_manifest_name(name))
+    }
+}
+
+/// An address along with what the chain knows of its actor, see
+/// [`crate::utils::rpc_context::Provider::actor_info`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActorInfo {
+    /// The ID address of the actor, `None` if the actor does not exist yet.
+    pub id: Option<Address>,
+    /// The public key or delegated address of the actor, `None` if it has none, e.g., miners.
+    pub robust: Option<Address>,
+    /// The state of the actor, `None` if the actor does not exist yet.
+    pub state: Option<ActorState>,
+    pub actor_type: Option<ActorType>,
+}
+
+impl ActorInfo {
+    /// Returns the Ethereum address of an actor with a delegated address.
+    pub fn eth_address(&self) -> Option<alloy::primitives::Address> {
+        self.robust
+            .filter(|robust| robust.protocol() == Protocol::Delegated)
+            .and_then(|robust| robust.into_eth_address().ok())
+    }
+
+    /// Returns the `0xff00…` Ethereum address masking the ID of the actor.
+    pub fn masked_id_address(&self) -> Option<alloy::primitives::Address> {
+        self.id.and_then(|id| id.into_eth_address().ok())
+    }
+
+    /// Returns the address the actor is known by in the FEVM, i.e., its delegated address if it
+    /// has one, and its ID address otherwise.
+    pub fn fevm_address(&self) -> Option<Address> {
+        self.robust
+            .filter(|robust| robust.protocol() == Protocol::Delegated)
+            .or(self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::lotus_json::LotusJson;
+
+    #[test]
+    fn test_resolve_actor_type() {
+        let account: Cid = "bafk2bzaceae2lbkfzzw4bqphxb2qc6hxbztmilmfhbwgxkepcuei5ujavp6ye"
+            .parse()
+            .unwrap();
+        let miner: Cid = "bafk2bzaceb2ypgfywylssxdw2hyhsybhmcf6bflsc6kq4wf7xamolrcvu6ctu"
+            .parse()
+            .unwrap();
+        let cron: Cid = "bafk2bzacecaz3nbnhftiwyslvbqxghpoyrlahy3uoplwgrrzmr4y6qgwgd6fo"
+            .parse()
+            .unwrap();
+        let code_cids = ActorCodeCids(
+            [
+                ("account".to_string(), LotusJson(account)),
+                ("storageminer".to_string(), LotusJson(miner)),
+                ("cron".to_string(), LotusJson(cron)),
+            ]
+            .into(),
+        );
+        let unknown = Cid::default();
+        let cases = [
+            (account, Some(ActorType::Account)),
+            (miner, Some(ActorType::Miner)),
+            (cron, Some(ActorType::Other("cron".to_string()))),
+            (unknown, None),
+        ];
+        for (code, expected) in cases {
+            assert_eq!(ActorType::resolve(&code_cids, &code), expected, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_address_forms() {
+        let eth = alloy::primitives::Address::repeat_byte(0xab);
+        let delegated = Address::new_delegated(10, eth.as_slice()).unwrap();
+        let contract = ActorInfo {
+            id: Some(Address::new_id(1234)),
+            robust: Some(delegated),
+            state: None,
+            actor_type: Some(ActorType::Evm),
+        };
+        assert_eq!(contract.eth_address(), Some(eth));
+        assert_eq!(
+            format!("{:#x}", contract.masked_id_address().unwrap()),
+            "0xff000000000000000000000000000000000004d2"
+        );
+        assert_eq!(contract.fevm_address(), Some(delegated));
+
+        let account = ActorInfo {
+            robust: Some(Address::new_secp256k1(&[4; 65]).unwrap()),
+            ..contract.clone()
+        };
+        assert_eq!(account.eth_address(), None);
+        assert_eq!(account.fevm_address(), Some(Address::new_id(1234)));
+
+        let unknown = ActorInfo {
+            id: None,
+            robust: Some(Address::new_secp256k1(&[4; 65]).unwrap()),
+            state: None,
+            actor_type: None,
+        };
+        assert_eq!(unknown.masked_id_address(), None);
+        assert_eq!(unknown.fevm_address(), None);
+    }
+}
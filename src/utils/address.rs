@@ -5,7 +5,10 @@ use fvm_shared::address::{Address, DelegatedAddress, Network, Protocol};
 use leptos::logging::error;
 use serde::{Deserialize, Serialize};
 
+use super::error::RpcError;
 use super::lotus_json::LotusJson;
+use super::network::NetworkConfig;
+use super::rpc_context::Provider;
 
 /// Represents an address that can be either a native Filecoin or Ethereum address and can be sent
 /// to/from the backend for further processing.
@@ -23,10 +26,10 @@ impl AnyAddress {
     /// Converts the underlying address to an [`Address`] type.
     ///
     /// Note: the conversion might fail if the network is not set correctly.
-    pub fn to_filecoin_address(&self, network: Network) -> anyhow::Result<Address> {
+    pub fn to_filecoin_address(&self, network: &NetworkConfig) -> anyhow::Result<Address> {
         match self {
-            AnyAddress::Filecoin(addr) => parse_address(&addr.0.to_string(), network),
-            AnyAddress::Ethereum(addr) => parse_address(&addr.to_string(), network),
+            AnyAddress::Filecoin(addr) => parse_address(&addr.0.to_string(), network.prefix),
+            AnyAddress::Ethereum(addr) => parse_address(&addr.to_string(), network.prefix),
         }
     }
 }
@@ -75,6 +78,43 @@ pub fn parse_address(raw: &str, n: Network) -> anyhow::Result<Address> {
     }
 }
 
+/// Returns the canonical form of a Filecoin address, so that all the ways of addressing the same
+/// actor map to a single identity, e.g., when rate limiting claimants.
+///
+/// Ethereum addresses are expected to be parsed with [`parse_address`] beforehand, i.e., into an
+/// `f4` address or, for masked IDs, an ID address. Robust addresses are already canonical. ID
+/// addresses are resolved to the robust address of their actor. The robust address is preferred
+/// because an ID address only exists once the actor is created on chain, which for a new wallet
+/// happens with its first drip. If the node reports that the ID address has no robust address,
+/// e.g., because the actor is a miner, the ID address is the only form of the actor and is returned
+/// as is. Fails if the node cannot be asked, rather than letting the claimant through under an
+/// identity that may not be its canonical one.
+pub async fn canonical_address(provider: &Provider, address: Address) -> anyhow::Result<Address> {
+    if address.protocol() != Protocol::ID {
+        return Ok(address);
+    }
+    match provider.account_key(address).await {
+        Ok(robust) => Ok(robust),
+        Err(e) if e.downcast_ref::<RpcError>().is_some() => {
+            log::warn!("{address} has no robust address, using it as is: {e}");
+            Ok(address)
+        }
+        Err(e) => Err(e.context(format!("Failed to resolve the robust address of {address}"))),
+    }
+}
+
+/// Formats the address with the prefix of the given network, regardless of the network set
+/// globally with [`fvm_shared::address::set_current_network`].
+pub fn format_address(address: &Address, network: Network) -> String {
+    let prefix = match network {
+        Network::Mainnet => 'f',
+        Network::Testnet => 't',
+    };
+    // The global network only determines the first character of the string representation.
+    let addr = address.to_string();
+    format!("{prefix}{}", &addr[1..])
+}
+
 /// Extensions around [`fvm_shared::address::Address`] to convert it into an
 /// Ethereum address, usable by the `alloy` crate.
 pub trait AddressAlloyExt {
@@ -130,6 +170,8 @@ impl AddressAlloyExt for Address {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::provider_pool::ProviderPool;
+    use crate::utils::rpc_context::tests::{MockEndpoint, provider, serve, unreachable};
     // Whenever we change the network in tests, we need to fork the test to avoid
     // changing the network for other tests. This is because the network is a global
     // variable. This is not a problem when run with `cargo nextest` because each test
@@ -290,6 +332,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_address() {
+        let id = Address::new_id(163506);
+        assert_eq!(format_address(&id, Network::Mainnet), "f0163506");
+        assert_eq!(format_address(&id, Network::Testnet), "t0163506");
+
+        let addr_str = "t410f2oekwcmo2pueydmaq53eic2i62crtbeyuzx2gmy";
+        let addr = parse_address(addr_str, Network::Testnet).unwrap();
+        assert_eq!(format_address(&addr, Network::Testnet), addr_str);
+        assert_eq!(
+            format_address(&addr, Network::Mainnet),
+            "f410f2oekwcmo2pueydmaq53eic2i62crtbeyuzx2gmy"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_canonical_address_of_robust_addresses() {
+        // Robust addresses are canonical as is, so the provider is never queried.
+        let provider = Provider::from_pool(ProviderPool::new(vec![
+            "http://localhost:1/rpc/v1".parse().unwrap(),
+        ]));
+        let robust = [
+            "t1rgci272nfk4k6cpyejepzv4xstpejjckldlzidy",
+            "t410fv2oexfiizeuzm3xtoie3gnxfpfwwglg4q3dgxki",
+            "0xAe9C4b9508c929966ef37209b336E5796D632CDc",
+        ]
+        .map(|addr| parse_address(addr, Network::Testnet).unwrap());
+        for addr in robust {
+            assert_eq!(canonical_address(&provider, addr).await.unwrap(), addr);
+        }
+        // The Ethereum address and its `t410` counterpart are the same claimant.
+        assert_eq!(robust[1], robust[2]);
+    }
+
+    #[tokio::test]
+    async fn test_canonical_address_of_id_addresses() {
+        let id = Address::new_id(163506);
+        let robust = "t1rgci272nfk4k6cpyejepzv4xstpejjckldlzidy";
+        let (url, _) = serve(MockEndpoint::Result(serde_json::json!(robust))).await;
+        assert_eq!(
+            canonical_address(&provider(vec![url]), id).await.unwrap(),
+            parse_address(robust, Network::Testnet).unwrap()
+        );
+
+        // Actors without a robust address are known by their ID address only.
+        let (url, _) = serve(MockEndpoint::Error).await;
+        assert_eq!(
+            canonical_address(&provider(vec![url]), id).await.unwrap(),
+            id
+        );
+
+        let url = unreachable().await;
+        assert!(canonical_address(&provider(vec![url]), id).await.is_err());
+    }
+
     #[test]
     fn test_invalid_address_conversion_to_eth() {
         let faulty_addresses = [
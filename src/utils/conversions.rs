@@ -3,32 +3,48 @@
 use fvm_shared::{bigint::BigInt, econ::TokenAmount};
 
 pub trait TokenAmountAlloyExt {
-    /// Converts an [`alloy::primitives::U256`] amount to a [`TokenAmount`].
-    ///
-    /// Warning! The assumption here is that the decimals are the same for both Filecoin
-    /// and given ERC20 token. This holds true for USDFC, but may not hold for other
-    /// tokens.
-    fn from_alloy_amount(amount: &alloy::primitives::U256) -> Self;
-
-    /// Converts a [`TokenAmount`] to an [`alloy::primitives::U256`] amount.
-    ///
-    /// Warning! The assumption here is that the decimals are the same for both Filecoin
-    /// and given ERC20 token. This holds true for USDFC, but may not hold for other
-    /// tokens.
-    #[allow(dead_code)]
-    fn to_alloy_amount(&self) -> alloy::primitives::U256;
+    /// Converts an [`alloy::primitives::U256`] amount in the base units of a token with the given
+    /// `decimals` to a [`TokenAmount`].
+    fn from_alloy_amount(amount: &alloy::primitives::U256, decimals: u8) -> Self;
+
+    /// Converts a [`TokenAmount`] to an [`alloy::primitives::U256`] amount in the base units of a
+    /// token with the given `decimals`. Digits beyond the precision of the token are truncated.
+    fn to_alloy_amount(&self, decimals: u8) -> alloy::primitives::U256;
+}
+
+/// Returns the factor between the base units of [`TokenAmount`] and of a token with the given
+/// `decimals`, and whether the token has more decimals.
+fn scale(decimals: u8) -> (BigInt, bool) {
+    let decimals = usize::from(decimals);
+    let exponent = decimals.abs_diff(TokenAmount::DECIMALS);
+    (
+        BigInt::from(10).pow(exponent as u32),
+        decimals > TokenAmount::DECIMALS,
+    )
 }
 
 impl TokenAmountAlloyExt for fvm_shared::econ::TokenAmount {
-    fn from_alloy_amount(amount: &alloy::primitives::U256) -> Self {
-        TokenAmount::from_atto(BigInt::from_bytes_be(
+    fn from_alloy_amount(amount: &alloy::primitives::U256, decimals: u8) -> Self {
+        let amount = BigInt::from_bytes_be(
             fvm_shared::bigint::Sign::Plus,
             &amount.to_be_bytes_trimmed_vec(),
-        ))
+        );
+        let (factor, more_decimals) = scale(decimals);
+        TokenAmount::from_atto(if more_decimals {
+            amount / factor
+        } else {
+            amount * factor
+        })
     }
-    fn to_alloy_amount(&self) -> alloy::primitives::U256 {
-        let atto = self.atto();
-        alloy::primitives::U256::from_be_slice(&atto.to_signed_bytes_be())
+
+    fn to_alloy_amount(&self, decimals: u8) -> alloy::primitives::U256 {
+        let (factor, more_decimals) = scale(decimals);
+        let amount = if more_decimals {
+            self.atto() * factor
+        } else {
+            self.atto() / factor
+        };
+        alloy::primitives::U256::from_be_slice(&amount.to_signed_bytes_be())
     }
 }
 
@@ -40,22 +56,43 @@ mod tests {
     #[test]
     fn test_from_alloy_amount() {
         let amount = U256::from(1000000000000000000u64); // 1e18, so 1 FIL
-        let token_amount = TokenAmount::from_alloy_amount(&amount);
+        let token_amount = TokenAmount::from_alloy_amount(&amount, 18);
         assert_eq!(token_amount.atto(), &BigInt::from(1000000000000000000u64));
+
+        let token_amount = TokenAmount::from_alloy_amount(&U256::from(1_500_000), 6);
+        assert_eq!(token_amount, TokenAmount::from_nano(1_500_000_000));
+
+        let token_amount = TokenAmount::from_alloy_amount(&U256::from(10).pow(U256::from(24)), 24);
+        assert_eq!(token_amount, TokenAmount::from_whole(1));
     }
 
     #[test]
     fn test_to_alloy_amount() {
         let token_amount = TokenAmount::from_whole(1);
-        let alloy_amount = token_amount.to_alloy_amount();
-        assert_eq!(alloy_amount, U256::from(1000000000000000000u64));
+        assert_eq!(
+            token_amount.to_alloy_amount(18),
+            U256::from(1000000000000000000u64)
+        );
+        assert_eq!(token_amount.to_alloy_amount(6), U256::from(1_000_000));
+        assert_eq!(token_amount.to_alloy_amount(0), U256::from(1));
+        assert_eq!(
+            token_amount.to_alloy_amount(24),
+            U256::from(10).pow(U256::from(24))
+        );
+        // Digits beyond the precision of the token are dropped.
+        assert_eq!(
+            TokenAmount::from_nano(1_234_567).to_alloy_amount(6),
+            U256::from(1_234)
+        );
     }
 
     #[test]
     fn test_alloy_conversion_round_trip() {
         let original_amount = TokenAmount::from_whole(42);
-        let alloy_amount = original_amount.to_alloy_amount();
-        let converted_back = TokenAmount::from_alloy_amount(&alloy_amount);
-        assert_eq!(original_amount, converted_back);
+        for decimals in [0, 6, 18, 24] {
+            let alloy_amount = original_amount.to_alloy_amount(decimals);
+            let converted_back = TokenAmount::from_alloy_amount(&alloy_amount, decimals);
+            assert_eq!(original_amount, converted_back);
+        }
     }
 }
@@ -0,0 +1,204 @@
+//! RPC endpoints added by the user, e.g., their own Forest or Lotus node, on top of the public
+//! endpoints of [`super::rpc_context::providers_for`].
+//!
+//! An endpoint is only added once it answers `Filecoin.StateNetworkName` with the name of the
+//! network selected in the explorer, and `Filecoin.EthChainId` with its chain ID. The name is not
+//! known ahead of time for every configured network, e.g., devnets, whereas the chain ID is. Custom
+//! endpoints are kept in the local storage of the browser, one per network.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+use url::Url;
+
+use super::network::NetworkConfig;
+use super::provider_pool::ProviderPool;
+use super::rpc_context::Provider;
+
+/// Local storage key of the custom endpoints.
+pub const CUSTOM_ENDPOINTS_KEY: &str = "custom_rpc_endpoints";
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum EndpointError {
+    #[error("Invalid endpoint URL: {0}")]
+    InvalidUrl(String),
+    #[error("Unsupported endpoint scheme `{0}`, expected `http` or `https`")]
+    UnsupportedScheme(String),
+    #[error("Endpoint serves chain ID {actual}, expected {expected}")]
+    NetworkMismatch { expected: u64, actual: u64 },
+    #[error("Endpoint serves network `{actual}`, expected `{expected}`")]
+    NetworkNameMismatch { expected: String, actual: String },
+    #[error("Endpoint is unreachable: {0}")]
+    Unreachable(String),
+}
+
+/// An RPC endpoint added by the user.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomEndpoint {
+    pub url: Url,
+    /// Bearer token sent along with the requests to the endpoint, if it requires one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl CustomEndpoint {
+    /// Parses the endpoint entered by the user. An empty token means the endpoint is public.
+    pub fn parse(url: &str, token: &str) -> Result<Self, EndpointError> {
+        let url = url
+            .trim()
+            .parse::<Url>()
+            .map_err(|e| EndpointError::InvalidUrl(e.to_string()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(EndpointError::UnsupportedScheme(url.scheme().to_string()));
+        }
+        let token = Some(token.trim())
+            .filter(|token| !token.is_empty())
+            .map(str::to_string);
+        Ok(Self { url, token })
+    }
+
+    /// Label of the endpoint in the provider selection.
+    pub fn label(&self) -> String {
+        format!("Custom ({})", self.url.host_str().unwrap_or_default())
+    }
+}
+
+/// Checks that the chain ID reported by an endpoint is the one of the selected network.
+pub fn check_chain_id(chain_id: u64, network: &NetworkConfig) -> Result<(), EndpointError> {
+    if chain_id == network.chain_id {
+        Ok(())
+    } else {
+        Err(EndpointError::NetworkMismatch {
+            expected: network.chain_id,
+            actual: chain_id,
+        })
+    }
+}
+
+/// Checks that the network name reported by an endpoint is the one of the selected network, if the
+/// latter is known.
+pub fn check_network_name(name: &str, network: &NetworkConfig) -> Result<(), EndpointError> {
+    match &network.network_name {
+        Some(expected) if expected != name => Err(EndpointError::NetworkNameMismatch {
+            expected: expected.clone(),
+            actual: name.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Validates an endpoint by asking it for its network name and chain ID, without failing over to
+/// any other endpoint.
+pub async fn validate(
+    endpoint: &CustomEndpoint,
+    network: &NetworkConfig,
+) -> Result<(), EndpointError> {
+    let provider = Provider::from_pool(ProviderPool::new(vec![endpoint.url.clone()]))
+        .with_custom_endpoint(endpoint.clone());
+    let name = provider
+        .network_name()
+        .await
+        .map_err(|e| EndpointError::Unreachable(e.to_string()))?;
+    check_network_name(&name, network)?;
+    let chain_id = provider
+        .chain_id()
+        .await
+        .map_err(|e| EndpointError::Unreachable(e.to_string()))?;
+    check_chain_id(chain_id, network)
+}
+
+/// Custom endpoints, as kept in local storage, by network name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CustomEndpoints(BTreeMap<String, CustomEndpoint>);
+
+impl CustomEndpoints {
+    pub fn get(&self, network: &NetworkConfig) -> Option<&CustomEndpoint> {
+        self.0.get(&network.name)
+    }
+
+    pub fn set(&mut self, network: &NetworkConfig, endpoint: Option<CustomEndpoint>) {
+        match endpoint {
+            Some(endpoint) => self.0.insert(network.name.clone(), endpoint),
+            None => self.0.remove(&network.name),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoint() {
+        let endpoint = CustomEndpoint::parse(" http://127.0.0.1:2345/rpc/v1 ", "  ").unwrap();
+        assert_eq!(endpoint.url.as_str(), "http://127.0.0.1:2345/rpc/v1");
+        assert_eq!(endpoint.token, None);
+        assert_eq!(endpoint.label(), "Custom (127.0.0.1)");
+
+        let endpoint = CustomEndpoint::parse("https://node.example.com/rpc/v1", "secret").unwrap();
+        assert_eq!(endpoint.token.as_deref(), Some("secret"));
+
+        assert!(matches!(
+            CustomEndpoint::parse("node.example.com/rpc/v1", ""),
+            Err(EndpointError::InvalidUrl(_))
+        ));
+        assert_eq!(
+            CustomEndpoint::parse("wss://node.example.com/rpc/v1", ""),
+            Err(EndpointError::UnsupportedScheme("wss".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_check_chain_id() {
+        assert!(check_chain_id(314, NetworkConfig::mainnet()).is_ok());
+        assert!(check_chain_id(314159, NetworkConfig::calibnet()).is_ok());
+        assert_eq!(
+            check_chain_id(314159, NetworkConfig::mainnet()),
+            Err(EndpointError::NetworkMismatch {
+                expected: 314,
+                actual: 314159,
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_network_name() {
+        assert!(check_network_name("mainnet", NetworkConfig::mainnet()).is_ok());
+        assert!(check_network_name("calibrationnet", NetworkConfig::calibnet()).is_ok());
+        assert_eq!(
+            check_network_name("calibrationnet", NetworkConfig::mainnet()),
+            Err(EndpointError::NetworkNameMismatch {
+                expected: "mainnet".to_string(),
+                actual: "calibrationnet".to_string(),
+            })
+        );
+        let devnet = NetworkConfig {
+            network_name: None,
+            ..NetworkConfig::calibnet().clone()
+        };
+        assert!(check_network_name("localnet-1a2b", &devnet).is_ok());
+    }
+
+    #[test]
+    fn test_custom_endpoints_per_network() {
+        let endpoint = CustomEndpoint::parse("http://127.0.0.1:2345/rpc/v1", "secret").unwrap();
+        let mut endpoints = CustomEndpoints::default();
+        endpoints.set(NetworkConfig::calibnet(), Some(endpoint.clone()));
+        assert_eq!(endpoints.get(NetworkConfig::calibnet()), Some(&endpoint));
+        assert_eq!(endpoints.get(NetworkConfig::mainnet()), None);
+
+        let stored = serde_json::to_string(&endpoints).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CustomEndpoints>(&stored).unwrap(),
+            endpoints
+        );
+        assert_eq!(
+            serde_json::from_str::<CustomEndpoints>("{}").unwrap(),
+            CustomEndpoints::default()
+        );
+
+        endpoints.set(NetworkConfig::calibnet(), None);
+        assert_eq!(endpoints, CustomEndpoints::default());
+    }
+}
@@ -0,0 +1,105 @@
+//! Pre-flight checks of datacap allocations made by the faucet verifier.
+
+use crate::utils::error::DatacapRejection;
+use fvm_shared::bigint::Zero;
+use fvm_shared::sector::StoragePower;
+
+/// Datacap status of the faucet verifier and of the recipient of an allocation, see
+/// [`crate::utils::rpc_context::Provider::datacap_status`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DatacapStatus {
+    /// Remaining allowance of the verifier, `None` if the address is not a verifier.
+    pub verifier_allowance: Option<StoragePower>,
+    /// Whether the recipient resolves to the same actor as the verifier.
+    pub recipient_is_verifier_itself: bool,
+    /// Allowance of the recipient as a verifier, `None` if it is not one.
+    pub recipient_verifier_allowance: Option<StoragePower>,
+    /// Datacap held by the recipient, `None` if it is not a verified client.
+    pub recipient_datacap: Option<StoragePower>,
+}
+
+impl DatacapStatus {
+    /// Checks that the verifier can allocate `allowance` to the recipient with
+    /// `AddVerifiedClient`.
+    pub fn check_allocation(&self, allowance: &StoragePower) -> Result<(), DatacapRejection> {
+        let verifier_allowance = self
+            .verifier_allowance
+            .as_ref()
+            .ok_or(DatacapRejection::NotVerifier)?;
+        if verifier_allowance < allowance {
+            return Err(DatacapRejection::InsufficientAllowance);
+        }
+        if self.recipient_is_verifier_itself {
+            return Err(DatacapRejection::RecipientIsFaucet);
+        }
+        if self.recipient_verifier_allowance.is_some() {
+            return Err(DatacapRejection::RecipientIsVerifier);
+        }
+        if self
+            .recipient_datacap
+            .as_ref()
+            .is_some_and(|datacap| !datacap.is_zero())
+        {
+            return Err(DatacapRejection::RecipientHasDatacap);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_allocation() {
+        let allowance = StoragePower::from(1024);
+        let eligible = DatacapStatus {
+            verifier_allowance: Some(StoragePower::from(4096)),
+            ..Default::default()
+        };
+        let cases = [
+            (eligible.clone(), Ok(())),
+            (
+                DatacapStatus {
+                    verifier_allowance: Some(allowance.clone()),
+                    recipient_datacap: Some(StoragePower::zero()),
+                    ..Default::default()
+                },
+                Ok(()),
+            ),
+            (DatacapStatus::default(), Err(DatacapRejection::NotVerifier)),
+            (
+                DatacapStatus {
+                    verifier_allowance: Some(StoragePower::from(1023)),
+                    ..Default::default()
+                },
+                Err(DatacapRejection::InsufficientAllowance),
+            ),
+            (
+                DatacapStatus {
+                    recipient_is_verifier_itself: true,
+                    recipient_verifier_allowance: Some(StoragePower::from(4096)),
+                    ..eligible.clone()
+                },
+                Err(DatacapRejection::RecipientIsFaucet),
+            ),
+            (
+                DatacapStatus {
+                    recipient_verifier_allowance: Some(StoragePower::zero()),
+                    ..eligible.clone()
+                },
+                Err(DatacapRejection::RecipientIsVerifier),
+            ),
+            (
+                DatacapStatus {
+                    recipient_datacap: Some(StoragePower::from(1)),
+                    ..eligible.clone()
+                },
+                Err(DatacapRejection::RecipientHasDatacap),
+            ),
+        ];
+        for (status, expected) in cases {
+            assert_eq!(status.check_allocation(&allowance), expected, "{status:?}");
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use fvm_shared::{bigint::Zero, econ::TokenAmount, sector::StoragePower};
 use serde::{Deserialize, Serialize};
-use std::ops::{Add, AddAssign, Mul};
+use std::ops::{Add, AddAssign, Mul, Sub};
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum DripAmount {
@@ -8,7 +8,8 @@ pub enum DripAmount {
     Storage(#[serde(with = "crate::utils::lotus_json")] StoragePower),
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TokenType {
     /// Filecoin native token
     Native,
@@ -20,6 +21,10 @@ pub enum TokenType {
 
 pub type ContractAddress = alloy::primitives::Address;
 
+/// Number of decimals of the native Filecoin token. Amounts of ERC-20 tokens are kept with the
+/// same precision, whatever the decimals of the token.
+pub const NATIVE_DECIMALS: u8 = TokenAmount::DECIMALS as u8;
+
 impl DripAmount {
     pub fn zero(token_type: TokenType) -> DripAmount {
         match token_type {
@@ -47,6 +52,18 @@ impl AddAssign<&DripAmount> for DripAmount {
     }
 }
 
+impl Sub<&DripAmount> for &DripAmount {
+    type Output = DripAmount;
+
+    fn sub(self, rhs: &DripAmount) -> DripAmount {
+        match (&self, rhs) {
+            (DripAmount::Token(x), DripAmount::Token(y)) => DripAmount::Token(x - y),
+            (DripAmount::Storage(x), DripAmount::Storage(y)) => DripAmount::Storage(x - y),
+            _ => unreachable!("DripAmount variant mismatch"),
+        }
+    }
+}
+
 impl Mul<i64> for DripAmount {
     type Output = DripAmount;
 
@@ -0,0 +1,55 @@
+//! ERC-20 tokens, whose metadata is read from their contract. Amounts of any token are kept as
+//! [`fvm_shared::econ::TokenAmount`], i.e., with 18 decimals, and converted to the base units of
+//! the token with [`super::conversions::TokenAmountAlloyExt`].
+
+use alloy::network::TransactionBuilder as _;
+use alloy::primitives::{Address, U256};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol_types::SolCall as _;
+use fvm_shared::econ::TokenAmount;
+use serde::{Deserialize, Serialize};
+
+alloy::sol! {
+    #[sol(rpc)]
+    contract ERC20 {
+        function name() public view returns (string);
+        function symbol() public view returns (string);
+        function decimals() public view returns (uint8);
+        function balanceOf(address owner) public view returns (uint256);
+        function transfer(address to, uint256 amount) public returns (bool);
+    }
+}
+
+/// Metadata of an ERC-20 token, as returned by its contract.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Erc20Metadata {
+    /// Name of the token, e.g., `USD for Filecoin Community`.
+    pub name: String,
+    /// Symbol of the token, e.g., `USDFC`.
+    pub symbol: String,
+    /// Number of decimals of the base unit of the token.
+    pub decimals: u8,
+}
+
+/// Gas limit and EIP-1559 fees of an Ethereum transaction. Fees are in attoFIL per unit of gas.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Eip1559Fees {
+    pub gas_limit: u64,
+    #[serde(with = "crate::utils::lotus_json")]
+    pub max_fee_per_gas: TokenAmount,
+    #[serde(with = "crate::utils::lotus_json")]
+    pub max_priority_fee_per_gas: TokenAmount,
+}
+
+/// Returns the transaction calling `transfer` on the token contract, without gas nor nonce. The
+/// `amount` is in the base units of the token.
+pub fn transfer_request(
+    contract_address: Address,
+    recipient: Address,
+    amount: U256,
+) -> TransactionRequest {
+    let calldata = ERC20::transferCall::new((recipient, amount)).abi_encode();
+    TransactionRequest::default()
+        .with_to(contract_address)
+        .with_input(calldata)
+}
@@ -6,18 +6,155 @@ use std::future::Future;
 use thiserror::Error;
 use uuid::Uuid;
 
+/// The limit that caused a faucet request to be rate limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, derive_more::Display)]
+pub enum RateLimitReason {
+    /// The faucet dripped its global cap for the current period.
+    #[display("faucet drip cap reached")]
+    DripCap,
+    /// The claimant received its maximum amount for the current period.
+    #[display("wallet cap reached")]
+    WalletCap,
+    /// The client IP (or IP prefix) claimed its maximum amount for the current period.
+    #[display("IP cap reached")]
+    IpCap,
+    /// The claimant recently received a drip and has to wait for the cool-down to pass.
+    #[display("wallet cool-down")]
+    Cooldown,
+    /// The faucet sent the maximum number of drips per minute across all claimants.
+    #[display("faucet is busy")]
+    Throughput,
+    /// The API key the claim was made with dripped its cap for the current period.
+    #[display("API key cap reached")]
+    KeyCap,
+}
+
+/// The reason a datacap allocation was rejected before its message was signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, derive_more::Display)]
+pub enum DatacapRejection {
+    /// The faucet address is not a verifier.
+    #[display("the faucet is not a verifier")]
+    NotVerifier,
+    /// The remaining allowance of the faucet verifier is lower than the allocation.
+    #[display("the faucet verifier allowance is exhausted")]
+    InsufficientAllowance,
+    /// The recipient is the faucet verifier itself.
+    #[display("the recipient is the faucet verifier")]
+    RecipientIsFaucet,
+    /// The recipient is a verifier, which cannot be a verified client at the same time.
+    #[display("the recipient is a verifier")]
+    RecipientIsVerifier,
+    /// The recipient already holds datacap.
+    #[display("the recipient already holds datacap")]
+    RecipientHasDatacap,
+}
+
 /// This enum represents all possible errors that can occur in the faucet system,
 /// including rate limiting and other server errors.
 #[derive(Debug, Error, Clone, Serialize, Deserialize)]
 pub enum FaucetError {
-    /// Returned when a request is rate limited. Contains the number of seconds to wait before retrying.
-    #[error("Rate limited. Try again in {retry_after_secs} seconds.")]
-    RateLimited { retry_after_secs: i32 },
+    /// Returned when a request is rate limited. Contains the number of seconds to wait before
+    /// retrying and the limit that was hit.
+    #[error("Rate limited ({reason}). Try again in {retry_after_secs} seconds.")]
+    RateLimited {
+        retry_after_secs: i32,
+        reason: RateLimitReason,
+    },
+    /// Returned when the proof-of-work challenge of the request is missing or invalid.
+    #[error("Proof of work rejected: {0}")]
+    Challenge(String),
+    /// Returned when the API key of the request is unknown or not valid for the faucet.
+    #[error("Invalid API key: {0}")]
+    ApiKey(String),
+    /// Returned when the human verification of the request is missing or failed.
+    #[error("Human verification failed: {0}")]
+    HumanVerification(String),
+    /// Returned when the faucet cannot cover the drip or its gas.
+    #[error("Insufficient funds: {0}")]
+    InsufficientFunds(String),
+    /// Returned when the datacap allocation of the request fails the pre-flight checks of the
+    /// verifier allowance and the recipient status.
+    #[error("Datacap allocation rejected: {0}")]
+    Datacap(DatacapRejection),
+    /// Returned when the node rejected the nonce of the drip message, usually because another
+    /// drip used it first. Retrying usually succeeds.
+    #[error("Nonce conflict: {0}. Please try again.")]
+    NonceConflict(String),
+    /// Returned when an actor the drip depends on does not exist on chain.
+    #[error("Actor not found: {0}")]
+    ActorNotFound(String),
+    /// Returned when the node rejected the request for any other reason.
+    #[error("Node error: {0}")]
+    Node(String),
     /// Represents a server-side error with a message.
     #[error("Server error: {0}")]
     Server(String),
 }
 
+/// JSON-RPC error code of a call to an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code of a call with invalid parameters.
+const INVALID_PARAMS: i64 = -32602;
+/// Lotus error code of an actor that does not exist, `api.EActorNotFound`.
+const LOTUS_ACTOR_NOT_FOUND: i64 = 4;
+/// Error codes Lotus and Forest report failures without a dedicated code with: the default code of
+/// go-jsonrpc, Lotus' `api.EUnknown`, the JSON-RPC internal error and the generic server error.
+const GENERIC_CODES: [i64; 4] = [1, 2, -32603, -32000];
+
+impl From<RpcError> for FaucetError {
+    /// The failure is told apart by the error code. Lotus and Forest report most failures with a
+    /// generic error code though, in which case it is told apart by the error message.
+    fn from(err: RpcError) -> Self {
+        match err.code {
+            LOTUS_ACTOR_NOT_FOUND => return FaucetError::ActorNotFound(err.message),
+            METHOD_NOT_FOUND | INVALID_PARAMS => return FaucetError::Node(err.to_string()),
+            code if !GENERIC_CODES.contains(&code) => return FaucetError::Node(err.to_string()),
+            _ => {}
+        }
+        let message = err.message.to_lowercase();
+        let matches = |patterns: &[&str]| patterns.iter().any(|pattern| message.contains(pattern));
+        if matches(&[
+            "insufficient funds",
+            "not enough funds",
+            "insufficient balance",
+        ]) {
+            FaucetError::InsufficientFunds(err.message)
+        } else if matches(&[
+            "nonce too low",
+            "sequence too low",
+            "nonce gap",
+            "already in mpool",
+            "existing nonce",
+        ]) {
+            FaucetError::NonceConflict(err.message)
+        } else if matches(&["actor not found"]) {
+            FaucetError::ActorNotFound(err.message)
+        } else {
+            FaucetError::Node(err.to_string())
+        }
+    }
+}
+
+impl FaucetError {
+    /// Converts an error of an RPC call, telling apart the failures reported by the node.
+    pub fn from_rpc(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<RpcError>() {
+            Some(err) => err.clone().into(),
+            None => FaucetError::Server(err.to_string()),
+        }
+    }
+}
+
+/// Error object of a JSON-RPC response, returned when the node rejects a call.
+#[derive(Debug, Error, Clone, PartialEq, Serialize, Deserialize)]
+#[error("{message} (code {code})")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
 impl FromServerFnError for FaucetError {
     type Encoder = JsonEncoding;
     fn from_server_fn_error(err: ServerFnErrorErr) -> Self {
@@ -37,6 +174,117 @@ pub async fn catch_all(
 ) {
     match cb.await {
         Ok(_) => (),
-        Err(e) => errors.update(|errors| errors.push((Uuid::new_v4(), e.to_string()))),
+        Err(e) => errors.update(|errors| errors.push((Uuid::new_v4(), error_message(&e)))),
+    }
+}
+
+/// Returns the message shown for an error. Rejections of the node are described by the matching
+/// [`FaucetError`].
+pub fn error_message(err: &anyhow::Error) -> String {
+    match err.downcast_ref::<RpcError>() {
+        Some(rpc_err) => FaucetError::from(rpc_err.clone()).to_string(),
+        None => err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rpc_error(message: &str) -> RpcError {
+        rpc_error_with_code(1, message)
+    }
+
+    fn rpc_error_with_code(code: i64, message: &str) -> RpcError {
+        RpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_rpc_error() {
+        let err: RpcError =
+            serde_json::from_str(r#"{"code":-32601,"message":"method not found"}"#).unwrap();
+        assert_eq!(err.code, -32601);
+        assert_eq!(err.message, "method not found");
+        assert_eq!(err.data, None);
+        assert_eq!(err.to_string(), "method not found (code -32601)");
+    }
+
+    #[test]
+    fn test_faucet_error_from_rpc_error() {
+        assert!(matches!(
+            FaucetError::from(rpc_error(
+                "mpool push: not enough funds (required: 1 FIL, tried: 0.5 FIL)"
+            )),
+            FaucetError::InsufficientFunds(_)
+        ));
+        assert!(matches!(
+            FaucetError::from(rpc_error(
+                "minimum expected nonce is 12: message nonce too low"
+            )),
+            FaucetError::NonceConflict(_)
+        ));
+        assert!(matches!(
+            FaucetError::from(rpc_error("Sequence too low")),
+            FaucetError::NonceConflict(_)
+        ));
+        assert!(matches!(
+            FaucetError::from(rpc_error(
+                "resolution lookup failed (t1abc): actor not found"
+            )),
+            FaucetError::ActorNotFound(_)
+        ));
+        assert!(matches!(
+            FaucetError::from(rpc_error("out of gas")),
+            FaucetError::Node(_)
+        ));
+    }
+
+    #[test]
+    fn test_faucet_error_from_rpc_error_code() {
+        assert!(matches!(
+            FaucetError::from(rpc_error_with_code(
+                LOTUS_ACTOR_NOT_FOUND,
+                "resolution lookup failed"
+            )),
+            FaucetError::ActorNotFound(_)
+        ));
+        // The message is only looked at for generic error codes.
+        assert!(matches!(
+            FaucetError::from(rpc_error_with_code(
+                INVALID_PARAMS,
+                "invalid params: actor not found in nonce too low"
+            )),
+            FaucetError::Node(_)
+        ));
+        assert!(matches!(
+            FaucetError::from(rpc_error_with_code(METHOD_NOT_FOUND, "method not found")),
+            FaucetError::Node(_)
+        ));
+        assert!(matches!(
+            FaucetError::from(rpc_error_with_code(-32603, "message nonce too low")),
+            FaucetError::NonceConflict(_)
+        ));
+    }
+
+    #[test]
+    fn test_error_message() {
+        let err = anyhow::Error::from(rpc_error("message nonce too low"))
+            .context("Failed to push message");
+        assert_eq!(
+            error_message(&err),
+            "Nonce conflict: message nonce too low. Please try again."
+        );
+        assert!(matches!(
+            FaucetError::from_rpc(&err),
+            FaucetError::NonceConflict(_)
+        ));
+        assert_eq!(
+            error_message(&anyhow::anyhow!("Invalid address")),
+            "Invalid address"
+        );
     }
 }
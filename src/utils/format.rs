@@ -1,16 +1,20 @@
 use crate::utils::drip_amount::DripAmount;
 use anyhow::{Result, anyhow};
-use fvm_shared::bigint::BigInt;
+use chrono::DateTime;
+use fvm_shared::bigint::{BigInt, Integer as _, Sign, Zero as _};
+use fvm_shared::econ::TokenAmount;
 use url::Url;
 
-/// Formats [`DripAmount`] to a human-readable string with the given unit.
-pub fn format_balance(amount: &DripAmount, unit: &str) -> String {
+/// Number of decimals shown for token amounts, unless the token has fewer.
+const DISPLAY_DECIMALS: usize = 2;
+
+/// Formats [`DripAmount`] to a human-readable string with the given unit. Token amounts are
+/// rounded to two decimals, or fewer if the token has fewer `decimals`. Small amounts that would
+/// round to zero get as many decimals as needed to show their first significant digit.
+pub fn format_balance(amount: &DripAmount, unit: &str, decimals: u8) -> String {
     match amount {
         DripAmount::Token(balance) => {
-            format!(
-                "{:.2} {unit}",
-                balance.to_string().parse::<f32>().unwrap_or_default(),
-            )
+            format!("{} {unit}", format_token_amount(balance, decimals))
         }
         DripAmount::Storage(balance) => {
             format!("{} MiB", balance / BigInt::from(1 << 20))
@@ -18,6 +22,40 @@ pub fn format_balance(amount: &DripAmount, unit: &str) -> String {
     }
 }
 
+fn format_token_amount(amount: &TokenAmount, decimals: u8) -> String {
+    let decimals = usize::from(decimals).min(TokenAmount::DECIMALS);
+    let atto = BigInt::from(amount.atto().magnitude().clone());
+    // Rounds half up to the given number of decimals.
+    let round = |precision: usize| -> BigInt {
+        let factor = BigInt::from(10).pow((TokenAmount::DECIMALS - precision) as u32);
+        (&atto + &factor / 2) / factor
+    };
+    let default_precision = decimals.min(DISPLAY_DECIMALS);
+    let precision = (default_precision..=decimals)
+        .find(|precision| !round(*precision).is_zero())
+        .unwrap_or(default_precision);
+    let (whole, fraction) = round(precision).div_rem(&BigInt::from(10).pow(precision as u32));
+    let sign = if amount.atto().sign() == Sign::Minus {
+        "-"
+    } else {
+        ""
+    };
+    if precision == 0 {
+        format!("{sign}{whole}")
+    } else {
+        format!("{sign}{whole}.{fraction:0>precision$}")
+    }
+}
+
+/// Formats a Unix timestamp in seconds as a UTC date and time, e.g., `2023-11-14 22:13:20 UTC`.
+pub fn format_timestamp(timestamp: u64) -> String {
+    i64::try_from(timestamp)
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
 /// Types of search paths in Filecoin explorer.
 #[derive(Copy, Clone)]
 pub enum SearchPath {
@@ -50,20 +88,57 @@ mod tests {
     #[test]
     fn test_format_balance() {
         let cases = [
-            (DripAmount::Token(TokenAmount::from_whole(1)), "1.00 FIL"),
-            (DripAmount::Token(TokenAmount::from_whole(0)), "0.00 FIL"),
+            (
+                DripAmount::Token(TokenAmount::from_whole(1)),
+                18,
+                "1.00 FIL",
+            ),
+            (
+                DripAmount::Token(TokenAmount::from_whole(0)),
+                18,
+                "0.00 FIL",
+            ),
             (
                 DripAmount::Token(TokenAmount::from_nano(10e6 as i64)),
+                18,
                 "0.01 FIL",
             ),
             (
                 DripAmount::Token(TokenAmount::from_nano(999_999_999)),
+                18,
                 "1.00 FIL",
             ),
-            (DripAmount::Storage(StoragePower::from(1 << 20)), "1 MiB"),
+            (
+                DripAmount::Token(TokenAmount::from_nano(1_234_567_890)),
+                18,
+                "1.23 FIL",
+            ),
+            // Small amounts show their first significant digit, within the token precision.
+            (
+                DripAmount::Token(TokenAmount::from_nano(1_234)),
+                18,
+                "0.000001 FIL",
+            ),
+            (DripAmount::Token(TokenAmount::from_atto(1)), 6, "0.00 FIL"),
+            (
+                DripAmount::Token(TokenAmount::from_nano(1_500_000_000)),
+                1,
+                "1.5 FIL",
+            ),
+            (
+                DripAmount::Token(TokenAmount::from_nano(1_500_000_000)),
+                0,
+                "2 FIL",
+            ),
+            (
+                DripAmount::Token(-TokenAmount::from_whole(3)),
+                18,
+                "-3.00 FIL",
+            ),
+            (DripAmount::Storage(StoragePower::from(1 << 20)), 0, "1 MiB"),
         ];
-        for (balance, expected) in cases.iter() {
-            assert_eq!(format_balance(balance, "FIL"), *expected);
+        for (balance, decimals, expected) in cases.iter() {
+            assert_eq!(format_balance(balance, "FIL", *decimals), *expected);
         }
     }
 
@@ -88,4 +163,11 @@ mod tests {
             assert_eq!(result.as_str(), *expected);
         }
     }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13:20 UTC");
+        assert_eq!(format_timestamp(u64::MAX), u64::MAX.to_string());
+    }
 }
@@ -0,0 +1,200 @@
+//! Subscription to the chain head over the WebSocket transport of an RPC endpoint, so that the
+//! faucet refreshes pending transactions and balances on each new tipset rather than on a timer.
+//!
+//! The subscription is made with `Filecoin.ChainNotify`, falling back to `eth_subscribe` with
+//! `newHeads` on endpoints only serving the Ethereum API. Callers fall back to polling when the
+//! endpoint supports neither, or has no WebSocket transport at all.
+
+#[cfg(any(feature = "hydrate", test))]
+use serde_json::{Value, json};
+#[cfg(any(feature = "hydrate", test))]
+use url::Url;
+
+/// ID of the `Filecoin.ChainNotify` request.
+#[cfg(any(feature = "hydrate", test))]
+const CHAIN_NOTIFY_ID: u64 = 0;
+/// ID of the `eth_subscribe` request, sent if `Filecoin.ChainNotify` is rejected.
+#[cfg(any(feature = "hydrate", test))]
+const ETH_SUBSCRIBE_ID: u64 = 1;
+
+/// Returns the WebSocket URL of a JSON-RPC endpoint, if it has one.
+#[cfg(any(feature = "hydrate", test))]
+pub fn ws_url(url: &Url) -> Option<Url> {
+    let scheme = match url.scheme() {
+        "http" | "ws" => "ws",
+        "https" | "wss" => "wss",
+        _ => return None,
+    };
+    let mut url = url.clone();
+    url.set_scheme(scheme).ok()?;
+    Some(url)
+}
+
+#[cfg(any(feature = "hydrate", test))]
+fn chain_notify_request() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "Filecoin.ChainNotify",
+        "params": [],
+        "id": CHAIN_NOTIFY_ID
+    })
+}
+
+#[cfg(any(feature = "hydrate", test))]
+fn eth_subscribe_request() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscribe",
+        "params": ["newHeads"],
+        "id": ETH_SUBSCRIBE_ID
+    })
+}
+
+/// A message received on the WebSocket of a subscription.
+#[cfg(any(feature = "hydrate", test))]
+#[derive(Debug, PartialEq, Eq)]
+enum Event {
+    /// The subscription request with the given ID was accepted.
+    Subscribed(u64),
+    /// The subscription request with the given ID was rejected.
+    Rejected(u64),
+    /// The chain head changed.
+    Head,
+    /// Anything else, e.g., a notification of another channel.
+    Other,
+}
+
+#[cfg(any(feature = "hydrate", test))]
+fn parse_event(msg: &Value) -> Event {
+    match msg.get("method").and_then(Value::as_str) {
+        // Lotus and Forest send `Filecoin.ChainNotify` changes on a channel.
+        Some("xrpc.ch.val" | "eth_subscription") => return Event::Head,
+        Some(_) => return Event::Other,
+        None => {}
+    }
+    match msg.get("id").and_then(Value::as_u64) {
+        Some(id) if msg.get("error").is_some() => Event::Rejected(id),
+        Some(id) if msg.get("result").is_some() => Event::Subscribed(id),
+        _ => Event::Other,
+    }
+}
+
+/// Subscribes to the chain head of an endpoint. Returns a stream yielding on each new tipset,
+/// which ends when the WebSocket closes. Browsers cannot set headers on WebSockets, so the bearer
+/// token, if any, is passed as the `token` query parameter, as Lotus and Forest accept.
+#[cfg(feature = "hydrate")]
+pub async fn subscribe_heads(
+    url: &Url,
+    token: Option<&str>,
+) -> anyhow::Result<impl futures::Stream<Item = ()>> {
+    use futures::{SinkExt as _, StreamExt as _};
+    use gloo_net::websocket::{Message, futures::WebSocket};
+
+    fn parse_message(msg: Message) -> Event {
+        match msg {
+            Message::Text(text) => serde_json::from_str(&text)
+                .map(|msg| parse_event(&msg))
+                .unwrap_or(Event::Other),
+            Message::Bytes(_) => Event::Other,
+        }
+    }
+
+    let url = ws_url(url).ok_or(anyhow::anyhow!("No WebSocket transport for {url}"))?;
+    let mut authenticated = url.clone();
+    if let Some(token) = token {
+        authenticated.query_pairs_mut().append_pair("token", token);
+    }
+    let mut ws = WebSocket::open(authenticated.as_str()).map_err(|e| anyhow::anyhow!("{e}"))?;
+    ws.send(Message::Text(chain_notify_request().to_string()))
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    loop {
+        let Some(msg) = ws.next().await else {
+            anyhow::bail!("WebSocket closed before subscribing to {url}");
+        };
+        match parse_message(msg.map_err(|e| anyhow::anyhow!("{e}"))?) {
+            Event::Subscribed(_) => break,
+            Event::Rejected(CHAIN_NOTIFY_ID) => {
+                ws.send(Message::Text(eth_subscribe_request().to_string()))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+            }
+            Event::Rejected(_) => anyhow::bail!("{url} does not support head subscriptions"),
+            Event::Head | Event::Other => {}
+        }
+    }
+    Ok(ws
+        .scan((), |_, msg| async move { msg.ok() })
+        .filter_map(|msg| async move {
+            match parse_message(msg) {
+                Event::Head => Some(()),
+                _ => None,
+            }
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws_url() {
+        let url = |s: &str| s.parse::<Url>().unwrap();
+        assert_eq!(
+            ws_url(&url("https://api.calibration.node.glif.io/rpc/v1")),
+            Some(url("wss://api.calibration.node.glif.io/rpc/v1"))
+        );
+        assert_eq!(
+            ws_url(&url("http://127.0.0.1:2345/rpc/v1")),
+            Some(url("ws://127.0.0.1:2345/rpc/v1"))
+        );
+        assert_eq!(ws_url(&url("ftp://example.com")), None);
+    }
+
+    #[test]
+    fn test_subscription_requests() {
+        let request = chain_notify_request();
+        assert_eq!(request["method"], "Filecoin.ChainNotify");
+        assert_eq!(request["id"], CHAIN_NOTIFY_ID);
+        let request = eth_subscribe_request();
+        assert_eq!(request["method"], "eth_subscribe");
+        assert_eq!(request["params"], json!(["newHeads"]));
+        assert_eq!(request["id"], ETH_SUBSCRIBE_ID);
+    }
+
+    #[test]
+    fn test_parse_event() {
+        assert_eq!(
+            parse_event(&json!({ "jsonrpc": "2.0", "id": 0, "result": 7 })),
+            Event::Subscribed(CHAIN_NOTIFY_ID)
+        );
+        assert_eq!(
+            parse_event(&json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "error": { "code": -32601, "message": "method not found" }
+            })),
+            Event::Rejected(CHAIN_NOTIFY_ID)
+        );
+        assert_eq!(
+            parse_event(&json!({
+                "jsonrpc": "2.0",
+                "method": "xrpc.ch.val",
+                "params": [7, [{ "Type": "apply", "Val": {} }]]
+            })),
+            Event::Head
+        );
+        assert_eq!(
+            parse_event(&json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": "0x1", "result": {} }
+            })),
+            Event::Head
+        );
+        assert_eq!(
+            parse_event(&json!({ "jsonrpc": "2.0", "method": "xrpc.ch.close", "params": [7] })),
+            Event::Other
+        );
+    }
+}
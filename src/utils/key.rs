@@ -86,7 +86,6 @@ impl FromStr for KeyInfo {
     }
 }
 
-#[cfg(feature = "ssr")]
 /// Generates BLAKE2b hash of fixed 32 bytes size.
 pub fn blake2b_256(ingest: &[u8]) -> [u8; 32] {
     use blake2b_simd::Params;
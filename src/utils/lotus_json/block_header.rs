@@ -0,0 +1,114 @@
+use super::ticket::Ticket;
+use super::tipset::TipsetKey;
+use super::*;
+use fvm_shared::{address::Address, bigint::BigInt, econ::TokenAmount};
+
+/// Header of a block, as returned by `Filecoin.ChainGetBlock`. Only the fields shown by the
+/// explorer are kept; the proofs, beacon entries and signatures of the header are dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub miner: Address,
+    /// Missing for the genesis block.
+    pub ticket: Option<Ticket>,
+    pub parents: TipsetKey,
+    pub parent_weight: BigInt,
+    pub height: i64,
+    pub parent_state_root: Cid,
+    pub parent_message_receipts: Cid,
+    pub messages: Cid,
+    /// Unix timestamp of the block, in seconds.
+    pub timestamp: u64,
+    pub fork_signaling: u64,
+    pub parent_base_fee: TokenAmount,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BlockHeaderLotusJson {
+    #[serde(with = "crate::utils::lotus_json")]
+    miner: Address,
+    #[serde(
+        with = "crate::utils::lotus_json",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    ticket: Option<Ticket>,
+    #[serde(with = "crate::utils::lotus_json")]
+    parents: TipsetKey,
+    #[serde(with = "crate::utils::lotus_json")]
+    parent_weight: BigInt,
+    height: i64,
+    #[serde(with = "crate::utils::lotus_json")]
+    parent_state_root: Cid,
+    #[serde(with = "crate::utils::lotus_json")]
+    parent_message_receipts: Cid,
+    #[serde(with = "crate::utils::lotus_json")]
+    messages: Cid,
+    timestamp: u64,
+    #[serde(default)]
+    fork_signaling: u64,
+    #[serde(with = "crate::utils::lotus_json")]
+    parent_base_fee: TokenAmount,
+}
+
+impl HasLotusJson for BlockHeader {
+    type LotusJson = BlockHeaderLotusJson;
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        let Self {
+            miner,
+            ticket,
+            parents,
+            parent_weight,
+            height,
+            parent_state_root,
+            parent_message_receipts,
+            messages,
+            timestamp,
+            fork_signaling,
+            parent_base_fee,
+        } = self;
+        Self::LotusJson {
+            miner,
+            ticket,
+            parents,
+            parent_weight,
+            height,
+            parent_state_root,
+            parent_message_receipts,
+            messages,
+            timestamp,
+            fork_signaling,
+            parent_base_fee,
+        }
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        let Self::LotusJson {
+            miner,
+            ticket,
+            parents,
+            parent_weight,
+            height,
+            parent_state_root,
+            parent_message_receipts,
+            messages,
+            timestamp,
+            fork_signaling,
+            parent_base_fee,
+        } = lotus_json;
+        Self {
+            miner,
+            ticket,
+            parents,
+            parent_weight,
+            height,
+            parent_state_root,
+            parent_message_receipts,
+            messages,
+            timestamp,
+            fork_signaling,
+            parent_base_fee,
+        }
+    }
+}
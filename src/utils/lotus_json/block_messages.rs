@@ -0,0 +1,64 @@
+use super::signed_message::SignedMessage;
+use super::*;
+use fvm_shared::message::Message;
+
+/// Messages included in a block, as returned by `Filecoin.ChainGetBlockMessages`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockMessages {
+    pub bls_messages: Vec<Message>,
+    pub secp_messages: Vec<SignedMessage>,
+    /// CIDs of the BLS messages followed by those of the Secp256k1 messages.
+    pub cids: Vec<Cid>,
+}
+
+impl BlockMessages {
+    /// Returns the messages of the block along with their CIDs, in the order of inclusion.
+    pub fn iter(&self) -> impl Iterator<Item = (&Cid, &Message)> {
+        self.cids.iter().zip(
+            self.bls_messages
+                .iter()
+                .chain(self.secp_messages.iter().map(|smsg| &smsg.message)),
+        )
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BlockMessagesLotusJson {
+    #[serde(with = "crate::utils::lotus_json")]
+    bls_messages: Vec<Message>,
+    #[serde(with = "crate::utils::lotus_json")]
+    secpk_messages: Vec<SignedMessage>,
+    #[serde(with = "crate::utils::lotus_json")]
+    cids: Vec<Cid>,
+}
+
+impl HasLotusJson for BlockMessages {
+    type LotusJson = BlockMessagesLotusJson;
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        let Self {
+            bls_messages,
+            secp_messages,
+            cids,
+        } = self;
+        Self::LotusJson {
+            bls_messages,
+            secpk_messages: secp_messages,
+            cids,
+        }
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        let Self::LotusJson {
+            bls_messages,
+            secpk_messages,
+            cids,
+        } = lotus_json;
+        Self {
+            bls_messages,
+            secp_messages: secpk_messages,
+            cids,
+        }
+    }
+}
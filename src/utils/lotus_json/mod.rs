@@ -165,13 +165,18 @@ mod big_int;
 mod cid;
 mod message;
 mod opt;
+mod receipt;
 mod signature;
 mod signature_type;
 mod token_amount;
 mod vec;
 mod vec_u8;
 
+pub mod block_header;
+pub mod block_messages;
 pub mod signed_message;
+pub mod ticket;
+pub mod tipset;
 
 // mod nonempty; // can't make snapshots of generic type
 // mod opt; // can't make snapshots of generic type
@@ -180,15 +185,85 @@ mod raw_bytes; // fvm_ipld_encoding::RawBytes: !quickcheck::Arbitrary
 
 // pub use vec::*;
 
+/// Execution of a message found on chain, as returned by `Filecoin.StateSearchMsg`.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct MessageLookup {
+    /// Height of the tipset the message was executed in, the one after its inclusion.
     pub height: i64,
     #[serde(with = "crate::utils::lotus_json")]
     pub message: Cid,
+    #[serde(with = "crate::utils::lotus_json")]
+    pub receipt: fvm_shared::receipt::Receipt,
+    #[serde(rename = "TipSet", with = "crate::utils::lotus_json")]
+    pub tipset: tipset::TipsetKey,
 }
 lotus_json_with_self!(MessageLookup);
 
+/// State of an actor, as returned by `Filecoin.StateGetActor`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ActorState {
+    /// Code CID of the actor, identifying its type and version.
+    #[serde(with = "crate::utils::lotus_json")]
+    pub code: Cid,
+    #[serde(with = "crate::utils::lotus_json")]
+    pub head: Cid,
+    pub nonce: u64,
+    #[serde(with = "crate::utils::lotus_json")]
+    pub balance: fvm_shared::econ::TokenAmount,
+    /// The `f4` address of actors created through the EAM.
+    #[serde(
+        with = "crate::utils::lotus_json",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub delegated_address: Option<fvm_shared::address::Address>,
+}
+lotus_json_with_self!(ActorState);
+
+/// Code CIDs of the builtin actors, keyed by their name in the builtin actors bundle, as returned
+/// by `Filecoin.StateActorCodeCIDs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ActorCodeCids(pub std::collections::HashMap<String, LotusJson<Cid>>);
+lotus_json_with_self!(ActorCodeCids);
+
+/// Gas cost of a replayed message, see [`InvocResult`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GasCost {
+    #[serde(with = "crate::utils::lotus_json")]
+    pub base_fee_burn: fvm_shared::econ::TokenAmount,
+    #[serde(with = "crate::utils::lotus_json")]
+    pub over_estimation_burn: fvm_shared::econ::TokenAmount,
+    #[serde(with = "crate::utils::lotus_json")]
+    pub miner_tip: fvm_shared::econ::TokenAmount,
+    #[serde(with = "crate::utils::lotus_json")]
+    pub refund: fvm_shared::econ::TokenAmount,
+    #[serde(with = "crate::utils::lotus_json")]
+    pub total_cost: fvm_shared::econ::TokenAmount,
+}
+
+/// Outcome of replaying a message, as returned by `Filecoin.StateReplay`. The execution trace is
+/// dropped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct InvocResult {
+    #[serde(
+        rename = "MsgRct",
+        with = "crate::utils::lotus_json",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub receipt: Option<fvm_shared::receipt::Receipt>,
+    pub gas_cost: GasCost,
+    /// Reason the execution failed, empty if it succeeded.
+    #[serde(default)]
+    pub error: String,
+}
+lotus_json_with_self!(InvocResult);
+
 /// Usage: `#[serde(with = "stringify")]`
 pub mod stringify {
     use super::*;
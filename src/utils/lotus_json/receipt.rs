@@ -0,0 +1,53 @@
+use super::*;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::error::ExitCode;
+use fvm_shared::receipt::Receipt;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReceiptLotusJson {
+    exit_code: u32,
+    #[serde(rename = "Return", with = "crate::utils::lotus_json")]
+    return_data: RawBytes,
+    gas_used: u64,
+    #[serde(
+        with = "crate::utils::lotus_json",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    events_root: Option<Cid>,
+}
+
+impl HasLotusJson for Receipt {
+    type LotusJson = ReceiptLotusJson;
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        let Self {
+            exit_code,
+            return_data,
+            gas_used,
+            events_root,
+        } = self;
+        Self::LotusJson {
+            exit_code: exit_code.value(),
+            return_data,
+            gas_used,
+            events_root,
+        }
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        let Self::LotusJson {
+            exit_code,
+            return_data,
+            gas_used,
+            events_root,
+        } = lotus_json;
+        Self {
+            exit_code: ExitCode::new(exit_code),
+            return_data,
+            gas_used,
+            events_root,
+        }
+    }
+}
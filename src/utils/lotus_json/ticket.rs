@@ -0,0 +1,27 @@
+use super::*;
+
+/// Ticket of a block, drawn by its miner from the ticket of the parent tipset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ticket {
+    pub vrf_proof: Vec<u8>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TicketLotusJson {
+    #[serde(rename = "VRFProof", with = "crate::utils::lotus_json")]
+    vrf_proof: Vec<u8>,
+}
+
+impl HasLotusJson for Ticket {
+    type LotusJson = TicketLotusJson;
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        let Self { vrf_proof } = self;
+        Self::LotusJson { vrf_proof }
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        let Self::LotusJson { vrf_proof } = lotus_json;
+        Self { vrf_proof }
+    }
+}
@@ -0,0 +1,120 @@
+use super::block_header::BlockHeader;
+use super::*;
+
+/// Key of a tipset: the CIDs of its blocks.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TipsetKey(pub Vec<Cid>);
+
+impl HasLotusJson for TipsetKey {
+    type LotusJson = <Vec<Cid> as HasLotusJson>::LotusJson;
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        self.0.into_lotus_json()
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        Self(Vec::from_lotus_json(lotus_json))
+    }
+}
+
+/// Blocks mined at the same height on the same parents, as returned by `Filecoin.ChainHead` and
+/// `Filecoin.ChainGetTipSetByHeight`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tipset {
+    pub key: TipsetKey,
+    pub blocks: Vec<BlockHeader>,
+    pub height: i64,
+}
+
+impl Tipset {
+    /// Returns the first block of the tipset. The fields shared by all blocks, such as the
+    /// parents, the parent weight and the base fee, are read from it.
+    pub fn first_block(&self) -> Option<&BlockHeader> {
+        self.blocks.first()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TipsetLotusJson {
+    #[serde(with = "crate::utils::lotus_json")]
+    cids: TipsetKey,
+    #[serde(with = "crate::utils::lotus_json")]
+    blocks: Vec<BlockHeader>,
+    height: i64,
+}
+
+impl HasLotusJson for Tipset {
+    type LotusJson = TipsetLotusJson;
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        let Self {
+            key,
+            blocks,
+            height,
+        } = self;
+        Self::LotusJson {
+            cids: key,
+            blocks,
+            height,
+        }
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        let Self::LotusJson {
+            cids,
+            blocks,
+            height,
+        } = lotus_json;
+        Self {
+            key: cids,
+            blocks,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+
+    #[test]
+    fn test_parse_tipset() {
+        let cid = "bafy2bzacecnamqgqmifpluoeldx7zzglxcljo6oja4vrmtj7432rphldpdmm2";
+        let parent = "bafy2bzaceaxm23epjsmh75yvzcecsrbavlmkcxnva66bkdebdcnyw3bjrc74u";
+        let json = serde_json::json!({
+            "Cids": [{ "/": cid }],
+            "Blocks": [{
+                "Miner": "t01000",
+                "Ticket": { "VRFProof": "AQID" },
+                "ElectionProof": { "WinCount": 1, "VRFProof": "AQID" },
+                "BeaconEntries": null,
+                "WinPoStProof": null,
+                "Parents": [{ "/": parent }],
+                "ParentWeight": "12345",
+                "Height": 42,
+                "ParentStateRoot": { "/": parent },
+                "ParentMessageReceipts": { "/": parent },
+                "Messages": { "/": parent },
+                "BLSAggregate": null,
+                "Timestamp": 1700000000,
+                "BlockSig": null,
+                "ForkSignaling": 0,
+                "ParentBaseFee": "100"
+            }],
+            "Height": 42
+        });
+        let LotusJson(tipset) = serde_json::from_value::<LotusJson<Tipset>>(json).unwrap();
+        assert_eq!(tipset.height, 42);
+        assert_eq!(tipset.key, TipsetKey(vec![cid.parse().unwrap()]));
+        let block = tipset.first_block().unwrap();
+        assert_eq!(block.miner, Address::new_id(1000));
+        assert_eq!(block.ticket.as_ref().unwrap().vrf_proof, [1, 2, 3]);
+        assert_eq!(block.parents, TipsetKey(vec![parent.parse().unwrap()]));
+        assert_eq!(block.parent_weight, 12345.into());
+        assert_eq!(block.timestamp, 1700000000);
+        assert_eq!(block.parent_base_fee, TokenAmount::from_atto(100));
+    }
+}
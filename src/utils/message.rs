@@ -44,6 +44,13 @@ pub fn message_transfer_native(
 
 const VERIFIED_REGISTRY_ACTOR: Address = Address::new_id(6);
 
+/// Method number of `AddVerifiedClient` of the verified registry actor.
+const ADD_VERIFIED_CLIENT_METHOD: u64 = frc42_dispatch::method_hash!("AddVerifiedClient");
+
+/// Builtin method number of `AddVerifiedClient`, used by most verifiers, e.g., by Lotus
+/// `filplus grant-datacap`.
+const ADD_VERIFIED_CLIENT_BUILTIN_METHOD: u64 = 4;
+
 /// Params for the `AddVerifiedClient` method.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
 pub struct AddVerifiedClientParams {
@@ -77,7 +84,7 @@ pub fn message_grant_datacap_native(
         from,
         to: VERIFIED_REGISTRY_ACTOR,
         value: TokenAmount::default(),
-        method_num: frc42_dispatch::method_hash!("AddVerifiedClient"),
+        method_num: ADD_VERIFIED_CLIENT_METHOD,
         params,
         gas_limit,
         gas_fee_cap,
@@ -86,3 +93,87 @@ pub fn message_grant_datacap_native(
         version: 0,
     }
 }
+
+/// A call of a method known to the explorer, with its decoded parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MethodCall {
+    /// Plain value transfer with [`METHOD_SEND`].
+    Send,
+    /// `AddVerifiedClient` of the verified registry actor, granting datacap.
+    AddVerifiedClient(AddVerifiedClientParams),
+}
+
+impl MethodCall {
+    /// Decodes the method call of a message. Returns `None` for unknown methods, and for known
+    /// methods whose parameters fail to decode.
+    pub fn decode(msg: &Message) -> Option<Self> {
+        match msg.method_num {
+            METHOD_SEND => Some(Self::Send),
+            ADD_VERIFIED_CLIENT_METHOD | ADD_VERIFIED_CLIENT_BUILTIN_METHOD
+                if msg.to == VERIFIED_REGISTRY_ACTOR =>
+            {
+                fvm_ipld_encoding::from_slice(msg.params.bytes())
+                    .ok()
+                    .map(Self::AddVerifiedClient)
+            }
+            _ => None,
+        }
+    }
+
+    /// Name of the called method.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Send => "Send",
+            Self::AddVerifiedClient(_) => "AddVerifiedClient",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_method_call() {
+        let from = Address::new_id(1000);
+        let transfer = message_transfer(from, Address::new_id(1234), TokenAmount::from_whole(1));
+        assert_eq!(MethodCall::decode(&transfer), Some(MethodCall::Send));
+
+        let params = AddVerifiedClientParams {
+            address: Address::new_id(1234),
+            allowance: StoragePower::from(1 << 20),
+        };
+        let grant = message_grant_datacap(
+            from,
+            RawBytes::new(fvm_ipld_encoding::to_vec(&params).unwrap()),
+        );
+        let call = MethodCall::decode(&grant).unwrap();
+        assert_eq!(call.name(), "AddVerifiedClient");
+        assert_eq!(call, MethodCall::AddVerifiedClient(params.clone()));
+        let builtin_grant = Message {
+            method_num: ADD_VERIFIED_CLIENT_BUILTIN_METHOD,
+            ..grant.clone()
+        };
+        assert_eq!(
+            MethodCall::decode(&builtin_grant),
+            Some(MethodCall::AddVerifiedClient(params))
+        );
+
+        // Same method number on another actor, or malformed parameters.
+        let other_actor = Message {
+            to: Address::new_id(7),
+            ..grant.clone()
+        };
+        assert_eq!(MethodCall::decode(&other_actor), None);
+        let builtin_other_actor = Message {
+            to: Address::new_id(7),
+            ..builtin_grant
+        };
+        assert_eq!(MethodCall::decode(&builtin_other_actor), None);
+        let malformed = Message {
+            params: RawBytes::new(vec![0xff]),
+            ..grant
+        };
+        assert_eq!(MethodCall::decode(&malformed), None);
+    }
+}
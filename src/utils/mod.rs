@@ -1,10 +1,18 @@
+pub mod actor;
 pub mod address;
 pub mod conversions;
+pub mod custom_endpoint;
+pub mod datacap;
 pub mod drip_amount;
+pub mod erc20;
 pub mod error;
 pub mod format;
+pub mod head_subscription;
 pub mod key;
 pub mod lotus_json;
 pub mod message;
+pub mod network;
+pub mod provider_pool;
+pub mod rpc_cache;
 pub mod rpc_context;
 pub mod transaction_id;
@@ -0,0 +1,254 @@
+//! Networks the explorer and the faucets can be pointed at.
+//!
+//! Calibnet and mainnet are built in. Other networks, e.g., local devnets, are configured at build
+//! time as a JSON array of [`NetworkConfig`], either inline in the `NETWORKS` environment variable
+//! or in the file given by `NETWORKS_FILE`. A configured network named like a built-in one
+//! replaces it.
+
+use anyhow::ensure;
+use fvm_shared::address::Network;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use url::Url;
+
+/// Name of the built-in calibration network.
+pub const CALIBNET: &str = "calibnet";
+/// Name of the built-in main network.
+pub const MAINNET: &str = "mainnet";
+
+/// A public JSON-RPC endpoint of a network.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcEndpoint {
+    pub label: String,
+    pub url: Url,
+}
+
+/// A Filecoin network, with the endpoints used to reach it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Short name of the network, e.g., `calibnet`.
+    pub name: String,
+    /// Prefix of the addresses of the network, `f` or `t`.
+    #[serde(with = "prefix")]
+    pub prefix: Network,
+    /// Ethereum chain ID of the network.
+    pub chain_id: u64,
+    /// Name of the network as reported by `Filecoin.StateNetworkName`, e.g., `calibrationnet`, if
+    /// it is known ahead of time.
+    #[serde(default)]
+    pub network_name: Option<String>,
+    /// Public endpoints of the network, the first one being preferred.
+    pub rpc_endpoints: Vec<RpcEndpoint>,
+    /// Base URL of a block explorer of the network, used to link to messages and addresses.
+    #[serde(default)]
+    pub explorer_url: Option<Url>,
+}
+
+impl NetworkConfig {
+    /// Returns the configured network with the given name, if any.
+    pub fn by_name(name: &str) -> Option<&'static Self> {
+        NETWORKS.iter().find(|network| network.name == name)
+    }
+
+    pub fn calibnet() -> &'static Self {
+        Self::by_name(CALIBNET).expect("calibnet is built in")
+    }
+
+    #[cfg(test)]
+    pub fn mainnet() -> &'static Self {
+        Self::by_name(MAINNET).expect("mainnet is built in")
+    }
+
+    /// Unit of the native token of the network, `FIL` on mainnet and `tFIL` on test networks.
+    pub fn native_unit(&self) -> &'static str {
+        match self.prefix {
+            Network::Mainnet => "FIL",
+            Network::Testnet => "tFIL",
+        }
+    }
+
+    /// Label of the network in the network selection, e.g., `Calibnet`.
+    pub fn label(&self) -> String {
+        let mut chars = self.name.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Serializes the address prefix of a network as `f` or `t`.
+mod prefix {
+    use fvm_shared::address::Network;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(network: &Network, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match network {
+            Network::Mainnet => "f",
+            Network::Testnet => "t",
+        })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Network, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "f" => Ok(Network::Mainnet),
+            "t" => Ok(Network::Testnet),
+            prefix => Err(serde::de::Error::custom(format!(
+                "invalid address prefix `{prefix}`, expected `f` or `t`"
+            ))),
+        }
+    }
+}
+
+fn endpoint(label: &str, url: &str) -> RpcEndpoint {
+    RpcEndpoint {
+        label: label.to_string(),
+        url: url.parse().expect("invalid built-in endpoint URL"),
+    }
+}
+
+fn builtin_networks() -> Vec<NetworkConfig> {
+    vec![
+        NetworkConfig {
+            name: CALIBNET.to_string(),
+            prefix: Network::Testnet,
+            // https://chainlist.org/chain/314159
+            chain_id: 314159,
+            network_name: Some("calibrationnet".to_string()),
+            rpc_endpoints: vec![
+                endpoint("Glif", "https://api.calibration.node.glif.io"),
+                endpoint("Ankr", "https://rpc.ankr.com/filecoin_testnet"),
+                endpoint("Filfox", "https://calibration.filfox.info/rpc/v1"),
+            ],
+            explorer_url: option_env!("FAUCET_TX_URL_CALIBNET")
+                .unwrap_or("https://beryx.io/fil/calibration/")
+                .parse()
+                .ok(),
+        },
+        NetworkConfig {
+            name: MAINNET.to_string(),
+            prefix: Network::Mainnet,
+            // https://chainlist.org/chain/314
+            chain_id: 314,
+            network_name: Some("mainnet".to_string()),
+            rpc_endpoints: vec![
+                endpoint("Glif", "https://api.node.glif.io"),
+                endpoint("Ankr", "https://rpc.ankr.com/filecoin"),
+                endpoint("Filfox", "https://filfox.info/rpc/v1"),
+            ],
+            explorer_url: option_env!("FAUCET_TX_URL_MAINNET")
+                .unwrap_or("https://beryx.io/fil/mainnet/")
+                .parse()
+                .ok(),
+        },
+    ]
+}
+
+/// Parses the configured networks.
+fn parse_networks(config: &str) -> anyhow::Result<Vec<NetworkConfig>> {
+    let networks = serde_json::from_str::<Vec<NetworkConfig>>(config)?;
+    for network in &networks {
+        ensure!(!network.name.is_empty(), "Network without a name");
+        ensure!(
+            !network.rpc_endpoints.is_empty(),
+            "Network `{}` has no RPC endpoint",
+            network.name
+        );
+    }
+    Ok(networks)
+}
+
+/// Adds the configured networks to `networks`, replacing the ones with the same name.
+fn merge_networks(networks: &mut Vec<NetworkConfig>, configured: Vec<NetworkConfig>) {
+    for network in configured {
+        match networks.iter_mut().find(|other| other.name == network.name) {
+            Some(other) => *other = network,
+            None => networks.push(network),
+        }
+    }
+}
+
+static NETWORKS: LazyLock<Vec<NetworkConfig>> = LazyLock::new(|| {
+    let mut networks = builtin_networks();
+    if let Some(config) = option_env!("NETWORKS") {
+        let configured = parse_networks(config).expect("networks are validated by build.rs");
+        merge_networks(&mut networks, configured);
+    }
+    networks
+});
+
+/// All the networks, built-in ones first.
+pub fn networks() -> &'static [NetworkConfig] {
+    &NETWORKS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEVNET: &str = r#"[
+        {
+            "name": "devnet",
+            "prefix": "t",
+            "chain_id": 31415926,
+            "rpc_endpoints": [{ "label": "Local", "url": "http://127.0.0.1:2345/rpc/v1" }]
+        }
+    ]"#;
+
+    #[test]
+    fn test_builtin_networks() {
+        let calibnet = NetworkConfig::calibnet();
+        assert_eq!(calibnet.prefix, Network::Testnet);
+        assert_eq!(calibnet.chain_id, 314159);
+        assert_eq!(calibnet.label(), "Calibnet");
+        let mainnet = NetworkConfig::mainnet();
+        assert_eq!(mainnet.prefix, Network::Mainnet);
+        assert_eq!(mainnet.chain_id, 314);
+        assert!(mainnet.explorer_url.is_some());
+    }
+
+    #[test]
+    fn test_parse_networks() {
+        let networks = parse_networks(DEVNET).unwrap();
+        assert_eq!(networks.len(), 1);
+        let devnet = &networks[0];
+        assert_eq!(devnet.prefix, Network::Testnet);
+        assert_eq!(devnet.chain_id, 31415926);
+        assert_eq!(devnet.explorer_url, None);
+        assert_eq!(devnet.network_name, None);
+        assert_eq!(
+            devnet.rpc_endpoints[0].url.as_str(),
+            "http://127.0.0.1:2345/rpc/v1"
+        );
+
+        // Round-trips through its serialized form.
+        let serialized = serde_json::to_string(&networks).unwrap();
+        assert_eq!(parse_networks(&serialized).unwrap(), networks);
+
+        assert!(parse_networks(&DEVNET.replace(r#""t""#, r#""x""#)).is_err());
+        assert!(
+            parse_networks(
+                r#"[{ "name": "devnet", "prefix": "t", "chain_id": 1, "rpc_endpoints": [] }]"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_merge_networks() {
+        let mut networks = builtin_networks();
+        let mut configured = parse_networks(DEVNET).unwrap();
+        configured.push(NetworkConfig {
+            chain_id: 1,
+            ..builtin_networks()[0].clone()
+        });
+        merge_networks(&mut networks, configured);
+        assert_eq!(
+            networks
+                .iter()
+                .map(|network| (network.name.as_str(), network.chain_id))
+                .collect::<Vec<_>>(),
+            [(CALIBNET, 1), (MAINNET, 314), ("devnet", 31415926)]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Pool of the RPC endpoints of a [`super::rpc_context::Provider`]. Each call is tried on the
+//! endpoints in turn until one of them answers, healthy endpoints first.
+//!
+//! The health of an endpoint, i.e., its error rate and latency, is tracked across all pools, so
+//! that an endpoint found down by one call is avoided by the next ones. Endpoints are ranked by
+//! health class and keep their configured order within a class, so the preferred endpoint is used
+//! as long as it is healthy.
+
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use url::Url;
+
+/// Timeout of a single JSON-RPC call on an endpoint.
+pub const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Weight of the latest call in the moving averages of the endpoint health.
+const SMOOTHING: f64 = 0.3;
+
+/// Endpoints failing at least this often are tried last.
+const MAX_ERROR_RATE: f64 = 0.5;
+
+/// Endpoints answering slower than this on average are tried after the faster ones.
+const SLOW_LATENCY_MS: f64 = 2000.0;
+
+/// Health of an RPC endpoint, as observed by the calls made to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EndpointHealth {
+    /// Exponential moving average of the failures, from 0 (never fails) to 1 (always fails).
+    pub error_rate: f64,
+    /// Exponential moving average of the latency of the successful calls, in milliseconds.
+    pub latency_ms: Option<f64>,
+}
+
+impl EndpointHealth {
+    fn record_success(&mut self, latency_ms: f64) {
+        self.error_rate *= 1.0 - SMOOTHING;
+        self.latency_ms = Some(match self.latency_ms {
+            Some(average) => average + SMOOTHING * (latency_ms - average),
+            None => latency_ms,
+        });
+    }
+
+    fn record_failure(&mut self) {
+        self.error_rate += SMOOTHING * (1.0 - self.error_rate);
+    }
+
+    /// Health class of the endpoint: 0 if it is healthy, 1 if it is slow and 2 if it fails too
+    /// often.
+    fn class(&self) -> u8 {
+        if self.error_rate >= MAX_ERROR_RATE {
+            2
+        } else if self
+            .latency_ms
+            .is_some_and(|latency| latency > SLOW_LATENCY_MS)
+        {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+static HEALTH: LazyLock<Mutex<HashMap<Url, EndpointHealth>>> = LazyLock::new(Default::default);
+
+/// Returns the health of an endpoint. Endpoints that were never called are deemed healthy.
+pub fn health(url: &Url) -> EndpointHealth {
+    HEALTH
+        .lock()
+        .expect("endpoint health lock poisoned")
+        .get(url)
+        .copied()
+        .unwrap_or_default()
+}
+
+fn update_health(url: &Url, update: impl FnOnce(&mut EndpointHealth)) {
+    update(
+        HEALTH
+            .lock()
+            .expect("endpoint health lock poisoned")
+            .entry(url.clone())
+            .or_default(),
+    );
+}
+
+/// Returns whether a call failed because of the endpoint, e.g., it is unreachable, timed out or
+/// answered garbage, as opposed to the node rejecting the call. Only the former is worth trying
+/// on another endpoint.
+pub fn is_endpoint_failure(err: &anyhow::Error) -> bool {
+    if err.downcast_ref::<reqwest::Error>().is_some() {
+        return true;
+    }
+    match err.downcast_ref::<alloy::transports::TransportError>() {
+        Some(err) => !err.is_error_resp(),
+        None => false,
+    }
+}
+
+/// Endpoints serving the same network, in order of preference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProviderPool {
+    urls: Vec<Url>,
+    timeout: Duration,
+}
+
+impl ProviderPool {
+    /// Creates a pool of the given endpoints. The first one is the preferred endpoint.
+    pub fn new(urls: Vec<Url>) -> Self {
+        assert!(!urls.is_empty(), "a provider pool needs an endpoint");
+        Self {
+            urls,
+            timeout: RPC_TIMEOUT,
+        }
+    }
+
+    /// Sets the timeout of a single JSON-RPC call on an endpoint.
+    #[cfg(test)]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The preferred endpoint of the pool.
+    pub fn primary(&self) -> &Url {
+        &self.urls[0]
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns the endpoints in the order they are tried: healthy endpoints first, then slow
+    /// ones, then failing ones, each in the configured order.
+    pub fn ranked(&self) -> Vec<Url> {
+        let mut urls = self.urls.clone();
+        urls.sort_by_key(|url| health(url).class());
+        urls
+    }
+
+    /// Makes a call on the endpoints of the pool in turn, until one of them does not fail, see
+    /// [`is_endpoint_failure`]. Returns the error of the last endpoint if all of them fail.
+    pub async fn call<T, F, Fut>(&self, call: F) -> anyhow::Result<T>
+    where
+        F: Fn(Url) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut last_err = None;
+        for url in self.ranked() {
+            let started = Utc::now();
+            let result = call(url.clone()).await;
+            match result {
+                Err(err) if is_endpoint_failure(&err) => {
+                    log::warn!("RPC endpoint {url} failed: {err}");
+                    update_health(&url, EndpointHealth::record_failure);
+                    last_err = Some(err);
+                }
+                result => {
+                    let latency_ms = (Utc::now() - started).num_milliseconds() as f64;
+                    update_health(&url, |health| health.record_success(latency_ms));
+                    return result;
+                }
+            }
+        }
+        Err(last_err.expect("a provider pool has at least one endpoint"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(host: &str) -> Url {
+        format!("https://{host}/rpc/v1").parse().unwrap()
+    }
+
+    #[test]
+    fn test_health_classes() {
+        let mut health = EndpointHealth::default();
+        assert_eq!(health.class(), 0);
+        health.record_success(SLOW_LATENCY_MS * 2.0);
+        assert_eq!(health.class(), 1);
+        for _ in 0..10 {
+            health.record_success(100.0);
+        }
+        assert_eq!(health.class(), 0);
+        health.record_failure();
+        assert_eq!(health.class(), 0);
+        health.record_failure();
+        health.record_failure();
+        assert_eq!(health.class(), 2);
+        // Recovers once it answers again.
+        for _ in 0..3 {
+            health.record_success(100.0);
+        }
+        assert_eq!(health.class(), 0);
+    }
+
+    #[test]
+    fn test_ranked_prefers_healthy_endpoints() {
+        let pool = ProviderPool::new(vec![
+            url("failing.ranked"),
+            url("slow.ranked"),
+            url("healthy.ranked"),
+            url("unknown.ranked"),
+        ]);
+        for _ in 0..3 {
+            update_health(&url("failing.ranked"), EndpointHealth::record_failure);
+        }
+        update_health(&url("slow.ranked"), |health| {
+            health.record_success(SLOW_LATENCY_MS * 2.0)
+        });
+        update_health(&url("healthy.ranked"), |health| {
+            health.record_success(100.0)
+        });
+        assert_eq!(
+            pool.ranked(),
+            [
+                url("healthy.ranked"),
+                url("unknown.ranked"),
+                url("slow.ranked"),
+                url("failing.ranked"),
+            ]
+        );
+        assert_eq!(pool.primary(), &url("failing.ranked"));
+    }
+
+    #[test]
+    fn test_is_endpoint_failure() {
+        assert!(!is_endpoint_failure(&anyhow::anyhow!("No result")));
+        let err = alloy::transports::TransportErrorKind::custom_str("connection refused");
+        assert!(is_endpoint_failure(&err.into()));
+    }
+}
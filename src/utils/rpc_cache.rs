@@ -0,0 +1,342 @@
+//! Cache of the results of JSON-RPC calls that never change, e.g., the ID address of an actor or
+//! the Ethereum hash of a message, or that change slowly, e.g., the network version.
+//!
+//! Each [`super::rpc_context::Provider`] keeps the results in a small LRU cache, keyed on the
+//! method and parameters of the call. On the worker, which creates a provider per request, the
+//! results are also kept in the Cloudflare Cache API, see [`worker_cache`]. The hits and misses of
+//! the caches of the worker are reported by the [`rpc_cache_stats`] endpoint.
+
+use chrono::{DateTime, Duration, Utc};
+use leptos::{prelude::ServerFnError, server, server_fn::codec::GetUrl};
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+/// Maximum number of results kept by a provider.
+const CAPACITY: NonZeroUsize = NonZeroUsize::new(256).unwrap();
+
+/// How long a found message is cached. The message may still be reverted until finality.
+const SEARCH_MSG_TTL: Duration = Duration::minutes(10);
+
+/// How long the actor addresses are cached. A new actor may still be reverted until finality, i.e.,
+/// 900 epochs of 30 seconds, and get another ID.
+const ACTOR_ADDRESS_TTL: Duration = Duration::seconds(900 * 30);
+
+/// How long the network version is cached. It only changes on network upgrades.
+const NETWORK_VERSION_TTL: Duration = Duration::minutes(5);
+
+/// Pseudo-method under which the metadata of an ERC-20 token is cached. It is read from the
+/// contract with several `eth_call`s, and never changes.
+pub const ERC20_METADATA: &str = "ERC20.metadata";
+
+/// How long the result of a call may be cached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lifetime {
+    /// The result never changes.
+    Immutable,
+    /// The result may change after the given duration.
+    Ttl(Duration),
+}
+
+/// Returns how long the result of a call may be cached, if at all. Errors are never cached, nor
+/// are missing results, which may show up later.
+pub fn lifetime(method: &str, result: &Value) -> Option<Lifetime> {
+    if result.is_null() {
+        return None;
+    }
+    method_lifetime(method)
+}
+
+/// Returns whether the results of a method may be cached.
+pub fn is_cacheable(method: &str) -> bool {
+    method_lifetime(method).is_some()
+}
+
+fn method_lifetime(method: &str) -> Option<Lifetime> {
+    match method {
+        "Filecoin.StateNetworkName"
+        | "Filecoin.EthChainId"
+        | "Filecoin.EthGetTransactionHashByCid"
+        | "Filecoin.EthGetMessageCidByTransactionHash"
+        | "Filecoin.ChainGetBlock"
+        | "Filecoin.ChainGetBlockMessages"
+        | "Filecoin.ChainGetMessage"
+        | "Filecoin.StateActorCodeCIDs"
+        | ERC20_METADATA => Some(Lifetime::Immutable),
+        "Filecoin.StateLookupID" | "Filecoin.StateAccountKey" => {
+            Some(Lifetime::Ttl(ACTOR_ADDRESS_TTL))
+        }
+        "Filecoin.StateSearchMsg" => Some(Lifetime::Ttl(SEARCH_MSG_TTL)),
+        "Filecoin.StateNetworkVersion" => Some(Lifetime::Ttl(NETWORK_VERSION_TTL)),
+        _ => None,
+    }
+}
+
+/// Returns the key of a call in the cache.
+pub fn cache_key(method: &str, params: &[Value]) -> String {
+    format!("{method}{}", Value::from(params))
+}
+
+/// Hits and misses of the cacheable calls of a provider, in its own cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    fn record(&mut self, hit: bool) {
+        match hit {
+            true => self.hits += 1,
+            false => self.misses += 1,
+        }
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} hits, {} misses", self.hits, self.misses)
+    }
+}
+
+/// Hits and misses of all the providers of the process, i.e., of the browser tab or of the worker
+/// isolate, which serves many requests.
+static TOTAL_STATS: Mutex<CacheStats> = Mutex::new(CacheStats { hits: 0, misses: 0 });
+
+/// Returns the hits and misses of all the providers of the process.
+pub fn total_stats() -> CacheStats {
+    *TOTAL_STATS.lock().expect("RPC cache stats lock poisoned")
+}
+
+/// Server API endpoint reporting the hits and misses of the RPC caches of the worker, for
+/// debugging. The counters are those of the worker isolate serving the request, and start over
+/// with every new isolate.
+#[server(endpoint = "rpc_cache_stats", input = GetUrl)]
+pub async fn rpc_cache_stats() -> Result<CacheStats, ServerFnError> {
+    Ok(total_stats())
+}
+
+struct Entry {
+    result: Value,
+    expires: Option<DateTime<Utc>>,
+}
+
+struct Inner {
+    entries: LruCache<String, Entry>,
+    stats: CacheStats,
+}
+
+/// LRU cache of call results, shared by the clones of a provider.
+#[derive(Clone)]
+pub struct RpcCache(Arc<Mutex<Inner>>);
+
+impl Default for RpcCache {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(Inner {
+            entries: LruCache::new(CAPACITY),
+            stats: CacheStats::default(),
+        })))
+    }
+}
+
+impl PartialEq for RpcCache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for RpcCache {}
+
+impl RpcCache {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.0.lock().expect("RPC cache lock poisoned")
+    }
+
+    /// Returns the cached result of a call, if it has not expired.
+    pub fn get(&self, key: &str, now: DateTime<Utc>) -> Option<Value> {
+        let mut inner = self.lock();
+        let result = match inner.entries.get(key) {
+            Some(entry) if entry.expires.is_none_or(|expires| now < expires) => {
+                Some(entry.result.clone())
+            }
+            Some(_) => {
+                inner.entries.pop(key);
+                None
+            }
+            None => None,
+        };
+        inner.stats.record(result.is_some());
+        TOTAL_STATS
+            .lock()
+            .expect("RPC cache stats lock poisoned")
+            .record(result.is_some());
+        result
+    }
+
+    /// Caches the result of a call, if its method allows it. Returns its lifetime.
+    pub fn put(
+        &self,
+        key: String,
+        method: &str,
+        result: &Value,
+        now: DateTime<Utc>,
+    ) -> Option<Lifetime> {
+        let lifetime = lifetime(method, result)?;
+        let expires = match lifetime {
+            Lifetime::Immutable => None,
+            Lifetime::Ttl(ttl) => Some(now + ttl),
+        };
+        self.lock().entries.put(
+            key,
+            Entry {
+                result: result.clone(),
+                expires,
+            },
+        );
+        Some(lifetime)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats
+    }
+}
+
+/// Second level of the cache on the worker, backed by the Cloudflare Cache API. It is best-effort:
+/// failures of the Cache API are ignored.
+#[cfg(all(feature = "ssr", not(test)))]
+pub mod worker_cache {
+    use super::{Lifetime, Value};
+    use url::Url;
+    use worker::{Cache, Response};
+
+    /// How long immutable results are kept, in seconds.
+    const IMMUTABLE_MAX_AGE: i64 = 30 * 24 * 60 * 60;
+
+    /// Returns the URL under which a call to an endpoint is cached.
+    fn cache_url(endpoint: &Url, key: &str) -> String {
+        let hash = blake2b_simd::Params::new()
+            .hash_length(32)
+            .to_state()
+            .update(endpoint.as_str().as_bytes())
+            .update(key.as_bytes())
+            .finalize();
+        format!("https://rpc-cache.invalid/{}", hash.to_hex())
+    }
+
+    pub async fn get(endpoint: &Url, key: &str) -> Option<Value> {
+        let mut response = Cache::default()
+            .get(cache_url(endpoint, key), false)
+            .await
+            .ok()??;
+        response.json().await.ok()
+    }
+
+    pub async fn put(endpoint: &Url, key: &str, result: &Value, lifetime: Lifetime) {
+        let max_age = match lifetime {
+            Lifetime::Immutable => IMMUTABLE_MAX_AGE,
+            Lifetime::Ttl(ttl) => ttl.num_seconds(),
+        };
+        let cached = async {
+            let mut response = Response::from_json(result)?;
+            response
+                .headers_mut()
+                .set("Cache-Control", &format!("max-age={max_age}"))?;
+            Cache::default()
+                .put(cache_url(endpoint, key), response)
+                .await
+        };
+        if let Err(e) = cached.await {
+            log::debug!("Failed to cache RPC result: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lifetime() {
+        assert_eq!(
+            lifetime("Filecoin.StateLookupID", &json!("t01234")),
+            Some(Lifetime::Ttl(ACTOR_ADDRESS_TTL))
+        );
+        assert_eq!(
+            lifetime("Filecoin.EthGetTransactionHashByCid", &json!("0x1234")),
+            Some(Lifetime::Immutable)
+        );
+        assert_eq!(
+            lifetime("Filecoin.ChainGetBlock", &json!({ "Height": 10 })),
+            Some(Lifetime::Immutable)
+        );
+        // The head moves on.
+        assert_eq!(
+            lifetime("Filecoin.ChainHead", &json!({ "Height": 10 })),
+            None
+        );
+        assert_eq!(
+            lifetime("Filecoin.StateSearchMsg", &json!({ "Height": 10 })),
+            Some(Lifetime::Ttl(SEARCH_MSG_TTL))
+        );
+        // Not found yet.
+        assert_eq!(
+            lifetime("Filecoin.EthGetTransactionHashByCid", &Value::Null),
+            None
+        );
+        assert_eq!(lifetime("Filecoin.MpoolGetNonce", &json!(7)), None);
+        assert!(!is_cacheable("Filecoin.MpoolGetNonce"));
+        assert!(is_cacheable("Filecoin.StateSearchMsg"));
+        assert!(is_cacheable(ERC20_METADATA));
+    }
+
+    #[test]
+    fn test_cache_hits_and_expiry() {
+        let cache = RpcCache::default();
+        let now = Utc::now();
+        let key = cache_key("Filecoin.StateSearchMsg", &[json!({ "/": "bafy" })]);
+        assert_eq!(cache.get(&key, now), None);
+        assert_eq!(
+            cache.put(key.clone(), "Filecoin.StateSearchMsg", &json!({}), now),
+            Some(Lifetime::Ttl(SEARCH_MSG_TTL))
+        );
+        assert_eq!(cache.get(&key, now + Duration::minutes(1)), Some(json!({})));
+        assert_eq!(cache.get(&key, now + SEARCH_MSG_TTL), None);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+        // Other tests may use caches concurrently.
+        let total = total_stats();
+        assert!(total.hits >= 1 && total.misses >= 2);
+
+        // Results of other methods are not cached.
+        let key = cache_key("Filecoin.MpoolGetNonce", &[json!("t01234")]);
+        assert_eq!(
+            cache.put(key.clone(), "Filecoin.MpoolGetNonce", &json!(7), now),
+            None
+        );
+        assert_eq!(cache.get(&key, now), None);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = RpcCache::default();
+        let now = Utc::now();
+        let key = |i: usize| cache_key("Filecoin.StateLookupID", &[json!(format!("t1{i}"))]);
+        for i in 0..CAPACITY.get() {
+            cache.put(key(i), "Filecoin.StateLookupID", &json!(i), now);
+        }
+        // Uses the first entry, so that the second one is evicted instead.
+        assert_eq!(cache.get(&key(0), now), Some(json!(0)));
+        cache.put(
+            key(CAPACITY.get()),
+            "Filecoin.StateLookupID",
+            &json!(0),
+            now,
+        );
+        assert!(cache.get(&key(0), now).is_some());
+        assert!(cache.get(&key(1), now).is_none());
+        assert!(cache.get(&key(CAPACITY.get()), now).is_some());
+    }
+}
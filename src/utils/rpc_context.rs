@@ -1,90 +1,104 @@
 use alloy::primitives::TxHash;
 use alloy::providers::{Provider as AlloyProvider, ProviderBuilder as AlloyProviderBuilder};
-use alloy::sol;
+use alloy::rpc::types::TransactionRequest;
 use anyhow::Context as _;
+use chrono::Utc;
 use cid::Cid;
-use fvm_shared::address::{Address, Network, set_current_network};
+use codee::string::JsonSerdeCodec;
+use fvm_shared::address::{Address, Protocol, set_current_network};
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::message::Message;
 use fvm_shared::sector::StoragePower;
 use leptos::prelude::*;
-use reqwest::Client;
+use leptos_use::storage::{UseStorageOptions, use_local_storage_with_options};
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use reqwest::{Client, RequestBuilder};
 use serde_json::{Value, json};
+use std::marker::PhantomData;
 use std::sync::LazyLock;
+use std::time::Duration;
 use url::Url;
 
+use crate::utils::actor::{ActorInfo, ActorType};
 use crate::utils::address::AddressAlloyExt as _;
 use crate::utils::conversions::TokenAmountAlloyExt as _;
+use crate::utils::custom_endpoint::{CUSTOM_ENDPOINTS_KEY, CustomEndpoint, CustomEndpoints};
+use crate::utils::datacap::DatacapStatus;
 use crate::utils::drip_amount::{DripAmount, TokenType};
+use crate::utils::erc20::{ERC20, Eip1559Fees, Erc20Metadata};
+use crate::utils::error::RpcError;
+use crate::utils::network::{NetworkConfig, RpcEndpoint};
+use crate::utils::rpc_cache::{
+    CacheStats, ERC20_METADATA, RpcCache, cache_key, is_cacheable, total_stats,
+};
 
-use super::lotus_json::{HasLotusJson, LotusJson, signed_message::SignedMessage};
+use super::lotus_json::block_header::BlockHeader;
+use super::lotus_json::block_messages::BlockMessages;
+use super::lotus_json::tipset::Tipset;
+use super::lotus_json::{
+    ActorCodeCids, ActorState, HasLotusJson, InvocResult, LotusJson, MessageLookup,
+    signed_message::SignedMessage,
+};
+use super::provider_pool::ProviderPool;
 
 static CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
 
-pub struct RpcEndpoint {
-    pub label: &'static str,
-    pub url: &'static str,
+/// Margin added to the gas limits estimated by the node, in percent.
+const GAS_LIMIT_MARGIN_PERCENT: u64 = 25;
+
+/// Returns the public endpoints of the network.
+pub fn providers_for(network: &NetworkConfig) -> &[RpcEndpoint] {
+    &network.rpc_endpoints
 }
 
-const CALIBNET_PROVIDERS: &[RpcEndpoint] = &[
-    RpcEndpoint {
-        label: "Glif",
-        url: "https://api.calibration.node.glif.io",
-    },
-    RpcEndpoint {
-        label: "Ankr",
-        url: "https://rpc.ankr.com/filecoin_testnet",
-    },
-    RpcEndpoint {
-        label: "Filfox",
-        url: "https://calibration.filfox.info/rpc/v1",
-    },
-];
-
-const MAINNET_PROVIDERS: &[RpcEndpoint] = &[
-    RpcEndpoint {
-        label: "Glif",
-        url: "https://api.node.glif.io",
-    },
-    RpcEndpoint {
-        label: "Ankr",
-        url: "https://rpc.ankr.com/filecoin",
-    },
-    RpcEndpoint {
-        label: "Filfox",
-        url: "https://filfox.info/rpc/v1",
-    },
-];
-
-pub fn providers_for(network: Network) -> &'static [RpcEndpoint] {
-    match network {
-        Network::Testnet => CALIBNET_PROVIDERS,
-        Network::Mainnet => MAINNET_PROVIDERS,
-    }
+/// Returns the provider of the network, failing over across all of its endpoints.
+pub fn default_provider(network: &NetworkConfig) -> Provider {
+    Provider::from_pool(ProviderPool::new(endpoint_urls(network).collect()))
 }
 
-pub fn default_provider(network: Network) -> Provider {
-    let endpoint = providers_for(network)
-        .first()
-        .expect("each network has at least one provider");
-    Provider::new(endpoint.url.parse().expect("invalid default provider URL"))
+fn endpoint_urls(network: &NetworkConfig) -> impl Iterator<Item = Url> {
+    providers_for(network)
+        .iter()
+        .map(|endpoint| endpoint.url.clone())
 }
 
 #[derive(Clone, Copy)]
 pub struct RpcContext {
-    network: RwSignal<Network>,
+    network: RwSignal<&'static NetworkConfig>,
     provider: RwSignal<Provider>,
+    custom_endpoints: Signal<CustomEndpoints>,
+    set_custom_endpoints: WriteSignal<CustomEndpoints>,
 }
 
 impl RpcContext {
     pub fn new() -> Self {
-        let network = RwSignal::new(Network::Testnet);
-        let provider = RwSignal::new(default_provider(Network::Testnet));
+        let network = RwSignal::new(NetworkConfig::calibnet());
+        let provider = RwSignal::new(default_provider(NetworkConfig::calibnet()));
+        let (custom_endpoints, set_custom_endpoints, _) =
+            use_local_storage_with_options::<CustomEndpoints, JsonSerdeCodec>(
+                CUSTOM_ENDPOINTS_KEY,
+                UseStorageOptions::default().delay_during_hydration(true),
+            );
         Effect::new(move |_| {
-            log::info!("Updating network: {:?}", network.get());
-            set_current_network(network.get());
+            log::info!("Updating network: {}", network.get().name);
+            set_current_network(network.get().prefix);
         });
-        Self { network, provider }
+        // Prefers the custom endpoint of the network, if any, also once it is loaded from local
+        // storage after a reload.
+        Effect::new(move |_| {
+            let network = network.get();
+            if let Some(endpoint) =
+                custom_endpoints.with(|endpoints| endpoints.get(network).cloned())
+            {
+                provider.set(Provider::preferring_custom(endpoint, network));
+            }
+        });
+        Self {
+            network,
+            provider,
+            custom_endpoints,
+            set_custom_endpoints,
+        }
     }
 
     pub fn provide_context() {
@@ -95,7 +109,7 @@ impl RpcContext {
         use_context::<Self>().expect("RpcContext should be provided")
     }
 
-    pub fn network(&self) -> RwSignal<Network> {
+    pub fn network(&self) -> RwSignal<&'static NetworkConfig> {
         self.network
     }
 
@@ -107,30 +121,104 @@ impl RpcContext {
         self.provider.get()
     }
 
-    pub fn set_network(&self, network: Network) {
+    pub fn set_network(&self, network: &'static NetworkConfig) {
         if self.network.get_untracked() != network {
             self.network.set(network);
             self.provider.set(default_provider(network));
         }
     }
 
+    /// Makes `url` the preferred endpoint. The other endpoints of the network remain as
+    /// fallbacks.
     pub fn set_provider_url(&self, url: Url) {
-        self.provider.set(Provider::new(url));
+        let network = self.network.get_untracked();
+        let provider = match self.custom_endpoint_untracked() {
+            Some(endpoint) if endpoint.url == url => Provider::preferring_custom(endpoint, network),
+            _ => Provider::preferring(url, network),
+        };
+        self.provider.set(provider);
+    }
+
+    /// The custom endpoint of the selected network, if the user added one.
+    pub fn custom_endpoint(&self) -> Option<CustomEndpoint> {
+        let network = self.network.get();
+        self.custom_endpoints
+            .with(|endpoints| endpoints.get(network).cloned())
+    }
+
+    fn custom_endpoint_untracked(&self) -> Option<CustomEndpoint> {
+        let network = self.network.get_untracked();
+        self.custom_endpoints
+            .with_untracked(|endpoints| endpoints.get(network).cloned())
+    }
+
+    /// Adds a custom endpoint to the selected network, replacing the previous one, and makes it
+    /// the preferred endpoint. The endpoint should be validated first, see
+    /// [`super::custom_endpoint::validate`].
+    pub fn add_custom_endpoint(&self, endpoint: CustomEndpoint) {
+        let network = self.network.get_untracked();
+        self.set_custom_endpoints
+            .update(|endpoints| endpoints.set(network, Some(endpoint)));
+    }
+
+    /// Removes the custom endpoint of the selected network, going back to the public endpoints.
+    pub fn remove_custom_endpoint(&self) {
+        let network = self.network.get_untracked();
+        self.set_custom_endpoints
+            .update(|endpoints| endpoints.set(network, None));
+        self.provider.set(default_provider(network));
     }
 }
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct Provider {
-    pub url: Url,
+    pool: ProviderPool,
+    /// Endpoint added by the user, if it is one of the endpoints of the pool.
+    custom: Option<CustomEndpoint>,
+    cache: RpcCache,
+}
+
+/// Starts a request to an endpoint, authenticated with its bearer token, if any.
+fn post(url: &Url, timeout: Duration, token: Option<&str>) -> RequestBuilder {
+    let request = CLIENT.post(url.as_ref()).timeout(timeout);
+    match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
 }
 
-async fn invoke_rpc_method<T: HasLotusJson + Clone>(
+/// Converts the error of a contract call, keeping transport errors apart so that the provider
+/// fails over to the next endpoint.
+fn contract_error(err: alloy::contract::Error) -> anyhow::Error {
+    match err {
+        alloy::contract::Error::TransportError(e) => e.into(),
+        e => anyhow::Error::from(e),
+    }
+}
+
+/// Returns an alloy provider of an endpoint, authenticated with its bearer token, if any.
+fn alloy_provider(url: Url, token: Option<&str>) -> anyhow::Result<impl AlloyProvider> {
+    let client = match token {
+        Some(token) => {
+            let mut authorization = HeaderValue::from_str(&format!("Bearer {token}"))?;
+            authorization.set_sensitive(true);
+            Client::builder()
+                .default_headers(HeaderMap::from_iter([(AUTHORIZATION, authorization)]))
+                .build()?
+        }
+        None => CLIENT.clone(),
+    };
+    Ok(AlloyProviderBuilder::new().connect_reqwest(client, url))
+}
+
+async fn invoke_rpc_method(
     url: &Url,
+    timeout: Duration,
+    token: Option<&str>,
     method: &str,
     params: &[Value],
-) -> anyhow::Result<T> {
-    let res = CLIENT
-        .post(url.as_ref())
+) -> anyhow::Result<Value> {
+    let res = post(url, timeout, token)
         .json(&json! {
             {
                 "jsonrpc": "2.0",
@@ -141,31 +229,419 @@ async fn invoke_rpc_method<T: HasLotusJson + Clone>(
         })
         .send()
         .await?;
-    let LotusJson(ret) = serde_json::from_value(
-        res.json::<Value>()
-            .await?
-            .get("result")
-            .ok_or(anyhow::anyhow!("No result"))?
-            .clone(),
-    )?;
+    Ok(parse_response(res.json::<Value>().await?)?)
+}
+
+/// Invokes several JSON-RPC methods as one batch request. Returns the results in the order of
+/// the calls; the node may reject each call on its own.
+async fn invoke_rpc_batch(
+    url: &Url,
+    timeout: Duration,
+    token: Option<&str>,
+    calls: &[(&str, Vec<Value>)],
+) -> anyhow::Result<Vec<Option<Result<Value, RpcError>>>> {
+    let requests = calls
+        .iter()
+        .enumerate()
+        .map(|(id, (method, params))| {
+            json! {
+                {
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": id
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    let res = post(url, timeout, token).json(&requests).send().await?;
+    let responses = match res.json::<Value>().await? {
+        Value::Array(responses) => responses,
+        // Nodes answer a batch they cannot handle with a single error.
+        res => {
+            parse_response(res)?;
+            anyhow::bail!("Invalid batch response");
+        }
+    };
+    let mut results = vec![None; calls.len()];
+    for res in responses {
+        let id = res
+            .get("id")
+            .and_then(Value::as_u64)
+            .and_then(|id| usize::try_from(id).ok())
+            .filter(|id| *id < calls.len())
+            .ok_or(anyhow::anyhow!("Invalid batch response id"))?;
+        results[id] = Some(parse_response(res));
+    }
+    Ok(results)
+}
+
+/// Returns the result of a JSON-RPC response, or the error object if the node rejected the call.
+fn parse_response(mut res: Value) -> Result<Value, RpcError> {
+    match res.get_mut("error") {
+        Some(err) => Err(
+            serde_json::from_value(err.take()).unwrap_or_else(|_| RpcError {
+                code: 0,
+                message: err.to_string(),
+                data: None,
+            }),
+        ),
+        None => Ok(res.get_mut("result").map(Value::take).unwrap_or_default()),
+    }
+}
+
+fn decode_result<T: HasLotusJson + Clone>(result: Value) -> anyhow::Result<T> {
+    if result.is_null() && serde_json::from_value::<LotusJson<T>>(Value::Null).is_err() {
+        anyhow::bail!("No result");
+    }
+    let LotusJson(ret) = serde_json::from_value(result)?;
     Ok(ret)
 }
 
+/// A JSON-RPC call whose result is decoded as `T`, either invoked on its own or as part of an
+/// [`RpcBatch`].
+pub struct RpcCall<T> {
+    method: &'static str,
+    params: Vec<Value>,
+    result: PhantomData<fn() -> T>,
+}
+
+impl<T> RpcCall<T> {
+    fn new(method: &'static str, params: Vec<Value>) -> Self {
+        Self {
+            method,
+            params,
+            result: PhantomData,
+        }
+    }
+}
+
+impl RpcCall<TokenAmount> {
+    /// Returns the balance of a wallet address in native Filecoin token.
+    pub fn wallet_balance(wallet_address: Address) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.WalletBalance",
+            vec![serde_json::to_value(LotusJson(wallet_address))?],
+        ))
+    }
+}
+
+impl RpcCall<Option<StoragePower>> {
+    /// Returns the remaining allowance of a verifier address, or `None` if it is not a verifier.
+    pub fn verifier_status(verifier_address: Address) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.StateVerifierStatus",
+            vec![
+                serde_json::to_value(LotusJson(verifier_address))?,
+                Value::Null,
+            ],
+        ))
+    }
+
+    /// Returns the remaining datacap of a verified client address, or `None` if it is not a
+    /// verified client.
+    pub fn verified_client_status(verified_client_address: Address) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.StateVerifiedClientStatus",
+            vec![
+                serde_json::to_value(LotusJson(verified_client_address))?,
+                Value::Null,
+            ],
+        ))
+    }
+}
+
+impl RpcCall<Message> {
+    /// Returns the message with the given CID, see [`Provider::chain_get_message`].
+    pub fn chain_get_message(cid: Cid) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.ChainGetMessage",
+            vec![serde_json::to_value(LotusJson(cid))?],
+        ))
+    }
+
+    pub fn estimate_gas(msg: Message) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.GasEstimateMessageGas",
+            vec![
+                serde_json::to_value(LotusJson(msg))?,
+                Value::Null,
+                Value::Null,
+            ],
+        ))
+    }
+}
+
+impl RpcCall<u64> {
+    pub fn mpool_get_nonce(addr: Address) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.MpoolGetNonce",
+            vec![serde_json::to_value(LotusJson(addr))?],
+        ))
+    }
+}
+
+impl RpcCall<u64> {
+    pub fn network_version() -> Self {
+        Self::new("Filecoin.StateNetworkVersion", vec![Value::Null])
+    }
+}
+
+impl RpcCall<Address> {
+    /// Looks up the ID address of a given Filecoin address.
+    pub fn lookup_id(addr: Address) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.StateLookupID",
+            vec![serde_json::to_value(LotusJson(addr))?, Value::Null],
+        ))
+    }
+
+    /// Resolves an address to the robust address of its actor, see [`Provider::account_key`].
+    pub fn account_key(addr: Address) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.StateAccountKey",
+            vec![serde_json::to_value(LotusJson(addr))?, Value::Null],
+        ))
+    }
+}
+
+impl RpcCall<BlockHeader> {
+    /// Returns the header of the block with the given CID, see [`Provider::chain_get_block`].
+    pub fn chain_get_block(cid: Cid) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.ChainGetBlock",
+            vec![serde_json::to_value(LotusJson(cid))?],
+        ))
+    }
+}
+
+impl RpcCall<ActorState> {
+    /// Returns the state of the actor of an address. The call fails if the actor does not exist.
+    pub fn state_get_actor(addr: Address) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            "Filecoin.StateGetActor",
+            vec![serde_json::to_value(LotusJson(addr))?, Value::Null],
+        ))
+    }
+}
+
+/// Calls sent to the node as one JSON-RPC batch request, saving the round-trips of separate
+/// requests. See [`Provider::batch`].
+#[derive(Default)]
+pub struct RpcBatch {
+    calls: Vec<(&'static str, Vec<Value>)>,
+}
+
+/// Handle on the result of a call added to an [`RpcBatch`].
+#[must_use]
+pub struct BatchSlot<T> {
+    index: usize,
+    result: PhantomData<fn() -> T>,
+}
+
+impl RpcBatch {
+    /// Adds a call to the batch. Its result is taken from the [`BatchResults`] of the batch.
+    pub fn add<T>(&mut self, call: RpcCall<T>) -> BatchSlot<T> {
+        self.calls.push((call.method, call.params));
+        BatchSlot {
+            index: self.calls.len() - 1,
+            result: PhantomData,
+        }
+    }
+}
+
+/// Results of the calls of an [`RpcBatch`].
+pub struct BatchResults {
+    results: Vec<Option<Result<Value, RpcError>>>,
+}
+
+impl BatchResults {
+    /// Takes the result of a call, decoded through its lotus JSON representation.
+    pub fn take<T: HasLotusJson + Clone>(&mut self, slot: BatchSlot<T>) -> anyhow::Result<T> {
+        let result = self
+            .results
+            .get_mut(slot.index)
+            .and_then(Option::take)
+            .ok_or(anyhow::anyhow!("No result"))?;
+        decode_result(result?)
+    }
+}
+
 impl Provider {
-    pub fn new(url: Url) -> Self {
-        Self { url }
+    pub fn from_pool(pool: ProviderPool) -> Self {
+        Self {
+            pool,
+            custom: None,
+            cache: RpcCache::default(),
+        }
     }
 
-    pub fn default_for(network: Network) -> Self {
+    /// Sets the endpoint added by the user. Its bearer token is sent along with the requests to
+    /// its URL only.
+    pub fn with_custom_endpoint(mut self, endpoint: CustomEndpoint) -> Self {
+        self.custom = Some(endpoint);
+        self
+    }
+
+    pub fn default_for(network: &NetworkConfig) -> Self {
         default_provider(network)
     }
 
+    /// Creates a provider preferring a custom endpoint, failing over to the public endpoints of
+    /// the network.
+    pub fn preferring_custom(endpoint: CustomEndpoint, network: &NetworkConfig) -> Self {
+        Self::preferring(endpoint.url.clone(), network).with_custom_endpoint(endpoint)
+    }
+
+    /// Creates a provider preferring `url`, failing over to the other endpoints of the network.
+    pub fn preferring(url: Url, network: &NetworkConfig) -> Self {
+        let others = endpoint_urls(network).filter(|other| *other != url);
+        Self::from_pool(ProviderPool::new(
+            std::iter::once(url.clone()).chain(others).collect(),
+        ))
+    }
+
+    /// The preferred endpoint of the provider.
+    pub fn url(&self) -> &Url {
+        self.pool.primary()
+    }
+
+    /// Returns the bearer token of an endpoint of the provider, if it requires one.
+    fn token_for(&self, url: &Url) -> Option<&str> {
+        self.custom
+            .as_ref()
+            .filter(|custom| custom.url == *url)
+            .and_then(|custom| custom.token.as_deref())
+    }
+
+    /// Hits and misses of the cache of the provider, see [`super::rpc_cache`].
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Returns the cached result of a call, if any.
+    async fn cached(&self, key: &str) -> Option<Value> {
+        let result = self.cache.get(key, Utc::now());
+        #[cfg(all(feature = "ssr", not(test)))]
+        let result = match result {
+            Some(result) => Some(result),
+            None => super::rpc_cache::worker_cache::get(self.url(), key).await,
+        };
+        log::debug!(
+            "RPC cache of {} ({}; {} in total)",
+            self.url(),
+            self.cache_stats(),
+            total_stats()
+        );
+        result
+    }
+
+    /// Caches the result of a call, if its method allows it.
+    async fn cache(&self, key: String, method: &str, result: &Value) {
+        #[cfg(all(feature = "ssr", not(test)))]
+        let worker_key = key.clone();
+        let lifetime = self.cache.put(key, method, result, Utc::now());
+        #[cfg(all(feature = "ssr", not(test)))]
+        if let Some(lifetime) = lifetime {
+            super::rpc_cache::worker_cache::put(self.url(), &worker_key, result, lifetime).await;
+        }
+        #[cfg(not(all(feature = "ssr", not(test))))]
+        let _ = lifetime;
+    }
+
+    /// Invokes a JSON-RPC method, failing over across the endpoints of the provider. Results that
+    /// cannot change are served from the cache.
+    async fn invoke<T: HasLotusJson + Clone>(
+        &self,
+        method: &str,
+        params: &[Value],
+    ) -> anyhow::Result<T> {
+        let key = is_cacheable(method).then(|| cache_key(method, params));
+        if let Some(key) = &key
+            && let Some(result) = self.cached(key).await
+        {
+            return decode_result(result);
+        }
+        let timeout = self.pool.timeout();
+        let result = self
+            .pool
+            .call(|url| async move {
+                invoke_rpc_method(&url, timeout, self.token_for(&url), method, params).await
+            })
+            .await?;
+        if let Some(key) = key {
+            self.cache(key, method, &result).await;
+        }
+        decode_result(result)
+    }
+
+    /// Invokes a typed JSON-RPC call, failing over across the endpoints of the provider.
+    pub async fn call<T: HasLotusJson + Clone>(&self, call: RpcCall<T>) -> anyhow::Result<T> {
+        self.invoke(call.method, &call.params).await
+    }
+
+    /// Sends the calls of a batch as one JSON-RPC request, failing over across the endpoints of
+    /// the provider. Results that cannot change are served from the cache, and only the other
+    /// calls are sent.
+    pub async fn batch(&self, batch: &RpcBatch) -> anyhow::Result<BatchResults> {
+        let mut results = vec![None; batch.calls.len()];
+        let mut keys = vec![None; batch.calls.len()];
+        for (i, (method, params)) in batch.calls.iter().enumerate() {
+            if !is_cacheable(method) {
+                continue;
+            }
+            let key = cache_key(method, params);
+            match self.cached(&key).await {
+                Some(result) => results[i] = Some(Ok(result)),
+                None => keys[i] = Some(key),
+            }
+        }
+        let uncached = (0..batch.calls.len())
+            .filter(|i| results[*i].is_none())
+            .collect::<Vec<_>>();
+        if uncached.is_empty() {
+            return Ok(BatchResults { results });
+        }
+        let calls = uncached
+            .iter()
+            .map(|i| batch.calls[*i].clone())
+            .collect::<Vec<_>>();
+        let timeout = self.pool.timeout();
+        let fetched = self
+            .pool
+            .call(|url| {
+                let calls = &calls;
+                async move { invoke_rpc_batch(&url, timeout, self.token_for(&url), calls).await }
+            })
+            .await?;
+        for (i, result) in uncached.into_iter().zip(fetched) {
+            if let (Some(key), Some(Ok(result))) = (keys[i].take(), &result) {
+                self.cache(key, batch.calls[i].0, result).await;
+            }
+            results[i] = result;
+        }
+        Ok(BatchResults { results })
+    }
+
+    /// Subscribes to the chain head over the WebSocket transport of the preferred endpoint, see
+    /// [`super::head_subscription`].
+    #[cfg(feature = "hydrate")]
+    pub async fn subscribe_heads(&self) -> anyhow::Result<impl futures::Stream<Item = ()>> {
+        super::head_subscription::subscribe_heads(self.url(), self.token_for(self.url())).await
+    }
+
     pub async fn network_name(&self) -> anyhow::Result<String> {
-        invoke_rpc_method(&self.url, "Filecoin.StateNetworkName", &[]).await
+        self.invoke("Filecoin.StateNetworkName", &[]).await
+    }
+
+    /// Returns the Ethereum chain ID of the network of the provider.
+    pub async fn chain_id(&self) -> anyhow::Result<u64> {
+        let chain_id: String = self.invoke("Filecoin.EthChainId", &[]).await?;
+        Ok(u64::from_str_radix(chain_id.trim_start_matches("0x"), 16)?)
     }
 
     pub async fn network_version(&self) -> anyhow::Result<u64> {
-        invoke_rpc_method(&self.url, "Filecoin.StateNetworkVersion", &[Value::Null]).await
+        self.call(RpcCall::network_version()).await
     }
 
     /// Returns the balance of a wallet address in the specified token type.
@@ -185,27 +661,76 @@ impl Provider {
                     .await?;
                 Ok(DripAmount::Token(balance))
             }
+            TokenType::Datacap => self
+                .wallet_balances(&[wallet_address], token_type)
+                .await?
+                .pop()
+                .context("No result")?,
+        }
+    }
+
+    /// Returns the balances of several wallet addresses in the specified token type. Balances of
+    /// native tokens and datacap are fetched in one JSON-RPC batch; the balance of each address
+    /// may fail on its own.
+    pub async fn wallet_balances(
+        &self,
+        wallet_addresses: &[Address],
+        token_type: &TokenType,
+    ) -> anyhow::Result<Vec<anyhow::Result<DripAmount>>> {
+        let mut batch = RpcBatch::default();
+        match token_type {
+            TokenType::Native => {
+                let slots = wallet_addresses
+                    .iter()
+                    .map(|address| Ok(batch.add(RpcCall::wallet_balance(*address)?)))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let mut results = self.batch(&batch).await?;
+                Ok(slots
+                    .into_iter()
+                    .map(|slot| results.take(slot).map(DripAmount::Token))
+                    .collect())
+            }
+            TokenType::Erc20(contract_address) => {
+                let mut balances = Vec::with_capacity(wallet_addresses.len());
+                for address in wallet_addresses {
+                    balances.push(
+                        self.wallet_balance_erc20(*address, *contract_address)
+                            .await
+                            .map(DripAmount::Token),
+                    );
+                }
+                Ok(balances)
+            }
             TokenType::Datacap => {
-                let balance = match self.wallet_balance_verifier_datacap(wallet_address).await {
-                    Ok(val) => val,
-                    Err(_) => {
-                        self.wallet_balance_verified_client_datacap(wallet_address)
-                            .await?
-                    }
-                };
-                Ok(DripAmount::Storage(balance))
+                let slots = wallet_addresses
+                    .iter()
+                    .map(|address| {
+                        Ok((
+                            batch.add(RpcCall::verifier_status(*address)?),
+                            batch.add(RpcCall::verified_client_status(*address)?),
+                        ))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let mut results = self.batch(&batch).await?;
+                Ok(slots
+                    .into_iter()
+                    .map(|(verifier, verified_client)| {
+                        // Verifiers hold an allowance and verified clients hold datacap. Other
+                        // addresses hold none.
+                        let verifier = results.take(verifier)?;
+                        let verified_client = results.take(verified_client)?;
+                        Ok(DripAmount::Storage(
+                            verifier.or(verified_client).unwrap_or_default(),
+                        ))
+                    })
+                    .collect())
             }
         }
     }
 
     /// Returns the balance of a wallet address in native Filecoin token.
     async fn wallet_balance_native(&self, wallet_address: Address) -> anyhow::Result<TokenAmount> {
-        invoke_rpc_method(
-            &self.url,
-            "Filecoin.WalletBalance",
-            &[serde_json::to_value(LotusJson(wallet_address))?],
-        )
-        .await
+        self.call(RpcCall::wallet_balance(wallet_address)?).await
     }
 
     /// Returns the balance of a wallet address in an ERC-20 token.
@@ -214,112 +739,197 @@ impl Provider {
         wallet_address: Address,
         contract_address: alloy::primitives::Address,
     ) -> anyhow::Result<TokenAmount> {
-        sol! {
-           #[sol(rpc)]
-           contract ERC20 {
-                function balanceOf(address owner) public view returns (uint256);
-           }
-        }
-
+        let decimals = self.erc20_metadata(contract_address).await?.decimals;
         let eth_address = wallet_address.into_eth_address()?;
-        let provider = AlloyProviderBuilder::new().connect_http(self.url.clone());
-        let erc20 = ERC20::new(contract_address, provider);
-
-        let balance = erc20.balanceOf(eth_address).call().await?;
-        Ok(TokenAmount::from_alloy_amount(&balance))
+        let balance = self
+            .pool
+            .call(|url| async move {
+                let provider = alloy_provider(url.clone(), self.token_for(&url))?;
+                let erc20 = ERC20::new(contract_address, provider);
+                erc20
+                    .balanceOf(eth_address)
+                    .call()
+                    .await
+                    .map_err(contract_error)
+            })
+            .await?;
+        Ok(TokenAmount::from_alloy_amount(&balance, decimals))
     }
 
-    /// Returns the remaining storage power of a verifier address.
-    async fn wallet_balance_verifier_datacap(
+    /// Returns the name, symbol and decimals of an ERC-20 token, read from its contract. They
+    /// never change, so they are served from the cache once read.
+    pub async fn erc20_metadata(
         &self,
-        verifier_address: Address,
-    ) -> anyhow::Result<StoragePower> {
-        invoke_rpc_method(
-            &self.url,
-            "Filecoin.StateVerifierStatus",
-            &[
-                serde_json::to_value(LotusJson(verifier_address))?,
-                Value::Null,
-            ],
-        )
-        .await
+        contract_address: alloy::primitives::Address,
+    ) -> anyhow::Result<Erc20Metadata> {
+        let key = cache_key(ERC20_METADATA, &[json!(contract_address)]);
+        if let Some(metadata) = self.cached(&key).await {
+            return Ok(serde_json::from_value(metadata)?);
+        }
+        let metadata = self
+            .pool
+            .call(|url| async move {
+                let provider = alloy_provider(url.clone(), self.token_for(&url))?;
+                let erc20 = ERC20::new(contract_address, provider);
+                let (name, symbol, decimals) = (erc20.name(), erc20.symbol(), erc20.decimals());
+                let (name, symbol, decimals) = futures::try_join!(
+                    name.call().into_future(),
+                    symbol.call().into_future(),
+                    decimals.call().into_future()
+                )
+                .map_err(contract_error)?;
+                Ok(Erc20Metadata {
+                    name,
+                    symbol,
+                    decimals,
+                })
+            })
+            .await?;
+        self.cache(key, ERC20_METADATA, &serde_json::to_value(&metadata)?)
+            .await;
+        Ok(metadata)
     }
 
-    /// Returns the remaining storage power of a verified client address.
-    async fn wallet_balance_verified_client_datacap(
+    /// Returns the datacap status of a verifier and of the recipient of an allocation, fetched in
+    /// one JSON-RPC batch. See [`DatacapStatus::check_allocation`].
+    pub async fn datacap_status(
         &self,
-        verified_client_address: Address,
-    ) -> anyhow::Result<StoragePower> {
-        invoke_rpc_method(
-            &self.url,
-            "Filecoin.StateVerifiedClientStatus",
-            &[
-                serde_json::to_value(LotusJson(verified_client_address))?,
-                Value::Null,
-            ],
-        )
-        .await
+        verifier: Address,
+        recipient: Address,
+    ) -> anyhow::Result<DatacapStatus> {
+        let mut batch = RpcBatch::default();
+        let verifier_id = batch.add(RpcCall::lookup_id(verifier)?);
+        let recipient_id = batch.add(RpcCall::lookup_id(recipient)?);
+        let verifier_allowance = batch.add(RpcCall::verifier_status(verifier)?);
+        let recipient_verifier_allowance = batch.add(RpcCall::verifier_status(recipient)?);
+        let recipient_datacap = batch.add(RpcCall::verified_client_status(recipient)?);
+        let mut results = self.batch(&batch).await?;
+        let verifier_id = results.take(verifier_id)?;
+        let verifier_allowance = results.take(verifier_allowance)?;
+        // A recipient without an actor is neither a verifier nor a verified client yet.
+        let Ok(recipient_id) = results.take(recipient_id) else {
+            return Ok(DatacapStatus {
+                verifier_allowance,
+                ..Default::default()
+            });
+        };
+        Ok(DatacapStatus {
+            verifier_allowance,
+            recipient_is_verifier_itself: recipient_id == verifier_id,
+            recipient_verifier_allowance: results.take(recipient_verifier_allowance)?,
+            recipient_datacap: results.take(recipient_datacap)?,
+        })
     }
 
     pub async fn send_eth_transaction_signed(&self, signed_tx: &[u8]) -> anyhow::Result<TxHash> {
-        let provider = AlloyProviderBuilder::new().connect_http(self.url.clone());
-        Ok(provider
-            .send_raw_transaction(signed_tx)
-            .await?
-            .tx_hash()
-            .to_owned())
+        self.pool
+            .call(|url| async move {
+                let provider = alloy_provider(url.clone(), self.token_for(&url))?;
+                Ok(provider
+                    .send_raw_transaction(signed_tx)
+                    .await?
+                    .tx_hash()
+                    .to_owned())
+            })
+            .await
     }
 
-    pub async fn estimate_gas(&self, msg: Message) -> anyhow::Result<Message> {
-        invoke_rpc_method(
-            &self.url,
-            "Filecoin.GasEstimateMessageGas",
-            &[
-                serde_json::to_value(LotusJson(msg))?,
-                Value::Null,
-                Value::Null,
-            ],
-        )
-        .await
+    pub async fn mpool_get_nonce(&self, addr: Address) -> anyhow::Result<u64> {
+        self.call(RpcCall::mpool_get_nonce(addr)?).await
     }
 
-    pub async fn mpool_get_nonce(&self, addr: Address) -> anyhow::Result<u64> {
-        invoke_rpc_method(
-            &self.url,
-            "Filecoin.MpoolGetNonce",
-            &[serde_json::to_value(LotusJson(addr))?],
-        )
-        .await
+    /// Prepares a message in one batch request: looks up the ID address of its recipient, the
+    /// nonce of its sender and estimates its gas. Gas is estimated for the message as given, which
+    /// costs no less than the message to the ID address. The ID address falls back to the
+    /// recipient if it has no actor yet.
+    pub async fn prepare_message(&self, msg: Message) -> anyhow::Result<(Address, u64, Message)> {
+        let recipient = msg.to;
+        let mut batch = RpcBatch::default();
+        let id_address = batch.add(RpcCall::lookup_id(recipient)?);
+        let nonce = batch.add(RpcCall::mpool_get_nonce(msg.from)?);
+        let msg = batch.add(RpcCall::estimate_gas(msg)?);
+        let mut results = self.batch(&batch).await?;
+        let id_address = results.take(id_address).unwrap_or_else(|_| {
+            log::debug!("ID lookup failed, using recipient address: {recipient}");
+            recipient
+        });
+        Ok((id_address, results.take(nonce)?, results.take(msg)?))
     }
 
-    /// Looks up the ID address of a given Filecoin address.
-    /// This method makes an RPC call to the Filecoin node to convert a Filecoin address
-    /// to its corresponding ID address in the current state tree.
-    pub async fn lookup_id(&self, addr: Address) -> anyhow::Result<Address> {
-        invoke_rpc_method(
-            &self.url,
-            "Filecoin.StateLookupID",
-            &[serde_json::to_value(LotusJson(addr))?, Value::Null],
-        )
-        .await
+    /// Resolves an address to the robust address of its actor, i.e., the public key address of an
+    /// account or the delegated address of an actor created through the EAM.
+    pub async fn account_key(&self, addr: Address) -> anyhow::Result<Address> {
+        self.call(RpcCall::account_key(addr)?).await
     }
 
-    /// Returns the current gas price in attoFIL.
-    ///
-    /// Internally, it prunes the result from `u128` to `u64` but it should be safe as we don't
-    /// expect the gas price to exceed 1 FIL (`1e18` attoFIL) in the foreseeable future.
-    pub async fn gas_price(&self) -> anyhow::Result<u64> {
-        let provider = AlloyProviderBuilder::new().connect_http(self.url.clone());
-        provider
-            .get_gas_price()
+    /// Returns the actor of an address, its type and the equivalent forms of its address. The
+    /// actor, its ID and its robust address are fetched in one JSON-RPC batch, the code CIDs of
+    /// the builtin actors once the network version is known.
+    pub async fn actor_info(&self, address: Address) -> anyhow::Result<ActorInfo> {
+        let mut batch = RpcBatch::default();
+        let state = batch.add(RpcCall::state_get_actor(address)?);
+        let id = batch.add(RpcCall::lookup_id(address)?);
+        let robust = batch.add(RpcCall::account_key(address)?);
+        let network_version = batch.add(RpcCall::network_version());
+        let mut results = self.batch(&batch).await?;
+        // Addresses without an actor are valid, they only hold nothing yet. Actors without a
+        // robust address, e.g., miners, fail the account key lookup.
+        let state = results.take(state).ok();
+        let id = match address.protocol() {
+            Protocol::ID => Some(address),
+            _ => results.take(id).ok(),
+        };
+        let robust = match address.protocol() {
+            Protocol::ID => results
+                .take(robust)
+                .ok()
+                .or(state.as_ref().and_then(|state| state.delegated_address)),
+            _ => Some(address),
+        };
+        let actor_type = match &state {
+            Some(state) => {
+                let network_version = results.take(network_version)?;
+                let code_cids: ActorCodeCids = self
+                    .invoke("Filecoin.StateActorCodeCIDs", &[json!(network_version)])
+                    .await?;
+                ActorType::resolve(&code_cids, &state.code)
+            }
+            None => None,
+        };
+        Ok(ActorInfo {
+            id,
+            robust,
+            state,
+            actor_type,
+        })
+    }
+
+    /// Estimates the gas limit and the EIP-1559 fees of an Ethereum transaction. The estimated gas
+    /// limit is raised by [`GAS_LIMIT_MARGIN_PERCENT`], as the gas used may change by the time the
+    /// transaction is included.
+    pub async fn estimate_eth_fees(&self, tx: &TransactionRequest) -> anyhow::Result<Eip1559Fees> {
+        self.pool
+            .call(|url| async move {
+                let provider = alloy_provider(url.clone(), self.token_for(&url))?;
+                let gas_limit = provider
+                    .estimate_gas(tx.clone())
+                    .await
+                    .context("Failed to estimate gas")?;
+                let fees = provider
+                    .estimate_eip1559_fees()
+                    .await
+                    .context("Failed to estimate fees")?;
+                Ok(Eip1559Fees {
+                    gas_limit: gas_limit.saturating_mul(100 + GAS_LIMIT_MARGIN_PERCENT) / 100,
+                    max_fee_per_gas: TokenAmount::from_atto(fees.max_fee_per_gas),
+                    max_priority_fee_per_gas: TokenAmount::from_atto(fees.max_priority_fee_per_gas),
+                })
+            })
             .await
-            .map(|price| price as u64)
-            .context("Failed to get gas price")
     }
 
     pub async fn mpool_push(&self, smsg: SignedMessage) -> anyhow::Result<Cid> {
-        invoke_rpc_method(
-            &self.url,
+        self.invoke(
             "Filecoin.MpoolPush",
             &[serde_json::to_value(LotusJson(smsg))?],
         )
@@ -330,72 +940,491 @@ impl Provider {
         &self,
         cid: Cid,
     ) -> anyhow::Result<Option<TxHash>> {
-        invoke_rpc_method::<Option<TxHash>>(
-            &self.url,
+        self.invoke::<Option<TxHash>>(
             "Filecoin.EthGetTransactionHashByCid",
             &[serde_json::to_value(LotusJson(cid))?],
         )
         .await
     }
 
+    /// Returns the CID of the message of an Ethereum transaction, `None` if the node does not
+    /// know the transaction.
+    pub async fn eth_get_message_cid_by_transaction_hash(
+        &self,
+        tx_hash: TxHash,
+    ) -> anyhow::Result<Option<Cid>> {
+        self.invoke::<Option<Cid>>(
+            "Filecoin.EthGetMessageCidByTransactionHash",
+            &[json!(tx_hash)],
+        )
+        .await
+    }
+
+    /// Searches the chain for the execution of a message, looking back at most `lookback_limit`
+    /// epochs from the head, or down to genesis if `None`.
     pub async fn state_search_msg(
         &self,
         msg: Cid,
-    ) -> anyhow::Result<Option<crate::utils::lotus_json::MessageLookup>> {
-        invoke_rpc_method(
-            &self.url,
+        lookback_limit: Option<i64>,
+    ) -> anyhow::Result<Option<MessageLookup>> {
+        self.invoke(
             "Filecoin.StateSearchMsg",
             &[
                 Value::Null,
                 serde_json::to_value(LotusJson(msg))?,
-                Value::Number(10.into()),
+                // The node takes -1 as no limit.
+                lookback_limit.unwrap_or(-1).into(),
                 Value::Bool(false),
             ],
         )
         .await
     }
 
+    /// Returns the message with the given CID, signed or not.
+    pub async fn chain_get_message(&self, cid: Cid) -> anyhow::Result<Message> {
+        self.call(RpcCall::chain_get_message(cid)?).await
+    }
+
+    /// Replays a message found on chain in the tipset it was included in.
+    pub async fn state_replay(&self, cid: Cid) -> anyhow::Result<InvocResult> {
+        self.invoke(
+            "Filecoin.StateReplay",
+            &[Value::Null, serde_json::to_value(LotusJson(cid))?],
+        )
+        .await
+    }
+
+    /// Returns the tipset at the head of the chain.
+    pub async fn chain_head(&self) -> anyhow::Result<Tipset> {
+        self.invoke("Filecoin.ChainHead", &[]).await
+    }
+
+    /// Returns the tipset at the given height of the canonical chain. Null rounds have no tipset,
+    /// so the node returns the first tipset below the height instead.
+    pub async fn chain_get_tipset_by_height(&self, height: i64) -> anyhow::Result<Tipset> {
+        self.invoke(
+            "Filecoin.ChainGetTipSetByHeight",
+            &[height.into(), Value::Null],
+        )
+        .await
+    }
+
+    /// Returns the header of the block with the given CID.
+    pub async fn chain_get_block(&self, cid: Cid) -> anyhow::Result<BlockHeader> {
+        self.call(RpcCall::chain_get_block(cid)?).await
+    }
+
+    /// Returns the messages included in the block with the given CID.
+    pub async fn chain_get_block_messages(&self, cid: Cid) -> anyhow::Result<BlockMessages> {
+        self.invoke(
+            "Filecoin.ChainGetBlockMessages",
+            &[serde_json::to_value(LotusJson(cid))?],
+        )
+        .await
+    }
+
     /// Checks if an Ethereum transaction is confirmed by checking if it is included in any block.
     pub async fn check_eth_transaction_confirmed(&self, tx_hash: TxHash) -> anyhow::Result<bool> {
-        let provider = AlloyProviderBuilder::new().connect_http(self.url.clone());
-        match provider.get_transaction_receipt(tx_hash).await? {
-            Some(receipt) => Ok(receipt.block_number.is_some() && receipt.status()),
-            None => Ok(false),
-        }
+        self.pool
+            .call(|url| async move {
+                let provider = alloy_provider(url.clone(), self.token_for(&url))?;
+                match provider.get_transaction_receipt(tx_hash).await? {
+                    Some(receipt) => Ok(receipt.block_number.is_some() && receipt.status()),
+                    None => Ok(false),
+                }
+            })
+            .await
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::utils::provider_pool::health;
+    use fvm_shared::error::ExitCode;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+    use tokio::net::{TcpListener, TcpStream};
 
     #[test]
-    fn test_providers_for_network_testnet() {
-        let providers = providers_for(Network::Testnet);
-        assert_eq!(providers.len(), CALIBNET_PROVIDERS.len());
-        for (actual, expected) in providers.iter().zip(CALIBNET_PROVIDERS) {
-            assert_eq!(actual.label, expected.label);
-            assert_eq!(actual.url, expected.url);
-        }
+    fn test_providers_for_network() {
+        let calibnet = providers_for(NetworkConfig::calibnet());
+        let mainnet = providers_for(NetworkConfig::mainnet());
+        assert_eq!(calibnet.len(), 3);
+        assert_eq!(calibnet[0].label, "Glif");
+        assert_eq!(
+            calibnet[0].url.as_str(),
+            "https://api.calibration.node.glif.io/"
+        );
+        assert_eq!(mainnet[0].url.as_str(), "https://api.node.glif.io/");
     }
 
     #[test]
-    fn test_providers_for_network_mainnet() {
-        let providers = providers_for(Network::Mainnet);
-        assert_eq!(providers.len(), MAINNET_PROVIDERS.len());
-        for (actual, expected) in providers.iter().zip(MAINNET_PROVIDERS) {
-            assert_eq!(actual.label, expected.label);
-            assert_eq!(actual.url, expected.url);
+    fn test_default_provider_per_network() {
+        let calibnet = NetworkConfig::calibnet();
+        let testnet = default_provider(calibnet);
+        let mainnet = default_provider(NetworkConfig::mainnet());
+
+        assert_eq!(testnet.url(), &calibnet.rpc_endpoints[0].url);
+        assert_eq!(
+            mainnet.url(),
+            &NetworkConfig::mainnet().rpc_endpoints[0].url
+        );
+        assert_ne!(testnet.url(), mainnet.url());
+        assert_eq!(testnet.pool.ranked().len(), calibnet.rpc_endpoints.len());
+    }
+
+    /// Behaviour of a mock JSON-RPC endpoint.
+    #[derive(Clone)]
+    pub(crate) enum MockEndpoint {
+        /// Answers with the given result.
+        Result(Value),
+        /// Answers with a JSON-RPC error, as a node rejecting the call does.
+        Error,
+        /// Answers with the given JSON body, e.g., the responses of a batch.
+        Body(Value),
+        /// Answers with something else than JSON, as a failing gateway does.
+        Garbage,
+        /// Never answers.
+        Hang,
+    }
+
+    /// Serves a mock JSON-RPC endpoint on a local port. Returns its URL and a counter of the
+    /// requests it received.
+    pub(crate) async fn serve(endpoint: MockEndpoint) -> (Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/rpc/v1", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                let endpoint = endpoint.clone();
+                tokio::spawn(async move {
+                    read_request(&mut stream).await;
+                    let (content_type, body) = match endpoint {
+                        MockEndpoint::Result(result) => (
+                            "application/json",
+                            json!({ "jsonrpc": "2.0", "id": 0, "result": result }).to_string(),
+                        ),
+                        MockEndpoint::Error => (
+                            "application/json",
+                            json!({
+                                "jsonrpc": "2.0",
+                                "id": 0,
+                                "error": { "code": 1, "message": "actor not found" }
+                            })
+                            .to_string(),
+                        ),
+                        MockEndpoint::Body(body) => ("application/json", body.to_string()),
+                        MockEndpoint::Garbage => ("text/html", "<html>502</html>".to_string()),
+                        MockEndpoint::Hang => {
+                            tokio::time::sleep(Duration::from_secs(60)).await;
+                            return;
+                        }
+                    };
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+        (url, requests)
+    }
+
+    /// Reads an HTTP request up to the end of its body.
+    async fn read_request(stream: &mut TcpStream) {
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            if n == 0 {
+                return;
+            }
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                let content_length = headers
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if body.len() >= content_length {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns the URL of a local port nothing listens on.
+    pub(crate) async fn unreachable() -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}/rpc/v1", listener.local_addr().unwrap())
+            .parse()
+            .unwrap()
+    }
+
+    pub(crate) fn provider(urls: Vec<Url>) -> Provider {
+        Provider::from_pool(ProviderPool::new(urls).with_timeout(Duration::from_millis(500)))
+    }
+
+    #[tokio::test]
+    async fn test_failover_to_next_endpoint() {
+        let down = unreachable().await;
+        let (garbage, _) = serve(MockEndpoint::Garbage).await;
+        let (healthy, _) = serve(MockEndpoint::Result(json!("calibrationnet"))).await;
+        let provider = provider(vec![down.clone(), garbage.clone(), healthy.clone()]);
+
+        assert_eq!(provider.network_name().await.unwrap(), "calibrationnet");
+        assert!(health(&down).error_rate > 0.0);
+        assert!(health(&garbage).error_rate > 0.0);
+        assert_eq!(health(&healthy).error_rate, 0.0);
+        assert!(health(&healthy).latency_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failover_on_timeout() {
+        let (hanging, _) = serve(MockEndpoint::Hang).await;
+        let (healthy, _) = serve(MockEndpoint::Result(json!(25))).await;
+        let provider = provider(vec![hanging.clone(), healthy]);
+
+        assert_eq!(provider.network_version().await.unwrap(), 25);
+        assert!(health(&hanging).error_rate > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_no_failover_on_node_error() {
+        let (rejecting, _) = serve(MockEndpoint::Error).await;
+        let (healthy, healthy_requests) = serve(MockEndpoint::Result(json!(25))).await;
+        let provider = provider(vec![rejecting.clone(), healthy]);
+
+        let err = provider.network_version().await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RpcError>(),
+            Some(&RpcError {
+                code: 1,
+                message: "actor not found".to_string(),
+                data: None,
+            })
+        );
+        assert_eq!(healthy_requests.load(Ordering::SeqCst), 0);
+        assert_eq!(health(&rejecting).error_rate, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_endpoint_is_skipped() {
+        let (garbage, garbage_requests) = serve(MockEndpoint::Garbage).await;
+        let (healthy, healthy_requests) = serve(MockEndpoint::Result(json!(25))).await;
+        let provider = provider(vec![garbage, healthy]);
+
+        for _ in 0..4 {
+            assert_eq!(
+                provider
+                    .mpool_get_nonce(Address::new_id(1234))
+                    .await
+                    .unwrap(),
+                25
+            );
         }
+        // The failing endpoint is tried last once its error rate is too high.
+        assert_eq!(garbage_requests.load(Ordering::SeqCst), 2);
+        assert_eq!(healthy_requests.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_all_endpoints_down() {
+        let provider = provider(vec![unreachable().await, unreachable().await]);
+        assert!(provider.network_version().await.is_err());
     }
 
     #[test]
-    fn test_default_provider_per_network() {
-        let testnet = default_provider(Network::Testnet);
-        let mainnet = default_provider(Network::Mainnet);
+    fn test_preferring_keeps_fallbacks() {
+        let calibnet = NetworkConfig::calibnet();
+        let url = calibnet.rpc_endpoints[1].url.clone();
+        let provider = Provider::preferring(url.clone(), calibnet);
+        assert_eq!(provider.url(), &url);
+        assert_eq!(provider.pool.ranked().len(), calibnet.rpc_endpoints.len());
+    }
+
+    #[tokio::test]
+    async fn test_batch_results_in_call_order() {
+        let (url, requests) = serve(MockEndpoint::Body(json!([
+            { "jsonrpc": "2.0", "id": 2, "error": { "code": 1, "message": "actor not found" } },
+            { "jsonrpc": "2.0", "id": 1, "result": "1000" },
+            { "jsonrpc": "2.0", "id": 0, "result": 42 },
+        ])))
+        .await;
+        let address = Address::new_id(1234);
+        let mut batch = RpcBatch::default();
+        let nonce = batch.add(RpcCall::mpool_get_nonce(address).unwrap());
+        let balance = batch.add(RpcCall::wallet_balance(address).unwrap());
+        let id_address = batch.add(RpcCall::lookup_id(address).unwrap());
+        let mut results = provider(vec![url]).batch(&batch).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(results.take(nonce).unwrap(), 42);
+        assert_eq!(results.take(balance).unwrap(), TokenAmount::from_atto(1000));
+        let err = results.take(id_address).unwrap_err();
+        assert_eq!(err.downcast_ref::<RpcError>().unwrap().code, 1);
+    }
+
+    #[tokio::test]
+    async fn test_datacap_status() {
+        let (url, _) = serve(MockEndpoint::Body(json!([
+            { "jsonrpc": "2.0", "id": 0, "result": "t01000" },
+            { "jsonrpc": "2.0", "id": 1, "result": "t01234" },
+            { "jsonrpc": "2.0", "id": 2, "result": "4096" },
+            { "jsonrpc": "2.0", "id": 3, "result": null },
+            { "jsonrpc": "2.0", "id": 4, "result": "1024" },
+        ])))
+        .await;
+        let status = provider(vec![url])
+            .datacap_status(Address::new_id(1000), Address::new_id(1234))
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            DatacapStatus {
+                verifier_allowance: Some(StoragePower::from(4096)),
+                recipient_is_verifier_itself: false,
+                recipient_verifier_allowance: None,
+                recipient_datacap: Some(StoragePower::from(1024)),
+            }
+        );
+
+        // Recipients without an actor hold nothing yet.
+        let (url, _) = serve(MockEndpoint::Body(json!([
+            { "jsonrpc": "2.0", "id": 0, "result": "t01000" },
+            { "jsonrpc": "2.0", "id": 1, "error": { "code": 1, "message": "actor not found" } },
+            { "jsonrpc": "2.0", "id": 2, "result": "4096" },
+            { "jsonrpc": "2.0", "id": 3, "error": { "code": 1, "message": "actor not found" } },
+            { "jsonrpc": "2.0", "id": 4, "error": { "code": 1, "message": "actor not found" } },
+        ])))
+        .await;
+        let status = provider(vec![url])
+            .datacap_status(Address::new_id(1000), Address::new_id(1234))
+            .await
+            .unwrap();
+        assert_eq!(status.verifier_allowance, Some(StoragePower::from(4096)));
+        assert_eq!(status.recipient_datacap, None);
+    }
+
+    #[tokio::test]
+    async fn test_actor_info_without_actor() {
+        let (url, requests) = serve(MockEndpoint::Body(json!([
+            { "jsonrpc": "2.0", "id": 0, "error": { "code": 1, "message": "actor not found" } },
+            { "jsonrpc": "2.0", "id": 1, "error": { "code": 1, "message": "actor not found" } },
+            { "jsonrpc": "2.0", "id": 2, "error": { "code": 1, "message": "actor not found" } },
+            { "jsonrpc": "2.0", "id": 3, "result": 25 },
+        ])))
+        .await;
+        let address = Address::new_secp256k1(&[4; 65]).unwrap();
+        let info = provider(vec![url]).actor_info(address).await.unwrap();
+        assert_eq!(
+            info,
+            ActorInfo {
+                id: None,
+                robust: Some(address),
+                state: None,
+                actor_type: None,
+            }
+        );
+        // The code CIDs are only needed to resolve the type of an existing actor.
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_state_search_msg() {
+        let cid: Cid = "bafy2bzaceawvht75twtb7jbw262yi5am5oiu5jpzfxndkiyormggdyljxrr6e"
+            .parse()
+            .unwrap();
+        let (url, _) = serve(MockEndpoint::Result(json!({
+            "Message": { "/": cid.to_string() },
+            "Receipt": { "ExitCode": 16, "Return": null, "GasUsed": 1234, "EventsRoot": null },
+            "ReturnDec": null,
+            "TipSet": [{ "/": cid.to_string() }],
+            "Height": 42,
+        })))
+        .await;
+        let lookup = provider(vec![url])
+            .state_search_msg(cid, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lookup.height, 42);
+        assert_eq!(lookup.tipset.0, vec![cid]);
+        assert_eq!(lookup.receipt.exit_code, ExitCode::USR_ILLEGAL_ARGUMENT);
+        assert_eq!(lookup.receipt.gas_used, 1234);
+        assert!(lookup.receipt.return_data.is_empty());
+
+        // Messages not on chain yet are not found.
+        let (url, _) = serve(MockEndpoint::Result(Value::Null)).await;
+        let lookup = provider(vec![url])
+            .state_search_msg(cid, None)
+            .await
+            .unwrap();
+        assert!(lookup.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_immutable_results_are_cached() {
+        let (url, requests) = serve(MockEndpoint::Result(json!("t01234"))).await;
+        let provider = provider(vec![url]);
+        let address = Address::new_secp256k1(&[0; 65]).unwrap();
+
+        assert_eq!(
+            provider
+                .call(RpcCall::lookup_id(address).unwrap())
+                .await
+                .unwrap(),
+            Address::new_id(1234)
+        );
+        // Served from the cache, without sending the batch to the endpoint.
+        let mut batch = RpcBatch::default();
+        let id_address = batch.add(RpcCall::lookup_id(address).unwrap());
+        let mut results = provider.batch(&batch).await.unwrap();
+        assert_eq!(results.take(id_address).unwrap(), Address::new_id(1234));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(provider.cache_stats(), CacheStats { hits: 1, misses: 1 });
+
+        // Nonces change, so they are always requested.
+        provider.mpool_get_nonce(address).await.ok();
+        provider.mpool_get_nonce(address).await.ok();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_rejected_by_node() {
+        let (url, _) = serve(MockEndpoint::Error).await;
+        let mut batch = RpcBatch::default();
+        let _ = batch.add(RpcCall::mpool_get_nonce(Address::new_id(1234)).unwrap());
+        let Err(err) = provider(vec![url]).batch(&batch).await else {
+            panic!("expected the batch to fail");
+        };
+        assert!(err.downcast_ref::<RpcError>().is_some());
+    }
 
-        assert_eq!(testnet.url, CALIBNET_PROVIDERS[0].url.parse().unwrap());
-        assert_eq!(mainnet.url, MAINNET_PROVIDERS[0].url.parse().unwrap());
-        assert_ne!(testnet.url, mainnet.url);
+    #[tokio::test]
+    async fn test_batch_missing_result() {
+        let (url, _) = serve(MockEndpoint::Body(json!([
+            { "jsonrpc": "2.0", "id": 0, "result": 42 },
+        ])))
+        .await;
+        let address = Address::new_id(1234);
+        let mut batch = RpcBatch::default();
+        let nonce = batch.add(RpcCall::mpool_get_nonce(address).unwrap());
+        let id_address = batch.add(RpcCall::lookup_id(address).unwrap());
+        let mut results = provider(vec![url]).batch(&batch).await.unwrap();
+        assert_eq!(results.take(nonce).unwrap(), 42);
+        assert_eq!(
+            results.take(id_address).unwrap_err().to_string(),
+            "No result"
+        );
     }
 }